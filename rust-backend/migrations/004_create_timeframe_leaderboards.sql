@@ -0,0 +1,25 @@
+-- Migration: Per-timeframe leaderboards (daily, weekly, monthly, all_time)
+ALTER TABLE trader_performance
+    ADD CONSTRAINT chk_performance_timeframe
+    CHECK (timeframe IN ('daily', 'weekly', 'monthly', 'all_time'));
+
+-- Materialized view lama hanya untuk 'daily', diganti dengan satu ranking per timeframe
+DROP MATERIALIZED VIEW IF EXISTS realtime_leaderboard;
+
+CREATE MATERIALIZED VIEW realtime_leaderboard AS
+SELECT 
+    ROW_NUMBER() OVER (PARTITION BY tp.timeframe ORDER BY tp.roi DESC) as rank,
+    tp.timeframe,
+    t.wallet_address as trader_wallet,
+    tp.account_value,
+    tp.pnl,
+    tp.roi,
+    tp.volume,
+    tp.calculated_at as last_updated
+FROM traders t
+INNER JOIN trader_performance tp ON t.id = tp.trader_id
+ORDER BY tp.timeframe, tp.roi DESC;
+-- Indexes for materialized view
+CREATE INDEX IF NOT EXISTS idx_realtime_leaderboard_rank ON realtime_leaderboard(timeframe, rank);  -- ✅ Fast rank lookup per timeframe
+-- View ini masih memuat semua snapshot per trader, jadi belum unik per (timeframe, trader_wallet);
+-- unique index untuk REFRESH CONCURRENTLY baru dibuat di 005 setelah view hanya memilih snapshot terakhir
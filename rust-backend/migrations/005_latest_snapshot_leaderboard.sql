@@ -0,0 +1,38 @@
+-- Migration: Rank only the latest performance snapshot per trader and timeframe
+-- trader_performance menyimpan snapshot baru setiap kali job update jalan,
+-- jadi view harus memilih snapshot terakhir supaya satu wallet hanya muncul sekali per timeframe
+CREATE INDEX IF NOT EXISTS idx_performance_latest
+    ON trader_performance(trader_id, timeframe, calculated_at DESC);
+
+DROP MATERIALIZED VIEW IF EXISTS realtime_leaderboard;
+
+CREATE MATERIALIZED VIEW realtime_leaderboard AS
+WITH latest_performance AS (
+    SELECT DISTINCT ON (tp.trader_id, tp.timeframe)
+        tp.id,
+        tp.trader_id,
+        tp.timeframe,
+        tp.account_value,
+        tp.pnl,
+        tp.roi,
+        tp.volume,
+        tp.calculated_at
+    FROM trader_performance tp
+    ORDER BY tp.trader_id, tp.timeframe, tp.calculated_at DESC, tp.id
+)
+SELECT 
+    ROW_NUMBER() OVER (PARTITION BY lp.timeframe ORDER BY lp.roi DESC, t.wallet_address) as rank,
+    lp.timeframe,
+    lp.id as snapshot_id,
+    t.wallet_address as trader_wallet,
+    lp.account_value,
+    lp.pnl,
+    lp.roi,
+    lp.volume,
+    lp.calculated_at as last_updated
+FROM traders t
+INNER JOIN latest_performance lp ON t.id = lp.trader_id
+ORDER BY lp.timeframe, lp.roi DESC;
+-- Indexes for materialized view
+CREATE INDEX IF NOT EXISTS idx_realtime_leaderboard_rank ON realtime_leaderboard(timeframe, rank);  -- ✅ Fast rank lookup per timeframe
+CREATE UNIQUE INDEX IF NOT EXISTS idx_realtime_leaderboard_wallet ON realtime_leaderboard(timeframe, trader_wallet);  -- ✅ One row per wallet, required for REFRESH CONCURRENTLY
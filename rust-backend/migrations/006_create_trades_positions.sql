@@ -0,0 +1,37 @@
+-- Migration: Create tables for ingested trades and positions
+CREATE TABLE IF NOT EXISTS trades (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    trader_id UUID NOT NULL REFERENCES traders(id) ON DELETE CASCADE,
+    source VARCHAR(32) NOT NULL,                       -- ✅ Nama TradeSource (file, mock, ...)
+    external_id VARCHAR(128) NOT NULL,                 -- ✅ Fill id dari sumber
+    symbol VARCHAR(32) NOT NULL,
+    side VARCHAR(4) NOT NULL CHECK (side IN ('buy', 'sell')),
+    price DECIMAL(30, 18) NOT NULL,
+    quantity DECIMAL(30, 18) NOT NULL,
+    fee DECIMAL(30, 18) NOT NULL DEFAULT 0,
+    realized_pnl DECIMAL(30, 18) NOT NULL DEFAULT 0,
+    executed_at TIMESTAMPTZ NOT NULL,
+    ingested_at TIMESTAMPTZ DEFAULT NOW(),
+    UNIQUE(source, external_id)                        -- ✅ Ingest ulang tidak membuat duplikat
+);
+CREATE INDEX IF NOT EXISTS idx_trades_trader_executed ON trades(trader_id, executed_at DESC);
+
+CREATE TABLE IF NOT EXISTS positions (
+    trader_id UUID NOT NULL REFERENCES traders(id) ON DELETE CASCADE,
+    source VARCHAR(32) NOT NULL,
+    symbol VARCHAR(32) NOT NULL,
+    quantity DECIMAL(30, 18) NOT NULL,                 -- ✅ Positif = long, negatif = short
+    entry_price DECIMAL(30, 18) NOT NULL,
+    mark_price DECIMAL(30, 18) NOT NULL,
+    updated_at TIMESTAMPTZ DEFAULT NOW(),
+    PRIMARY KEY (trader_id, source, symbol)
+);
+
+-- Posisi terakhir yang sudah di-ingest per sumber dan jenis data (fills, cash_flows)
+CREATE TABLE IF NOT EXISTS ingestion_cursors (
+    source VARCHAR(32) NOT NULL,
+    kind VARCHAR(16) NOT NULL DEFAULT 'fills',
+    last_executed_at TIMESTAMPTZ NOT NULL,
+    updated_at TIMESTAMPTZ DEFAULT NOW(),
+    PRIMARY KEY (source, kind)
+);
@@ -0,0 +1,13 @@
+-- Migration: Create cash flows table (deposits & withdrawals) for ROI calculation
+CREATE TABLE IF NOT EXISTS cash_flows (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    trader_id UUID NOT NULL REFERENCES traders(id) ON DELETE CASCADE,
+    source VARCHAR(32) NOT NULL,
+    external_id VARCHAR(128) NOT NULL,
+    kind VARCHAR(10) NOT NULL CHECK (kind IN ('deposit', 'withdrawal')),
+    amount DECIMAL(30, 18) NOT NULL CHECK (amount >= 0),  -- ✅ Selalu positif, arah ditentukan kind
+    occurred_at TIMESTAMPTZ NOT NULL,
+    ingested_at TIMESTAMPTZ DEFAULT NOW(),
+    UNIQUE(source, external_id)
+);
+CREATE INDEX IF NOT EXISTS idx_cash_flows_trader ON cash_flows(trader_id, occurred_at);
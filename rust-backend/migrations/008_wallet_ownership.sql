@@ -0,0 +1,59 @@
+-- Migration: Wallet ownership (SIWE) and trader profile
+ALTER TABLE traders
+    ADD COLUMN IF NOT EXISTS display_name VARCHAR(64),
+    ADD COLUMN IF NOT EXISTS opted_out BOOLEAN NOT NULL DEFAULT FALSE,
+    ADD COLUMN IF NOT EXISTS verified_at TIMESTAMPTZ;            -- ✅ Diisi setelah SIWE berhasil
+
+-- Nonce SIWE sekali pakai
+CREATE TABLE IF NOT EXISTS siwe_nonces (
+    nonce VARCHAR(64) PRIMARY KEY,
+    created_at TIMESTAMPTZ DEFAULT NOW(),
+    expires_at TIMESTAMPTZ NOT NULL,
+    consumed_at TIMESTAMPTZ
+);
+CREATE INDEX IF NOT EXISTS idx_siwe_nonces_expires ON siwe_nonces(expires_at);
+
+-- Session pemilik wallet; hanya hash token yang disimpan
+CREATE TABLE IF NOT EXISTS wallet_sessions (
+    token_hash VARCHAR(64) PRIMARY KEY,
+    trader_id UUID NOT NULL REFERENCES traders(id) ON DELETE CASCADE,
+    created_at TIMESTAMPTZ DEFAULT NOW(),
+    expires_at TIMESTAMPTZ NOT NULL
+);
+CREATE INDEX IF NOT EXISTS idx_wallet_sessions_trader ON wallet_sessions(trader_id);
+
+-- Trader yang opt-out tidak ikut diranking; display name ikut ditampilkan
+DROP MATERIALIZED VIEW IF EXISTS realtime_leaderboard;
+
+CREATE MATERIALIZED VIEW realtime_leaderboard AS
+WITH latest_performance AS (
+    SELECT DISTINCT ON (tp.trader_id, tp.timeframe)
+        tp.id,
+        tp.trader_id,
+        tp.timeframe,
+        tp.account_value,
+        tp.pnl,
+        tp.roi,
+        tp.volume,
+        tp.calculated_at
+    FROM trader_performance tp
+    ORDER BY tp.trader_id, tp.timeframe, tp.calculated_at DESC, tp.id
+)
+SELECT 
+    ROW_NUMBER() OVER (PARTITION BY lp.timeframe ORDER BY lp.roi DESC, t.wallet_address) as rank,
+    lp.timeframe,
+    lp.id as snapshot_id,
+    t.wallet_address as trader_wallet,
+    t.display_name,
+    lp.account_value,
+    lp.pnl,
+    lp.roi,
+    lp.volume,
+    lp.calculated_at as last_updated
+FROM traders t
+INNER JOIN latest_performance lp ON t.id = lp.trader_id
+WHERE NOT t.opted_out
+ORDER BY lp.timeframe, lp.roi DESC;
+-- Indexes for materialized view
+CREATE INDEX IF NOT EXISTS idx_realtime_leaderboard_rank ON realtime_leaderboard(timeframe, rank);  -- ✅ Fast rank lookup per timeframe
+CREATE UNIQUE INDEX IF NOT EXISTS idx_realtime_leaderboard_wallet ON realtime_leaderboard(timeframe, trader_wallet);  -- ✅ One row per wallet, required for REFRESH CONCURRENTLY
@@ -0,0 +1,76 @@
+-- Migration: Multi-chain wallet addresses with (chain, wallet_address) uniqueness
+-- View bergantung pada traders.wallet_address, jadi harus di-drop sebelum tipe kolom diubah
+DROP MATERIALIZED VIEW IF EXISTS realtime_leaderboard;
+
+ALTER TABLE traders
+    ALTER COLUMN wallet_address TYPE VARCHAR(90),               -- ✅ Cukup untuk bech32m (taproot)
+    ADD COLUMN IF NOT EXISTS chain VARCHAR(10) NOT NULL DEFAULT 'evm'
+        CHECK (chain IN ('evm', 'solana', 'bitcoin'));
+
+-- Gabungkan trader EVM yang sama tapi beda huruf besar/kecil ke trader tertua
+CREATE TEMP TABLE wallet_merge AS
+SELECT 
+    id,
+    FIRST_VALUE(id) OVER (PARTITION BY LOWER(wallet_address) ORDER BY created_at, id) as keep_id
+FROM traders
+WHERE chain = 'evm';
+
+DELETE FROM wallet_merge WHERE id = keep_id;
+
+UPDATE trades SET trader_id = m.keep_id FROM wallet_merge m WHERE trades.trader_id = m.id;
+UPDATE cash_flows SET trader_id = m.keep_id FROM wallet_merge m WHERE cash_flows.trader_id = m.id;
+UPDATE wallet_sessions SET trader_id = m.keep_id FROM wallet_merge m WHERE wallet_sessions.trader_id = m.id;
+UPDATE trader_performance tp
+SET trader_id = m.keep_id
+FROM wallet_merge m
+WHERE tp.trader_id = m.id
+  AND NOT EXISTS (
+      SELECT 1 FROM trader_performance existing
+      WHERE existing.trader_id = m.keep_id
+        AND existing.timeframe = tp.timeframe
+        AND existing.calculated_at = tp.calculated_at
+  );
+-- Sisa baris (posisi, snapshot bentrok) ikut terhapus lewat ON DELETE CASCADE
+DELETE FROM traders WHERE id IN (SELECT id FROM wallet_merge);
+DROP TABLE wallet_merge;
+
+-- Alamat EVM disimpan dengan checksum EIP-55; sementara diseragamkan ke huruf kecil,
+-- lalu di-backfill ke checksum oleh aplikasi saat startup
+UPDATE traders SET wallet_address = LOWER(wallet_address) WHERE chain = 'evm';
+
+ALTER TABLE traders DROP CONSTRAINT IF EXISTS traders_wallet_address_key;
+ALTER TABLE traders ADD CONSTRAINT traders_chain_wallet_key UNIQUE (chain, wallet_address);
+
+CREATE MATERIALIZED VIEW realtime_leaderboard AS
+WITH latest_performance AS (
+    SELECT DISTINCT ON (tp.trader_id, tp.timeframe)
+        tp.id,
+        tp.trader_id,
+        tp.timeframe,
+        tp.account_value,
+        tp.pnl,
+        tp.roi,
+        tp.volume,
+        tp.calculated_at
+    FROM trader_performance tp
+    ORDER BY tp.trader_id, tp.timeframe, tp.calculated_at DESC, tp.id
+)
+SELECT 
+    ROW_NUMBER() OVER (PARTITION BY lp.timeframe ORDER BY lp.roi DESC, t.wallet_address) as rank,
+    lp.timeframe,
+    lp.id as snapshot_id,
+    t.chain,
+    t.wallet_address as trader_wallet,
+    t.display_name,
+    lp.account_value,
+    lp.pnl,
+    lp.roi,
+    lp.volume,
+    lp.calculated_at as last_updated
+FROM traders t
+INNER JOIN latest_performance lp ON t.id = lp.trader_id
+WHERE NOT t.opted_out
+ORDER BY lp.timeframe, lp.roi DESC;
+-- Indexes for materialized view
+CREATE INDEX IF NOT EXISTS idx_realtime_leaderboard_rank ON realtime_leaderboard(timeframe, rank);  -- ✅ Fast rank lookup per timeframe
+CREATE UNIQUE INDEX IF NOT EXISTS idx_realtime_leaderboard_wallet ON realtime_leaderboard(timeframe, chain, trader_wallet);  -- ✅ One row per wallet, required for REFRESH CONCURRENTLY
@@ -0,0 +1,13 @@
+-- Migration: Leaderboard version counter and keyset pagination indexes
+-- Versi naik setiap kali realtime_leaderboard di-refresh; cursor pagination terikat ke versi ini
+CREATE TABLE IF NOT EXISTS leaderboard_state (
+    id BOOLEAN PRIMARY KEY DEFAULT TRUE CHECK (id),   -- ✅ Hanya satu baris
+    version BIGINT NOT NULL DEFAULT 0,
+    refreshed_at TIMESTAMPTZ DEFAULT NOW()
+);
+INSERT INTO leaderboard_state (id, version) VALUES (TRUE, 0) ON CONFLICT DO NOTHING;
+
+-- Keyset pagination: (timeframe, nilai sort, wallet)
+CREATE INDEX IF NOT EXISTS idx_realtime_leaderboard_roi_keyset ON realtime_leaderboard(timeframe, roi, trader_wallet);
+CREATE INDEX IF NOT EXISTS idx_realtime_leaderboard_pnl_keyset ON realtime_leaderboard(timeframe, pnl, trader_wallet);
+CREATE INDEX IF NOT EXISTS idx_realtime_leaderboard_volume_keyset ON realtime_leaderboard(timeframe, volume, trader_wallet);
@@ -0,0 +1,62 @@
+-- Migration: Immutable leaderboard snapshots for point-in-time queries
+-- id sama dengan leaderboard_state.version saat refresh
+CREATE TABLE IF NOT EXISTS leaderboard_snapshots (
+    id BIGINT PRIMARY KEY,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    entry_count INTEGER NOT NULL DEFAULT 0
+);
+CREATE INDEX IF NOT EXISTS idx_leaderboard_snapshots_created ON leaderboard_snapshots(created_at DESC);
+
+CREATE TABLE IF NOT EXISTS leaderboard_snapshot_entries (
+    snapshot_id BIGINT NOT NULL REFERENCES leaderboard_snapshots(id) ON DELETE CASCADE,
+    timeframe VARCHAR(10) NOT NULL,
+    rank INTEGER NOT NULL,
+    chain VARCHAR(10) NOT NULL,
+    trader_wallet VARCHAR(90) NOT NULL,
+    display_name VARCHAR(64),
+    account_value DECIMAL(30, 18) NOT NULL,
+    pnl DECIMAL(30, 18) NOT NULL,
+    roi DECIMAL(10, 4) NOT NULL,
+    volume DECIMAL(30, 18) NOT NULL,
+    last_updated TIMESTAMPTZ,
+    PRIMARY KEY (snapshot_id, timeframe, rank)
+);
+CREATE INDEX IF NOT EXISTS idx_snapshot_entries_wallet ON leaderboard_snapshot_entries(trader_wallet, snapshot_id);
+
+-- Snapshot tidak boleh diubah setelah ditulis (audit payout kompetisi)
+CREATE OR REPLACE FUNCTION reject_snapshot_update() RETURNS TRIGGER AS $$
+BEGIN
+    RAISE EXCEPTION 'leaderboard snapshots are immutable';
+END;
+$$ LANGUAGE plpgsql;
+
+-- Snapshot juga tidak boleh dihapus, kecuali oleh retention di cleanup job (opt-in lewat
+-- jobs.snapshot_retention_days) yang menyalakan leaderboard.snapshot_retention di transaksinya sendiri
+CREATE OR REPLACE FUNCTION reject_snapshot_delete() RETURNS TRIGGER AS $$
+BEGIN
+    IF current_setting('leaderboard.snapshot_retention', true) = 'on' THEN
+        RETURN NULL;
+    END IF;
+    RAISE EXCEPTION 'leaderboard snapshots can only be deleted by the snapshot retention cleanup';
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS trg_leaderboard_snapshots_immutable ON leaderboard_snapshots;
+CREATE TRIGGER trg_leaderboard_snapshots_immutable
+    BEFORE UPDATE ON leaderboard_snapshots
+    FOR EACH ROW EXECUTE FUNCTION reject_snapshot_update();
+
+DROP TRIGGER IF EXISTS trg_leaderboard_snapshot_entries_immutable ON leaderboard_snapshot_entries;
+CREATE TRIGGER trg_leaderboard_snapshot_entries_immutable
+    BEFORE UPDATE ON leaderboard_snapshot_entries
+    FOR EACH ROW EXECUTE FUNCTION reject_snapshot_update();
+
+DROP TRIGGER IF EXISTS trg_leaderboard_snapshots_no_delete ON leaderboard_snapshots;
+CREATE TRIGGER trg_leaderboard_snapshots_no_delete
+    BEFORE DELETE ON leaderboard_snapshots
+    FOR EACH STATEMENT EXECUTE FUNCTION reject_snapshot_delete();
+
+DROP TRIGGER IF EXISTS trg_leaderboard_snapshot_entries_no_delete ON leaderboard_snapshot_entries;
+CREATE TRIGGER trg_leaderboard_snapshot_entries_no_delete
+    BEFORE DELETE ON leaderboard_snapshot_entries
+    FOR EACH STATEMENT EXECUTE FUNCTION reject_snapshot_delete();
@@ -0,0 +1,7 @@
+-- Migration: Rank-change tracking antar snapshot
+-- previous_rank diisi saat snapshot ditulis (rank trader di snapshot sebelumnya), NULL untuk trader baru
+ALTER TABLE leaderboard_snapshot_entries ADD COLUMN IF NOT EXISTS previous_rank INTEGER;
+
+-- Lookup per trader di satu snapshot (join dengan realtime_leaderboard dan snapshot lain)
+CREATE INDEX IF NOT EXISTS idx_snapshot_entries_trader
+    ON leaderboard_snapshot_entries(snapshot_id, timeframe, chain, trader_wallet);
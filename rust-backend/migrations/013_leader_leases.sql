@@ -0,0 +1,27 @@
+-- Migration: Lease leader election untuk background job antar replica
+-- Satu baris per job; fencing_token naik setiap kali lease berpindah ke instance lain
+CREATE TABLE IF NOT EXISTS leader_leases (
+    name VARCHAR(64) PRIMARY KEY,
+    holder VARCHAR(128) NOT NULL,
+    fencing_token BIGINT NOT NULL DEFAULT 1,
+    acquired_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    expires_at TIMESTAMPTZ NOT NULL
+);
+
+-- Fencing token untuk tulisan background job.
+-- Dipanggil tepat sebelum commit di transaksi tulis job. Baris lease dikunci sampai transaksi selesai,
+-- jadi replica lain baru bisa mengambil alih lease setelah commit, dan transaksi dari leader lama
+-- (token sudah naik atau lease kedaluwarsa) dibatalkan.
+-- clock_timestamp(), bukan NOW(): NOW() adalah waktu mulai transaksi, sedangkan fence dicek setelah
+-- REFRESH yang bisa berjalan beberapa menit; lease yang habis di tengah transaksi harus tetap ditolak.
+CREATE OR REPLACE FUNCTION check_leader_fence(lease_name TEXT, token BIGINT) RETURNS VOID AS $$
+BEGIN
+    PERFORM 1 FROM leader_leases
+    WHERE name = lease_name AND fencing_token = token AND expires_at > clock_timestamp()
+    FOR UPDATE;
+    
+    IF NOT FOUND THEN
+        RAISE EXCEPTION 'stale fencing token % for lease %', token, lease_name;
+    END IF;
+END;
+$$ LANGUAGE plpgsql;
@@ -0,0 +1,20 @@
+-- Migration: Riwayat & pengaturan job scheduler
+CREATE TABLE IF NOT EXISTS job_runs (
+    id BIGSERIAL PRIMARY KEY,
+    job_name VARCHAR(64) NOT NULL,
+    trigger VARCHAR(16) NOT NULL CHECK (trigger IN ('schedule', 'manual')),
+    status VARCHAR(16) NOT NULL CHECK (status IN ('running', 'succeeded', 'failed', 'skipped')),
+    attempts INTEGER NOT NULL DEFAULT 0,
+    instance_id VARCHAR(128) NOT NULL,
+    started_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    finished_at TIMESTAMPTZ,
+    detail TEXT
+);
+CREATE INDEX IF NOT EXISTS idx_job_runs_job ON job_runs(job_name, started_at DESC);
+
+-- Status pause berlaku untuk semua replica
+CREATE TABLE IF NOT EXISTS job_settings (
+    job_name VARCHAR(64) PRIMARY KEY,
+    paused BOOLEAN NOT NULL DEFAULT FALSE,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
@@ -0,0 +1,360 @@
+// src/auth.rs
+// API key dengan role (reader < operator < admin).
+// Key dikirim lewat header `Authorization: Bearer <key>` atau `X-API-Key: <key>`.
+use actix_web::body::EitherBody;
+use actix_web::dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform};
+use actix_web::http::header::HeaderMap;
+use actix_web::{web, HttpMessage, ResponseError};
+use async_graphql::ErrorExtensions;
+use futures_util::future::LocalBoxFuture;
+use std::future::{ready, Ready};
+use crate::error::AppError;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Role {
+    Reader,
+    Operator,
+    Admin,
+}
+
+impl Role {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "reader" => Some(Role::Reader),
+            "operator" => Some(Role::Operator),
+            "admin" => Some(Role::Admin),
+            _ => None,
+        }
+    }
+}
+
+// Identitas pemanggil yang sudah terautentikasi, disimpan di request extensions / GraphQL data
+#[derive(Debug, Clone)]
+pub struct Principal {
+    pub key_id: String,
+    pub role: Role,
+}
+
+struct ApiKey {
+    key_id: String,
+    secret: String,
+    role: Role,
+}
+
+pub struct ApiKeyStore {
+    keys: Vec<ApiKey>,
+}
+
+impl ApiKeyStore {
+    // Format: "role:key" dipisah koma, contoh API_KEYS="admin:s3cret,operator:r3fresh"
+    pub fn from_env_value(value: &str) -> Result<Self, String> {
+        let mut keys = Vec::new();
+        
+        for (index, entry) in value.split(',').map(str::trim).filter(|e| !e.is_empty()).enumerate() {
+            let (role, secret) = entry
+                .split_once(':')
+                .ok_or_else(|| format!("API key #{} must look like role:key", index + 1))?;
+            let role = Role::parse(role)
+                .ok_or_else(|| format!("API key #{} has unknown role '{}'", index + 1, role))?;
+            if secret.len() < 16 {
+                return Err(format!("API key #{} must be at least 16 characters", index + 1));
+            }
+            
+            keys.push(ApiKey {
+                key_id: format!("{:?}-{}", role, index + 1).to_lowercase(),
+                secret: secret.to_string(),
+                role,
+            });
+        }
+        
+        Ok(Self { keys })
+    }
+    
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+    
+    pub fn authenticate(&self, headers: &HeaderMap) -> Option<Principal> {
+        let presented = headers
+            .get("x-api-key")
+            .and_then(|v| v.to_str().ok())
+            .or_else(|| {
+                headers
+                    .get("authorization")
+                    .and_then(|v| v.to_str().ok())
+                    .and_then(|v| v.strip_prefix("Bearer "))
+            })?
+            .trim();
+        
+        // Bandingkan semua key tanpa early return supaya waktu respon tidak membocorkan key
+        let mut found = None;
+        for key in &self.keys {
+            if constant_time_eq(key.secret.as_bytes(), presented.as_bytes()) {
+                found = Some(Principal {
+                    key_id: key.key_id.clone(),
+                    role: key.role,
+                });
+            }
+        }
+        found
+    }
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+// Middleware actix: tolak request tanpa API key dengan role minimal `role`
+pub struct RequireRole {
+    role: Role,
+}
+
+impl RequireRole {
+    pub fn new(role: Role) -> Self {
+        Self { role }
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for RequireRole
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = actix_web::Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = actix_web::Error;
+    type Transform = RequireRoleMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+    
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(RequireRoleMiddleware {
+            service,
+            role: self.role,
+        }))
+    }
+}
+
+pub struct RequireRoleMiddleware<S> {
+    service: S,
+    role: Role,
+}
+
+impl<S, B> Service<ServiceRequest> for RequireRoleMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = actix_web::Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = actix_web::Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+    
+    forward_ready!(service);
+    
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let principal = req
+            .app_data::<web::Data<ApiKeyStore>>()
+            .and_then(|store| store.authenticate(req.headers()));
+        
+        let rejection = match principal {
+            Some(principal) if principal.role >= self.role => {
+                req.extensions_mut().insert(principal);
+                let fut = self.service.call(req);
+                return Box::pin(async move { fut.await.map(ServiceResponse::map_into_left_body) });
+            }
+            Some(principal) => {
+                log::warn!("API key {} lacks role {:?} for {}", principal.key_id, self.role, req.path());
+                AppError::Forbidden("Insufficient role".to_string()).error_response()
+            }
+            None => AppError::Unauthorized("Missing or invalid API key".to_string()).error_response(),
+        };
+        
+        Box::pin(async move { Ok(req.into_response(rejection).map_into_right_body()) })
+    }
+}
+
+// Guard async-graphql untuk mutation yang butuh role tertentu
+pub struct RoleGuard {
+    role: Role,
+}
+
+impl RoleGuard {
+    pub fn new(role: Role) -> Self {
+        Self { role }
+    }
+}
+
+#[async_trait::async_trait]
+impl async_graphql::Guard for RoleGuard {
+    async fn check(&self, ctx: &async_graphql::Context<'_>) -> async_graphql::Result<()> {
+        match ctx.data_opt::<Principal>() {
+            Some(principal) if principal.role >= self.role => Ok(()),
+            Some(_) => Err(AppError::Forbidden("Insufficient role".to_string()).extend()),
+            None => Err(AppError::Unauthorized("Missing or invalid API key".to_string()).extend()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::http::header::{HeaderName, HeaderValue};
+    use actix_web::http::StatusCode;
+    use actix_web::test::{call_service, init_service, read_body, TestRequest};
+    use actix_web::{App, HttpResponse};
+    use async_graphql::{EmptyMutation, EmptySubscription, Object, Schema};
+    
+    const ADMIN_KEY: &str = "admin-key-0123456789";
+    const READER_KEY: &str = "reader-key-0123456789";
+    
+    fn store() -> ApiKeyStore {
+        ApiKeyStore::from_env_value(&format!("admin:{}, reader:{}", ADMIN_KEY, READER_KEY)).unwrap()
+    }
+    
+    fn headers(pairs: &[(&'static str, &str)]) -> HeaderMap {
+        let mut map = HeaderMap::new();
+        for (name, value) in pairs {
+            map.insert(HeaderName::from_static(name), HeaderValue::from_str(value).unwrap());
+        }
+        map
+    }
+    
+    #[test]
+    fn role_ordering() {
+        assert!(Role::Reader < Role::Operator);
+        assert!(Role::Operator < Role::Admin);
+        assert_eq!(Role::parse(" Admin "), Some(Role::Admin));
+        assert_eq!(Role::parse("root"), None);
+    }
+    
+    #[test]
+    fn from_env_value_parses_roles() {
+        let store = ApiKeyStore::from_env_value("admin:aaaaaaaaaaaaaaaa, OPERATOR:bbbbbbbbbbbbbbbb,").unwrap();
+        assert_eq!(store.keys.len(), 2);
+        assert_eq!(store.keys[0].role, Role::Admin);
+        assert_eq!(store.keys[0].key_id, "admin-1");
+        assert_eq!(store.keys[1].role, Role::Operator);
+        assert_eq!(store.keys[1].key_id, "operator-2");
+        
+        assert!(ApiKeyStore::from_env_value("").unwrap().is_empty());
+    }
+    
+    #[test]
+    fn from_env_value_rejects_bad_entries() {
+        assert_eq!(
+            ApiKeyStore::from_env_value("admin:aaaaaaaaaaaaaaaa,bbbbbbbbbbbbbbbb").err().unwrap(),
+            "API key #2 must look like role:key"
+        );
+        assert_eq!(
+            ApiKeyStore::from_env_value("root:aaaaaaaaaaaaaaaa").err().unwrap(),
+            "API key #1 has unknown role 'root'"
+        );
+        // Minimal 16 karakter
+        assert_eq!(
+            ApiKeyStore::from_env_value("reader:aaaaaaaaaaaaaaa").err().unwrap(),
+            "API key #1 must be at least 16 characters"
+        );
+        assert!(ApiKeyStore::from_env_value("reader:aaaaaaaaaaaaaaaa").is_ok());
+    }
+    
+    #[test]
+    fn authenticate_accepts_both_headers() {
+        let store = store();
+        
+        let principal = store.authenticate(&headers(&[("x-api-key", ADMIN_KEY)])).unwrap();
+        assert_eq!(principal.role, Role::Admin);
+        
+        let bearer = format!("Bearer {}", READER_KEY);
+        let principal = store.authenticate(&headers(&[("authorization", &bearer)])).unwrap();
+        assert_eq!(principal.role, Role::Reader);
+        assert_eq!(principal.key_id, "reader-2");
+        
+        // X-API-Key didahulukan kalau keduanya ada
+        let principal = store
+            .authenticate(&headers(&[("x-api-key", READER_KEY), ("authorization", &format!("Bearer {}", ADMIN_KEY))]))
+            .unwrap();
+        assert_eq!(principal.role, Role::Reader);
+    }
+    
+    #[test]
+    fn authenticate_rejects_wrong_or_missing_key() {
+        let store = store();
+        assert!(store.authenticate(&headers(&[])).is_none());
+        assert!(store.authenticate(&headers(&[("x-api-key", "admin-key-012345678X")])).is_none());
+        assert!(store.authenticate(&headers(&[("x-api-key", "admin-key")])).is_none());
+        // Tanpa prefix "Bearer "
+        assert!(store.authenticate(&headers(&[("authorization", ADMIN_KEY)])).is_none());
+    }
+    
+    #[actix_web::test]
+    async fn require_role_middleware() {
+        let app = init_service(
+            App::new().app_data(web::Data::new(store())).service(
+                web::resource("/admin")
+                    .wrap(RequireRole::new(Role::Operator))
+                    .to(|req: actix_web::HttpRequest| async move {
+                        let role = req.extensions().get::<Principal>().map(|p| p.role);
+                        HttpResponse::Ok().body(format!("{:?}", role))
+                    }),
+            ),
+        )
+        .await;
+        
+        let response = call_service(&app, TestRequest::get().uri("/admin").to_request()).await;
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        assert_eq!(response.headers().get("www-authenticate").unwrap(), "Bearer");
+        
+        let request = TestRequest::get().uri("/admin").insert_header(("x-api-key", READER_KEY)).to_request();
+        let response = call_service(&app, request).await;
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        
+        let request = TestRequest::get()
+            .uri("/admin")
+            .insert_header(("authorization", format!("Bearer {}", ADMIN_KEY)))
+            .to_request();
+        let response = call_service(&app, request).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(read_body(response).await, "Some(Admin)");
+    }
+    
+    struct GuardedQuery;
+    
+    #[Object]
+    impl GuardedQuery {
+        #[graphql(guard = "RoleGuard::new(Role::Operator)")]
+        async fn secret(&self) -> i32 {
+            42
+        }
+    }
+    
+    async fn run_guarded(principal: Option<Principal>) -> async_graphql::Response {
+        let schema = Schema::new(GuardedQuery, EmptyMutation, EmptySubscription);
+        let mut request = async_graphql::Request::new("{ secret }");
+        if let Some(principal) = principal {
+            request = request.data(principal);
+        }
+        schema.execute(request).await
+    }
+    
+    fn error_code(response: &async_graphql::Response) -> serde_json::Value {
+        let extensions = response.errors[0].extensions.as_ref().unwrap();
+        serde_json::to_value(extensions).unwrap()["code"].clone()
+    }
+    
+    #[actix_web::test]
+    async fn role_guard() {
+        let response = run_guarded(None).await;
+        assert_eq!(error_code(&response), "UNAUTHORIZED");
+        
+        let reader = Principal { key_id: "reader-1".to_string(), role: Role::Reader };
+        let response = run_guarded(Some(reader)).await;
+        assert_eq!(error_code(&response), "FORBIDDEN");
+        
+        let admin = Principal { key_id: "admin-1".to_string(), role: Role::Admin };
+        let response = run_guarded(Some(admin)).await;
+        assert!(response.errors.is_empty(), "{:?}", response.errors);
+        assert_eq!(response.data.into_json().unwrap(), serde_json::json!({ "secret": 42 }));
+    }
+}
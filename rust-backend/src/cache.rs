@@ -1,23 +1,244 @@
 // src/cache.rs
-use redis::{Client, AsyncCommands};
-use serde_json;
-use crate::models::LeaderboardEntry;
+use actix_web::web::Bytes;
+use redis::{AsyncCommands, Client, RedisResult};
+use redis::aio::ConnectionManager;
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::broadcast;
+use crate::circuit_breaker::CircuitBreaker;
+use crate::error::AppError;
+use crate::memory_cache::MemoryCache;
+use crate::metrics::metrics;
 
+// Dua tier: memori proses dulu, lalu Redis. Redis opsional: tanpa client (Redis dimatikan)
+// atau saat circuit terbuka, baca dianggap miss dan tulis dilewati, jadi request langsung
+// ke database tanpa menunggu Redis.
+// Error Redis dikembalikan sebagai AppError::Unavailable; pemanggil memutuskan apakah cukup di-log
+// (cache bersifat best effort) atau diteruskan ke klien.
 pub struct LeaderboardCache {
-    client: Client,
+    memory: MemoryCache,
+    client: Option<Client>,
+    // Satu koneksi multiplexed untuk semua request; dibuat saat pertama dipakai
+    connection: Mutex<Option<ConnectionManager>>,
+    // Hanya satu request yang membuat koneksi; request lain tidak menunggu dan dianggap skipped
+    connecting: AtomicBool,
+    breaker: CircuitBreaker,
+    connect_timeout: Duration,
+    command_timeout: Duration,
 }
 
 impl LeaderboardCache {
-    pub async fn get_cached_leaderboard(&self, key: &str) -> Option<Vec<LeaderboardEntry>> {
-        let mut conn = self.client.get_async_connection().await.ok()?;
-        let data: String = conn.get(key).await.ok()?;
-        serde_json::from_str(&data).ok()
+    pub fn new(client: Option<Client>) -> Self {
+        Self {
+            memory: MemoryCache::disabled(),
+            client,
+            connection: Mutex::new(None),
+            connecting: AtomicBool::new(false),
+            breaker: CircuitBreaker::new("redis", 5, Duration::from_secs(30)),
+            connect_timeout: Duration::from_secs(1),
+            command_timeout: Duration::from_millis(250),
+        }
+    }
+    
+    pub fn with_timeouts(mut self, connect: Duration, command: Duration) -> Self {
+        self.connect_timeout = connect;
+        self.command_timeout = command;
+        self
+    }
+    
+    pub fn with_circuit_breaker(mut self, breaker: CircuitBreaker) -> Self {
+        self.breaker = breaker;
+        self
+    }
+    
+    pub fn with_memory_tier(mut self, memory: MemoryCache) -> Self {
+        self.memory = memory;
+        self
+    }
+    
+    pub fn is_enabled(&self) -> bool {
+        self.client.is_some()
+    }
+    
+    pub fn circuit_state(&self) -> &'static str {
+        self.breaker.state()
     }
     
-    pub async fn cache_leaderboard(&self, key: &str, data: &[LeaderboardEntry], ttl: usize) {
-        if let Ok(mut conn) = self.client.get_async_connection().await {
-            let json = serde_json::to_string(data).unwrap();
-            let _ = conn.set_ex(key, json, ttl).await;
+    // Body JSON yang sudah di-serialize; Ok(None) = cache miss.
+    // Hit dari Redis disalin ke tier memori dengan sisa TTL-nya di Redis.
+    #[tracing::instrument(skip(self), fields(cache.hit = tracing::field::Empty, cache.tier = tracing::field::Empty), err)]
+    pub async fn get_cached_leaderboard(&self, key: &str) -> Result<Option<Bytes>, AppError> {
+        if let Some(body) = self.memory.get(key) {
+            metrics().observe_cache(key, "memory_hit");
+            let span = tracing::Span::current();
+            span.record("cache.hit", true);
+            span.record("cache.tier", "memory");
+            return Ok(Some(body));
         }
+        
+        let data = self
+            .execute(|mut conn| async move {
+                redis::pipe()
+                    .get(key)
+                    .pttl(key)
+                    .query_async::<_, (Option<Vec<u8>>, i64)>(&mut conn)
+                    .await
+            })
+            .await;
+        metrics().observe_cache(key, match &data {
+            Ok(Some((Some(_), _))) => "hit",
+            Ok(Some((None, _))) => "miss",
+            Ok(None) => "skipped",
+            Err(_) => "error",
+        });
+        
+        let body = match data? {
+            Some((Some(body), ttl_ms)) => {
+                let body = Bytes::from(body);
+                // pttl negatif = key tanpa TTL atau sudah hilang; tidak disalin ke memori
+                if ttl_ms > 0 {
+                    self.memory.insert(key, body.clone(), Duration::from_millis(ttl_ms as u64));
+                }
+                Some(body)
+            }
+            _ => None,
+        };
+        let span = tracing::Span::current();
+        span.record("cache.hit", body.is_some());
+        span.record("cache.tier", "redis");
+        
+        Ok(body)
     }
-}
\ No newline at end of file
+    
+    #[tracing::instrument(skip(self, body), err)]
+    pub async fn cache_leaderboard(&self, key: &str, body: Bytes, ttl_seconds: usize) -> Result<(), AppError> {
+        self.memory.insert(key, body.clone(), Duration::from_secs(ttl_seconds as u64));
+        
+        self.execute(|mut conn| async move { conn.set_ex::<_, _, ()>(key, &body[..], ttl_seconds).await }).await?;
+        Ok(())
+    }
+    
+    // Jumlah key yang dihapus
+    #[tracing::instrument(skip(self), err)]
+    pub async fn clear_pattern(&self, pattern: &str) -> Result<usize, AppError> {
+        // Tier memori hanya mengenal pola "prefix*"
+        self.memory.invalidate_prefix(pattern.trim_end_matches('*'));
+        
+        let deleted = self
+            .execute(|mut conn| async move {
+                let keys: Vec<String> = conn.keys(pattern).await?;
+                if !keys.is_empty() {
+                    conn.del::<_, ()>(&keys).await?;
+                }
+                Ok::<_, redis::RedisError>(keys.len())
+            })
+            .await?;
+        Ok(deleted.unwrap_or(0))
+    }
+    
+    // Tier memori dikosongkan setiap ada event refresh, termasuk event dari replica lain
+    // (diteruskan EventBus lewat Redis pub/sub ke `ws_tx`)
+    pub async fn run_invalidation(self: Arc<Self>, mut rx: broadcast::Receiver<String>) {
+        // Berhenti saat channel ditutup; event yang terlewat (Lagged) tetap memicu invalidasi
+        while let Ok(_) | Err(broadcast::error::RecvError::Lagged(_)) = rx.recv().await {
+            let removed = self.memory.invalidate_prefix("leaderboard:");
+            log::debug!("Invalidated {} in-memory leaderboard entries", removed);
+        }
+    }
+    
+    // Publish pub/sub lewat koneksi, timeout dan circuit breaker yang sama dengan perintah cache
+    pub async fn publish(&self, channel: &str, message: String) -> Result<(), AppError> {
+        self.execute(|mut conn| async move { conn.publish::<_, _, i64>(channel, message).await })
+            .await?
+            .ok_or_else(skipped)?;
+        Ok(())
+    }
+    
+    pub async fn ping(&self) -> Result<(), AppError> {
+        self.execute(|mut conn| async move { redis::cmd("PING").query_async::<_, String>(&mut conn).await })
+            .await?
+            .ok_or_else(skipped)?;
+        Ok(())
+    }
+    
+    // Ok(None) = Redis tidak dipanggil (dimatikan atau circuit terbuka).
+    // Setiap perintah dibatasi command_timeout; error dan timeout dihitung oleh circuit breaker.
+    async fn execute<T, F, Fut>(&self, command: F) -> Result<Option<T>, AppError>
+    where
+        F: FnOnce(ConnectionManager) -> Fut,
+        Fut: Future<Output = RedisResult<T>>,
+    {
+        if !self.is_enabled() || !self.breaker.allow() {
+            return Ok(None);
+        }
+        
+        let result = match self.connection().await {
+            Ok(Some(conn)) => match tokio::time::timeout(self.command_timeout, command(conn)).await {
+                Ok(result) => result.map_err(AppError::from),
+                Err(_) => Err(timed_out("command", self.command_timeout)),
+            },
+            // Koneksi pertama sedang dibuat request lain: lewati Redis, jangan ikut menunggu.
+            // Kalau allow() tadi memulai percobaan half-open, percobaan itu dibatalkan supaya request
+            // berikutnya yang mencoba (bukan menunggu satu cooldown lagi).
+            Ok(None) => {
+                self.breaker.cancel_probe();
+                return Ok(None);
+            }
+            Err(e) => Err(e),
+        };
+        
+        match &result {
+            Ok(_) => self.breaker.record_success(),
+            Err(_) => self.breaker.record_failure(),
+        }
+        metrics().cache_circuit_open.set(self.breaker.is_open() as i64);
+        
+        result.map(Some)
+    }
+    
+    // ConnectionManager menyambung ulang sendiri; di sini hanya koneksi pertama yang dibuat.
+    // Ok(None) kalau koneksi itu sedang dibuat oleh request lain. Lock tidak ditahan selama connect.
+    async fn connection(&self) -> Result<Option<ConnectionManager>, AppError> {
+        if let Some(conn) = self.connection.lock().unwrap().as_ref() {
+            return Ok(Some(conn.clone()));
+        }
+        if self.connecting.swap(true, Ordering::SeqCst) {
+            return Ok(None);
+        }
+        let _connecting = ConnectingGuard(&self.connecting);
+        
+        let client = self.client.clone().ok_or_else(skipped)?;
+        let conn = tokio::time::timeout(self.connect_timeout, ConnectionManager::new(client))
+            .await
+            .map_err(|_| timed_out("connect", self.connect_timeout))??;
+        log::info!("Connected to Redis");
+        
+        *self.connection.lock().unwrap() = Some(conn.clone());
+        Ok(Some(conn))
+    }
+}
+
+// Reset flag connecting walaupun connect gagal atau future-nya dibatalkan
+struct ConnectingGuard<'a>(&'a AtomicBool);
+
+impl Drop for ConnectingGuard<'_> {
+    fn drop(&mut self) {
+        self.0.store(false, Ordering::SeqCst);
+    }
+}
+
+fn skipped() -> AppError {
+    AppError::Unavailable {
+        service: "cache",
+        detail: "Redis is disabled or its circuit is open".to_string(),
+    }
+}
+
+fn timed_out(what: &str, after: Duration) -> AppError {
+    AppError::Unavailable {
+        service: "cache",
+        detail: format!("Redis {} timed out after {:?}", what, after),
+    }
+}
@@ -0,0 +1,439 @@
+// src/calculation.rs
+// Engine perhitungan PnL/ROI yang deterministik dan tidak butuh database.
+// Semua angka memakai BigDecimal supaya presisi sama dengan kolom DECIMAL(30, 18).
+use bigdecimal::{BigDecimal, Signed, Zero};
+use chrono::{DateTime, Utc};
+use std::collections::{HashMap, VecDeque};
+use crate::models::TradeSide;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CostBasisMethod {
+    Fifo,
+    Lifo,
+    AverageCost,
+}
+
+impl CostBasisMethod {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.to_lowercase().as_str() {
+            "fifo" => Some(CostBasisMethod::Fifo),
+            "lifo" => Some(CostBasisMethod::Lifo),
+            "average" | "average_cost" | "avg" => Some(CostBasisMethod::AverageCost),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Fill {
+    pub symbol: String,
+    pub side: TradeSide,
+    pub price: BigDecimal,
+    pub quantity: BigDecimal,
+    pub fee: BigDecimal,
+    pub executed_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CashFlowKind {
+    Deposit,
+    Withdrawal,
+}
+
+#[derive(Debug, Clone)]
+pub struct CashFlow {
+    pub kind: CashFlowKind,
+    pub amount: BigDecimal,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct OpenPosition {
+    pub symbol: String,
+    // Positif = long, negatif = short
+    pub quantity: BigDecimal,
+    pub average_entry_price: BigDecimal,
+    pub mark_price: BigDecimal,
+    pub unrealized_pnl: BigDecimal,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PnlReport {
+    // Realized pnl dari posisi yang ditutup di dalam window (belum dikurangi fee)
+    pub realized_pnl: BigDecimal,
+    // Unrealized pnl semua posisi terbuka terhadap mark price
+    pub unrealized_pnl: BigDecimal,
+    // Fee dari fill di dalam window
+    pub fees: BigDecimal,
+    // realized_pnl - fees + unrealized_pnl
+    pub total_pnl: BigDecimal,
+    // Notional (price * quantity) dari fill di dalam window
+    pub volume: BigDecimal,
+    // Deposit - withdrawal sampai sekarang
+    pub net_deposits: BigDecimal,
+    // net_deposits + realized pnl seumur hidup - fee seumur hidup + unrealized pnl
+    pub account_value: BigDecimal,
+    // total_pnl / net_deposits * 100, nol kalau belum ada modal yang disetor
+    pub roi: BigDecimal,
+    pub open_positions: Vec<OpenPosition>,
+}
+
+#[derive(Debug, Clone)]
+struct Lot {
+    // Bertanda sama dengan posisi (positif = long, negatif = short)
+    quantity: BigDecimal,
+    price: BigDecimal,
+}
+
+pub struct PnlCalculator {
+    method: CostBasisMethod,
+}
+
+impl PnlCalculator {
+    pub fn new(method: CostBasisMethod) -> Self {
+        Self { method }
+    }
+    
+    // Hitung pnl/roi/volume. Fill dan cash flow sebelum `window_start` tetap dipakai
+    // untuk membangun posisi dan modal, tapi realized pnl, fee dan volume hanya dihitung
+    // di dalam window. `marks` berisi harga terakhir per simbol; kalau tidak ada,
+    // harga fill terakhir simbol tersebut yang dipakai.
+    pub fn calculate(
+        &self,
+        fills: &[Fill],
+        cash_flows: &[CashFlow],
+        marks: &HashMap<String, BigDecimal>,
+        window_start: Option<DateTime<Utc>>,
+    ) -> PnlReport {
+        let in_window = |at: &DateTime<Utc>| window_start.map_or(true, |start| *at >= start);
+        
+        let mut fills: Vec<&Fill> = fills.iter().collect();
+        fills.sort_by_key(|fill| fill.executed_at);
+        
+        let mut books: HashMap<&str, VecDeque<Lot>> = HashMap::new();
+        let mut last_prices: HashMap<&str, &BigDecimal> = HashMap::new();
+        let mut realized_pnl = BigDecimal::zero();
+        let mut lifetime_realized_pnl = BigDecimal::zero();
+        let mut fees = BigDecimal::zero();
+        let mut lifetime_fees = BigDecimal::zero();
+        let mut volume = BigDecimal::zero();
+        
+        for fill in fills {
+            let book = books.entry(fill.symbol.as_str()).or_default();
+            let realized = self.apply_fill(book, fill);
+            last_prices.insert(fill.symbol.as_str(), &fill.price);
+            
+            lifetime_realized_pnl += &realized;
+            lifetime_fees += &fill.fee;
+            
+            if in_window(&fill.executed_at) {
+                realized_pnl += realized;
+                fees += &fill.fee;
+                volume += &fill.price * &fill.quantity;
+            }
+        }
+        
+        let mut unrealized_pnl = BigDecimal::zero();
+        let mut open_positions = Vec::new();
+        let mut symbols: Vec<&&str> = books.keys().collect();
+        symbols.sort();
+        
+        for symbol in symbols {
+            let lots = &books[*symbol];
+            let quantity: BigDecimal = lots.iter().map(|lot| lot.quantity.clone()).sum();
+            if quantity.is_zero() {
+                continue;
+            }
+            
+            let mark_price = marks
+                .get(*symbol)
+                .cloned()
+                .unwrap_or_else(|| last_prices[*symbol].clone());
+            let cost: BigDecimal = lots.iter().map(|lot| &lot.quantity * &lot.price).sum();
+            let position_pnl = &mark_price * &quantity - &cost;
+            
+            unrealized_pnl += &position_pnl;
+            open_positions.push(OpenPosition {
+                symbol: symbol.to_string(),
+                average_entry_price: &cost / &quantity,
+                quantity,
+                mark_price,
+                unrealized_pnl: position_pnl,
+            });
+        }
+        
+        let net_deposits: BigDecimal = cash_flows
+            .iter()
+            .map(|flow| match flow.kind {
+                CashFlowKind::Deposit => flow.amount.clone(),
+                CashFlowKind::Withdrawal => -flow.amount.clone(),
+            })
+            .sum();
+        
+        let total_pnl = &realized_pnl - &fees + &unrealized_pnl;
+        let account_value = &net_deposits + &lifetime_realized_pnl - &lifetime_fees + &unrealized_pnl;
+        let roi = if net_deposits.is_positive() {
+            &total_pnl / &net_deposits * BigDecimal::from(100)
+        } else {
+            BigDecimal::zero()
+        };
+        
+        PnlReport {
+            realized_pnl,
+            unrealized_pnl,
+            fees,
+            total_pnl,
+            volume,
+            net_deposits,
+            account_value,
+            roi,
+            open_positions,
+        }
+    }
+    
+    // Terapkan satu fill ke lot book dan kembalikan realized pnl-nya
+    fn apply_fill(&self, book: &mut VecDeque<Lot>, fill: &Fill) -> BigDecimal {
+        let mut remaining = match fill.side {
+            TradeSide::Buy => fill.quantity.clone(),
+            TradeSide::Sell => -fill.quantity.clone(),
+        };
+        let mut realized = BigDecimal::zero();
+        
+        // Tutup lot yang berlawanan arah dulu
+        while !remaining.is_zero() {
+            let lot = match self.method {
+                CostBasisMethod::Lifo => book.back_mut(),
+                CostBasisMethod::Fifo | CostBasisMethod::AverageCost => book.front_mut(),
+            };
+            let Some(lot) = lot else {
+                break;
+            };
+            if lot.quantity.is_positive() == remaining.is_positive() {
+                break;
+            }
+            
+            let closed = if lot.quantity.abs() <= remaining.abs() {
+                lot.quantity.clone()
+            } else {
+                -remaining.clone()
+            };
+            
+            // Long: (harga jual - harga lot) * qty, short: kebalikannya; `closed` sudah bertanda
+            realized += (&fill.price - &lot.price) * &closed;
+            lot.quantity -= &closed;
+            remaining += &closed;
+            
+            if lot.quantity.is_zero() {
+                match self.method {
+                    CostBasisMethod::Lifo => book.pop_back(),
+                    CostBasisMethod::Fifo | CostBasisMethod::AverageCost => book.pop_front(),
+                };
+            }
+        }
+        
+        // Sisa quantity membuka (atau menambah) posisi
+        if !remaining.is_zero() {
+            match (self.method, book.front_mut()) {
+                (CostBasisMethod::AverageCost, Some(lot)) => {
+                    let total = &lot.quantity + &remaining;
+                    lot.price = (&lot.quantity * &lot.price + &remaining * &fill.price) / &total;
+                    lot.quantity = total;
+                }
+                _ => book.push_back(Lot {
+                    quantity: remaining,
+                    price: fill.price.clone(),
+                }),
+            }
+        }
+        
+        realized
+    }
+}
+
+// Panjang window per timeframe; all_time tidak dibatasi
+pub fn timeframe_window_start(timeframe: &str, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+    match timeframe {
+        "daily" => Some(now - chrono::Duration::days(1)),
+        "weekly" => Some(now - chrono::Duration::days(7)),
+        "monthly" => Some(now - chrono::Duration::days(30)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+    use chrono::TimeZone;
+    
+    fn dec(value: &str) -> BigDecimal {
+        BigDecimal::from_str(value).unwrap()
+    }
+    
+    fn at(minute: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 1, 1, 0, minute, 0).unwrap()
+    }
+    
+    fn fill(side: TradeSide, price: &str, quantity: &str, minute: u32) -> Fill {
+        Fill {
+            symbol: "BTC".to_string(),
+            side,
+            price: dec(price),
+            quantity: dec(quantity),
+            fee: BigDecimal::zero(),
+            executed_at: at(minute),
+        }
+    }
+    
+    fn deposit(amount: &str) -> CashFlow {
+        CashFlow { kind: CashFlowKind::Deposit, amount: dec(amount) }
+    }
+    
+    fn calculate(method: CostBasisMethod, fills: &[Fill], cash_flows: &[CashFlow]) -> PnlReport {
+        PnlCalculator::new(method).calculate(fills, cash_flows, &HashMap::new(), None)
+    }
+    
+    // Dua lot dengan harga berbeda, lalu satu lot ditutup
+    fn two_lots_one_close() -> Vec<Fill> {
+        vec![
+            fill(TradeSide::Buy, "100", "1", 1),
+            fill(TradeSide::Buy, "200", "1", 2),
+            fill(TradeSide::Sell, "300", "1", 3),
+        ]
+    }
+    
+    #[test]
+    fn fifo_closes_oldest_lot_first() {
+        let report = calculate(CostBasisMethod::Fifo, &two_lots_one_close(), &[]);
+        assert_eq!(report.realized_pnl, dec("200"));
+        assert_eq!(report.open_positions[0].average_entry_price, dec("200"));
+    }
+    
+    #[test]
+    fn lifo_closes_newest_lot_first() {
+        let report = calculate(CostBasisMethod::Lifo, &two_lots_one_close(), &[]);
+        assert_eq!(report.realized_pnl, dec("100"));
+        assert_eq!(report.open_positions[0].average_entry_price, dec("100"));
+    }
+    
+    #[test]
+    fn average_cost_closes_at_blended_price() {
+        let report = calculate(CostBasisMethod::AverageCost, &two_lots_one_close(), &[]);
+        assert_eq!(report.realized_pnl, dec("150"));
+        assert_eq!(report.open_positions[0].average_entry_price, dec("150"));
+    }
+    
+    #[test]
+    fn partial_close_keeps_remaining_quantity() {
+        let fills = vec![
+            fill(TradeSide::Buy, "100", "2", 1),
+            fill(TradeSide::Sell, "120", "0.5", 2),
+        ];
+        let report = calculate(CostBasisMethod::Fifo, &fills, &[]);
+        
+        assert_eq!(report.realized_pnl, dec("10"));
+        assert_eq!(report.open_positions.len(), 1);
+        assert_eq!(report.open_positions[0].quantity, dec("1.5"));
+        assert_eq!(report.open_positions[0].average_entry_price, dec("100"));
+    }
+    
+    #[test]
+    fn oversized_sell_flips_long_into_short() {
+        let fills = vec![
+            fill(TradeSide::Buy, "100", "1", 1),
+            fill(TradeSide::Sell, "150", "3", 2),
+        ];
+        
+        for method in [CostBasisMethod::Fifo, CostBasisMethod::Lifo, CostBasisMethod::AverageCost] {
+            let report = calculate(method, &fills, &[]);
+            assert_eq!(report.realized_pnl, dec("50"), "{:?}", method);
+            assert_eq!(report.open_positions[0].quantity, dec("-2"), "{:?}", method);
+            assert_eq!(report.open_positions[0].average_entry_price, dec("150"), "{:?}", method);
+        }
+    }
+    
+    #[test]
+    fn closing_short_realizes_pnl_on_price_drop() {
+        let fills = vec![
+            fill(TradeSide::Sell, "150", "2", 1),
+            fill(TradeSide::Buy, "140", "2", 2),
+        ];
+        let report = calculate(CostBasisMethod::Fifo, &fills, &[]);
+        
+        assert_eq!(report.realized_pnl, dec("20"));
+        assert!(report.open_positions.is_empty());
+    }
+    
+    #[test]
+    fn unrealized_pnl_uses_mark_price() {
+        let fills = vec![fill(TradeSide::Buy, "100", "2", 1)];
+        let marks = HashMap::from([("BTC".to_string(), dec("130"))]);
+        let report = PnlCalculator::new(CostBasisMethod::Fifo).calculate(&fills, &[deposit("1000")], &marks, None);
+        
+        assert_eq!(report.realized_pnl, BigDecimal::zero());
+        assert_eq!(report.unrealized_pnl, dec("60"));
+        assert_eq!(report.open_positions[0].mark_price, dec("130"));
+        assert_eq!(report.open_positions[0].unrealized_pnl, dec("60"));
+        assert_eq!(report.account_value, dec("1060"));
+    }
+    
+    #[test]
+    fn unrealized_pnl_falls_back_to_last_fill_price() {
+        let fills = vec![
+            fill(TradeSide::Buy, "100", "1", 1),
+            fill(TradeSide::Buy, "110", "1", 2),
+        ];
+        let report = calculate(CostBasisMethod::Fifo, &fills, &[]);
+        
+        assert_eq!(report.open_positions[0].mark_price, dec("110"));
+        assert_eq!(report.unrealized_pnl, dec("10"));
+    }
+    
+    #[test]
+    fn roi_is_relative_to_net_deposits() {
+        let cash_flows = vec![
+            deposit("1000"),
+            CashFlow { kind: CashFlowKind::Withdrawal, amount: dec("200") },
+        ];
+        let report = calculate(CostBasisMethod::Fifo, &two_lots_one_close(), &cash_flows);
+        
+        // realized 200 + unrealized (300 - 200) = 300 di atas modal bersih 800
+        assert_eq!(report.net_deposits, dec("800"));
+        assert_eq!(report.total_pnl, dec("300"));
+        assert_eq!(report.roi, dec("37.5"));
+        assert_eq!(report.account_value, dec("1100"));
+    }
+    
+    #[test]
+    fn roi_is_zero_without_deposits() {
+        let report = calculate(CostBasisMethod::Fifo, &two_lots_one_close(), &[]);
+        assert_eq!(report.roi, BigDecimal::zero());
+        
+        let withdrawn = vec![CashFlow { kind: CashFlowKind::Withdrawal, amount: dec("50") }];
+        let report = calculate(CostBasisMethod::Fifo, &two_lots_one_close(), &withdrawn);
+        assert_eq!(report.roi, BigDecimal::zero());
+    }
+    
+    #[test]
+    fn fees_reduce_total_pnl() {
+        let mut fills = two_lots_one_close();
+        fills[2].fee = dec("5");
+        let report = calculate(CostBasisMethod::Fifo, &fills, &[deposit("1000")]);
+        
+        assert_eq!(report.fees, dec("5"));
+        assert_eq!(report.total_pnl, dec("295"));
+    }
+    
+    #[test]
+    fn fills_before_window_build_position_but_not_window_pnl() {
+        let report = PnlCalculator::new(CostBasisMethod::Fifo).calculate(
+            &two_lots_one_close(),
+            &[],
+            &HashMap::new(),
+            Some(at(3)),
+        );
+        
+        assert_eq!(report.realized_pnl, dec("200"));
+        assert_eq!(report.volume, dec("300"));
+        assert_eq!(report.open_positions[0].quantity, dec("1"));
+    }
+}
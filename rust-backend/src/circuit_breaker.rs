@@ -0,0 +1,199 @@
+// src/circuit_breaker.rs
+// Circuit breaker sederhana: setelah N kegagalan beruntun dependency dianggap mati dan dilewati
+// selama cooldown. Setelah cooldown satu request percobaan (half-open) boleh lewat;
+// sukses menutup circuit lagi, gagal membukanya untuk satu cooldown berikutnya.
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+pub struct CircuitBreaker {
+    name: &'static str,
+    failure_threshold: u32,
+    cooldown: Duration,
+    state: Mutex<BreakerState>,
+}
+
+#[derive(Default)]
+struct BreakerState {
+    consecutive_failures: u32,
+    opened_at: Option<Instant>,
+    // Percobaan half-open yang sedang berjalan; dianggap hilang kalau melewati cooldown
+    probe_started_at: Option<Instant>,
+}
+
+impl CircuitBreaker {
+    pub fn new(name: &'static str, failure_threshold: u32, cooldown: Duration) -> Self {
+        Self {
+            name,
+            failure_threshold: failure_threshold.max(1),
+            cooldown,
+            state: Mutex::new(BreakerState::default()),
+        }
+    }
+    
+    // false = jangan panggil dependency sekarang
+    pub fn allow(&self) -> bool {
+        let mut state = self.state.lock().unwrap();
+        
+        match state.opened_at {
+            None => true,
+            Some(opened_at) if opened_at.elapsed() >= self.cooldown => {
+                let probing = state
+                    .probe_started_at
+                    .is_some_and(|started| started.elapsed() < self.cooldown);
+                if probing {
+                    return false;
+                }
+                state.probe_started_at = Some(Instant::now());
+                true
+            }
+            Some(_) => false,
+        }
+    }
+    
+    pub fn record_success(&self) {
+        let mut state = self.state.lock().unwrap();
+        if state.opened_at.is_some() {
+            log::info!("{} circuit closed, dependency recovered", self.name);
+        }
+        *state = BreakerState::default();
+    }
+    
+    pub fn record_failure(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.consecutive_failures += 1;
+        state.probe_started_at = None;
+        
+        if state.opened_at.is_some() {
+            // Percobaan half-open gagal: tunggu satu cooldown lagi
+            state.opened_at = Some(Instant::now());
+        } else if state.consecutive_failures >= self.failure_threshold {
+            log::warn!(
+                "{} circuit opened after {} consecutive failures, skipping for {:?}",
+                self.name, state.consecutive_failures, self.cooldown
+            );
+            state.opened_at = Some(Instant::now());
+        }
+    }
+    
+    // Percobaan yang diizinkan allow() tapi batal tanpa memanggil dependency: request berikutnya boleh mencoba
+    pub fn cancel_probe(&self) {
+        self.state.lock().unwrap().probe_started_at = None;
+    }
+    
+    pub fn is_open(&self) -> bool {
+        self.state.lock().unwrap().opened_at.is_some()
+    }
+    
+    // "closed", "open" atau "half_open" (cooldown lewat, menunggu percobaan berikutnya)
+    pub fn state(&self) -> &'static str {
+        let state = self.state.lock().unwrap();
+        match state.opened_at {
+            None => "closed",
+            Some(opened_at) if opened_at.elapsed() >= self.cooldown => "half_open",
+            Some(_) => "open",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread::sleep;
+    
+    const COOLDOWN: Duration = Duration::from_millis(50);
+    
+    fn tripped(threshold: u32) -> CircuitBreaker {
+        let breaker = CircuitBreaker::new("test", threshold, COOLDOWN);
+        for _ in 0..threshold {
+            assert!(breaker.allow());
+            breaker.record_failure();
+        }
+        breaker
+    }
+    
+    #[test]
+    fn opens_after_threshold_consecutive_failures() {
+        let breaker = CircuitBreaker::new("test", 3, COOLDOWN);
+        breaker.record_failure();
+        breaker.record_failure();
+        assert_eq!(breaker.state(), "closed");
+        assert!(breaker.allow());
+        
+        breaker.record_failure();
+        assert_eq!(breaker.state(), "open");
+        assert!(breaker.is_open());
+        assert!(!breaker.allow());
+    }
+    
+    #[test]
+    fn success_resets_failure_count() {
+        let breaker = CircuitBreaker::new("test", 2, COOLDOWN);
+        breaker.record_failure();
+        breaker.record_success();
+        breaker.record_failure();
+        assert_eq!(breaker.state(), "closed");
+    }
+    
+    #[test]
+    fn half_open_allows_a_single_probe() {
+        let breaker = tripped(2);
+        sleep(COOLDOWN);
+        assert_eq!(breaker.state(), "half_open");
+        
+        assert!(breaker.allow());
+        // Percobaan masih berjalan: request lain tetap dilewati
+        assert!(!breaker.allow());
+        assert!(!breaker.allow());
+    }
+    
+    #[test]
+    fn successful_probe_closes() {
+        let breaker = tripped(2);
+        sleep(COOLDOWN);
+        assert!(breaker.allow());
+        breaker.record_success();
+        
+        assert_eq!(breaker.state(), "closed");
+        assert!(!breaker.is_open());
+        assert!(breaker.allow());
+        assert!(breaker.allow());
+    }
+    
+    #[test]
+    fn failed_probe_reopens_for_another_cooldown() {
+        let breaker = tripped(2);
+        sleep(COOLDOWN);
+        assert!(breaker.allow());
+        breaker.record_failure();
+        
+        assert_eq!(breaker.state(), "open");
+        assert!(!breaker.allow());
+        sleep(COOLDOWN);
+        assert_eq!(breaker.state(), "half_open");
+        assert!(breaker.allow());
+    }
+    
+    #[test]
+    fn cancelled_probe_lets_next_request_probe() {
+        let breaker = tripped(1);
+        sleep(COOLDOWN);
+        assert!(breaker.allow());
+        breaker.cancel_probe();
+        
+        assert_eq!(breaker.state(), "half_open");
+        assert!(breaker.allow());
+        assert!(!breaker.allow());
+    }
+    
+    #[test]
+    fn lost_probe_expires_after_cooldown() {
+        let breaker = tripped(1);
+        sleep(COOLDOWN);
+        assert!(breaker.allow());
+        assert!(!breaker.allow());
+        
+        // Hasil percobaan tidak pernah dicatat (future dibatalkan): setelah satu cooldown dicoba lagi
+        sleep(COOLDOWN);
+        assert!(breaker.allow());
+    }
+}
@@ -0,0 +1,304 @@
+// src/error.rs
+// Error aplikasi dengan kode stabil. Handler REST mengembalikannya lewat `ResponseError`
+// (problem details JSON), resolver GraphQL lewat `ErrorExtensions` (extensions.code).
+// Detail internal (SQL, Redis) hanya ditulis ke log, tidak pernah dikirim ke klien.
+use actix_web::http::StatusCode;
+use actix_web::{HttpResponse, ResponseError};
+use async_graphql::ErrorExtensions;
+use std::fmt;
+use crate::models::WalletAddressError;
+use crate::pagination::PaginationError;
+use crate::scheduler::SchedulerError;
+use crate::siwe::SiweError;
+
+#[derive(Debug)]
+pub enum AppError {
+    NotFound(String),
+    InvalidInput { code: &'static str, message: String },
+    Unauthorized(String),
+    Forbidden(String),
+    Conflict { code: &'static str, message: String },
+    Gone { code: &'static str, message: String },
+    // Dependency (database, Redis) tidak bisa dihubungi; aman untuk dicoba lagi
+    Unavailable { service: &'static str, detail: String },
+    // Belum ada pembatas rate; kodenya sudah disiapkan supaya klien bisa menanganinya
+    #[allow(dead_code)]
+    RateLimited { retry_after_secs: u64 },
+    Internal(String),
+}
+
+impl AppError {
+    pub fn invalid(message: impl Into<String>) -> Self {
+        AppError::InvalidInput {
+            code: "INVALID_INPUT",
+            message: message.into(),
+        }
+    }
+    
+    pub fn code(&self) -> &'static str {
+        match self {
+            AppError::NotFound(_) => "NOT_FOUND",
+            AppError::InvalidInput { code, .. } => code,
+            AppError::Unauthorized(_) => "UNAUTHORIZED",
+            AppError::Forbidden(_) => "FORBIDDEN",
+            AppError::Conflict { code, .. } => code,
+            AppError::Gone { code, .. } => code,
+            AppError::Unavailable { .. } => "UNAVAILABLE",
+            AppError::RateLimited { .. } => "RATE_LIMITED",
+            AppError::Internal(_) => "INTERNAL",
+        }
+    }
+    
+    // Pesan yang boleh dilihat klien
+    pub fn public_message(&self) -> String {
+        match self {
+            AppError::NotFound(msg)
+            | AppError::InvalidInput { message: msg, .. }
+            | AppError::Unauthorized(msg)
+            | AppError::Forbidden(msg)
+            | AppError::Conflict { message: msg, .. }
+            | AppError::Gone { message: msg, .. } => msg.clone(),
+            AppError::Unavailable { service, .. } => format!("{} is temporarily unavailable", service),
+            AppError::RateLimited { retry_after_secs } => format!("too many requests, retry in {}s", retry_after_secs),
+            AppError::Internal(_) => "internal server error".to_string(),
+        }
+    }
+    
+    fn log(&self) {
+        match self {
+            AppError::Internal(detail) => log::error!("Internal error: {}", detail),
+            AppError::Unavailable { service, detail } => log::warn!("{} unavailable: {}", service, detail),
+            _ => {}
+        }
+    }
+}
+
+impl fmt::Display for AppError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AppError::Unavailable { service, detail } => write!(f, "{} unavailable: {}", service, detail),
+            AppError::Internal(detail) => write!(f, "internal error: {}", detail),
+            _ => write!(f, "{}", self.public_message()),
+        }
+    }
+}
+
+impl std::error::Error for AppError {}
+
+impl ResponseError for AppError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            AppError::NotFound(_) => StatusCode::NOT_FOUND,
+            AppError::InvalidInput { .. } => StatusCode::BAD_REQUEST,
+            AppError::Unauthorized(_) => StatusCode::UNAUTHORIZED,
+            AppError::Forbidden(_) => StatusCode::FORBIDDEN,
+            AppError::Conflict { .. } => StatusCode::CONFLICT,
+            AppError::Gone { .. } => StatusCode::GONE,
+            AppError::Unavailable { .. } => StatusCode::SERVICE_UNAVAILABLE,
+            AppError::RateLimited { .. } => StatusCode::TOO_MANY_REQUESTS,
+            AppError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+    
+    // Problem details (RFC 7807) + `code`; field `error` dipertahankan untuk klien lama
+    fn error_response(&self) -> HttpResponse {
+        self.log();
+        
+        let status = self.status_code();
+        let message = self.public_message();
+        let mut response = HttpResponse::build(status);
+        
+        match self {
+            AppError::RateLimited { retry_after_secs } => {
+                response.insert_header(("Retry-After", retry_after_secs.to_string()));
+            }
+            AppError::Unauthorized(_) => {
+                response.insert_header(("WWW-Authenticate", "Bearer"));
+            }
+            _ => {}
+        }
+        
+        response
+            .content_type("application/problem+json")
+            .json(serde_json::json!({
+                "type": "about:blank",
+                "title": status.canonical_reason().unwrap_or("Error"),
+                "status": status.as_u16(),
+                "code": self.code(),
+                "detail": message,
+                "error": message,
+            }))
+    }
+}
+
+impl ErrorExtensions for AppError {
+    fn extend(&self) -> async_graphql::Error {
+        self.log();
+        
+        async_graphql::Error::new(self.public_message()).extend_with(|_, ext| {
+            ext.set("code", self.code());
+            ext.set("status", self.status_code().as_u16());
+        })
+    }
+}
+
+impl From<sqlx::Error> for AppError {
+    fn from(e: sqlx::Error) -> Self {
+        match e {
+            sqlx::Error::PoolTimedOut | sqlx::Error::PoolClosed | sqlx::Error::Io(_) => AppError::Unavailable {
+                service: "database",
+                detail: e.to_string(),
+            },
+            // Termasuk RowNotFound: fetch_one yang tidak menemukan baris adalah bug (mis. baris leaderboard_state
+            // hilang), bukan 404. Handler yang mencari resource klien memakai fetch_optional dan NotFound eksplisit.
+            e => AppError::Internal(format!("database error: {}", e)),
+        }
+    }
+}
+
+impl From<redis::RedisError> for AppError {
+    fn from(e: redis::RedisError) -> Self {
+        AppError::Unavailable {
+            service: "cache",
+            detail: e.to_string(),
+        }
+    }
+}
+
+impl From<PaginationError> for AppError {
+    fn from(e: PaginationError) -> Self {
+        match e {
+            PaginationError::CursorExpired => AppError::Gone {
+                code: e.code(),
+                message: e.to_string(),
+            },
+            PaginationError::InvalidCursor => AppError::InvalidInput {
+                code: e.code(),
+                message: e.to_string(),
+            },
+            PaginationError::Database(e) => e.into(),
+        }
+    }
+}
+
+impl From<WalletAddressError> for AppError {
+    fn from(e: WalletAddressError) -> Self {
+        AppError::InvalidInput {
+            code: e.code(),
+            message: e.to_string(),
+        }
+    }
+}
+
+impl From<SiweError> for AppError {
+    fn from(e: SiweError) -> Self {
+        match e {
+            SiweError::Malformed(_) => AppError::InvalidInput {
+                code: "INVALID_SIWE_MESSAGE",
+                message: e.to_string(),
+            },
+            e => AppError::Unauthorized(e.to_string()),
+        }
+    }
+}
+
+impl From<SchedulerError> for AppError {
+    fn from(e: SchedulerError) -> Self {
+        match e {
+            SchedulerError::UnknownJob(_) => AppError::NotFound(e.to_string()),
+            SchedulerError::NotLeader(_) | SchedulerError::AlreadyRunning(_) => AppError::Conflict {
+                code: e.code(),
+                message: e.to_string(),
+            },
+            SchedulerError::InvalidSchedule(_) => AppError::InvalidInput {
+                code: e.code(),
+                message: e.to_string(),
+            },
+            SchedulerError::Database(e) => e.into(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::body::to_bytes;
+    
+    #[test]
+    fn status_and_code_per_variant() {
+        let cases = [
+            (AppError::NotFound("x".into()), 404, "NOT_FOUND"),
+            (AppError::invalid("x"), 400, "INVALID_INPUT"),
+            (AppError::Unauthorized("x".into()), 401, "UNAUTHORIZED"),
+            (AppError::Forbidden("x".into()), 403, "FORBIDDEN"),
+            (AppError::Conflict { code: "NOT_LEADER", message: "x".into() }, 409, "NOT_LEADER"),
+            (AppError::Gone { code: "CURSOR_EXPIRED", message: "x".into() }, 410, "CURSOR_EXPIRED"),
+            (AppError::Unavailable { service: "cache", detail: "x".into() }, 503, "UNAVAILABLE"),
+            (AppError::RateLimited { retry_after_secs: 5 }, 429, "RATE_LIMITED"),
+            (AppError::Internal("x".into()), 500, "INTERNAL"),
+        ];
+        for (error, status, code) in cases {
+            assert_eq!(error.status_code().as_u16(), status, "{:?}", error);
+            assert_eq!(error.code(), code, "{:?}", error);
+        }
+    }
+    
+    #[test]
+    fn row_not_found_is_internal_not_404() {
+        let error = AppError::from(sqlx::Error::RowNotFound);
+        assert_eq!(error.status_code(), StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(error.code(), "INTERNAL");
+    }
+    
+    #[test]
+    fn pool_timeout_is_unavailable() {
+        let error = AppError::from(sqlx::Error::PoolTimedOut);
+        assert_eq!(error.status_code(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(error.public_message(), "database is temporarily unavailable");
+    }
+    
+    #[actix_web::test]
+    async fn problem_details_body() {
+        let response = AppError::invalid("limit must be positive").error_response();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(response.headers().get("content-type").unwrap(), "application/problem+json");
+        
+        let body = to_bytes(response.into_body()).await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body, serde_json::json!({
+            "type": "about:blank",
+            "title": "Bad Request",
+            "status": 400,
+            "code": "INVALID_INPUT",
+            "detail": "limit must be positive",
+            "error": "limit must be positive",
+        }));
+    }
+    
+    #[actix_web::test]
+    async fn internal_detail_is_not_sent_to_client() {
+        let response = AppError::Internal("relation \"secret_table\" does not exist".into()).error_response();
+        let body = to_bytes(response.into_body()).await.unwrap();
+        let body = String::from_utf8(body.to_vec()).unwrap();
+        assert!(body.contains("internal server error"));
+        assert!(!body.contains("secret_table"));
+    }
+    
+    #[actix_web::test]
+    async fn unauthorized_and_rate_limited_set_headers() {
+        let response = AppError::Unauthorized("missing API key".into()).error_response();
+        assert_eq!(response.headers().get("www-authenticate").unwrap(), "Bearer");
+        
+        let response = AppError::RateLimited { retry_after_secs: 7 }.error_response();
+        assert_eq!(response.headers().get("retry-after").unwrap(), "7");
+    }
+    
+    #[test]
+    fn graphql_extensions_carry_code_and_status() {
+        let error = AppError::NotFound("trader not found".into()).extend();
+        assert_eq!(error.message, "trader not found");
+        let extensions = serde_json::to_value(error.extensions.unwrap()).unwrap();
+        assert_eq!(extensions["code"], "NOT_FOUND");
+        assert_eq!(extensions["status"], 404);
+    }
+}
@@ -0,0 +1,228 @@
+// src/event_bus.rs
+use futures_util::StreamExt;
+use redis::Client;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::broadcast;
+use crate::cache::LeaderboardCache;
+
+// Event leaderboard dikirim ke semua instance lewat Redis pub/sub.
+// Instance pengirim langsung meneruskan ke `ws_tx` lokal; salinan dari Redis yang berasal dari
+// dirinya sendiri diabaikan, jadi tidak ada event ganda dan refresh tetap jalan walau Redis mati.
+// Tanpa client (Redis dimatikan) event hanya diteruskan ke instance ini.
+// Publish memakai koneksi & circuit breaker LeaderboardCache; client hanya dipakai untuk koneksi subscribe.
+#[derive(Serialize, Deserialize)]
+struct Envelope {
+    origin: String,
+    payload: String,
+}
+
+pub struct EventBus {
+    client: Option<Client>,
+    cache: Arc<LeaderboardCache>,
+    channel: String,
+    instance_id: String,
+    local_tx: broadcast::Sender<String>,
+}
+
+impl EventBus {
+    pub fn new(
+        client: Option<Client>,
+        cache: Arc<LeaderboardCache>,
+        channel: String,
+        instance_id: String,
+        local_tx: broadcast::Sender<String>,
+    ) -> Self {
+        Self {
+            client,
+            cache,
+            channel,
+            instance_id,
+            local_tx,
+        }
+    }
+    
+    pub fn instance_id(&self) -> &str {
+        &self.instance_id
+    }
+    
+    pub fn is_distributed(&self) -> bool {
+        self.client.is_some()
+    }
+    
+    pub async fn publish(&self, payload: String) {
+        let envelope = match serde_json::to_string(&Envelope {
+            origin: self.instance_id.clone(),
+            payload: payload.clone(),
+        }) {
+            Ok(envelope) => envelope,
+            Err(e) => {
+                log::error!("Failed to serialize event envelope: {:?}", e);
+                return;
+            }
+        };
+        
+        let _ = self.local_tx.send(payload);
+        
+        if !self.is_distributed() {
+            return;
+        }
+        // Gagal atau circuit terbuka: replica lain menyusul lewat patch di refresh berikutnya
+        if let Err(e) = self.cache.publish(&self.channel, envelope).await {
+            log::error!("Failed to publish leaderboard event: {}", e);
+        }
+    }
+    
+    // Teruskan event dari instance lain ke `ws_tx` lokal. Koneksi yang putus dibuka ulang dengan backoff;
+    // event yang terlewat selama putus tertutup oleh patch berikutnya (dihitung dari seq masing-masing klien).
+    pub async fn run_relay(&self) {
+        if self.client.is_none() {
+            return;
+        }
+        let mut backoff = Duration::from_secs(1);
+        
+        loop {
+            match self.relay_once().await {
+                Ok(()) => {
+                    log::warn!("Redis event subscription ended, reconnecting");
+                    backoff = Duration::from_secs(1);
+                }
+                Err(e) => {
+                    log::error!("Redis event subscription failed: {:?}", e);
+                }
+            }
+            
+            tokio::time::sleep(backoff).await;
+            backoff = (backoff * 2).min(Duration::from_secs(30));
+        }
+    }
+    
+    async fn relay_once(&self) -> Result<(), redis::RedisError> {
+        let Some(client) = &self.client else {
+            return Ok(());
+        };
+        let mut pubsub = client.get_async_connection().await?.into_pubsub();
+        pubsub.subscribe(&self.channel).await?;
+        log::info!("Subscribed to Redis channel {}", self.channel);
+        
+        let mut messages = pubsub.on_message();
+        while let Some(message) = messages.next().await {
+            let raw: String = match message.get_payload() {
+                Ok(raw) => raw,
+                Err(e) => {
+                    log::warn!("Ignoring non-text Redis message: {:?}", e);
+                    continue;
+                }
+            };
+            
+            self.relay_envelope(&raw);
+        }
+        
+        Ok(())
+    }
+    
+    // Envelope dari Redis diteruskan ke `ws_tx` lokal, kecuali yang dikirim instance ini sendiri
+    // (sudah diteruskan langsung saat publish)
+    fn relay_envelope(&self, raw: &str) {
+        match serde_json::from_str::<Envelope>(raw) {
+            Ok(envelope) if envelope.origin == self.instance_id => {}
+            Ok(envelope) => {
+                let _ = self.local_tx.send(envelope.payload);
+            }
+            Err(e) => log::warn!("Ignoring malformed leaderboard event: {:?}", e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::sync::broadcast::error::TryRecvError;
+    
+    fn bus(client: Option<Client>, instance_id: &str) -> (Arc<EventBus>, broadcast::Receiver<String>) {
+        let (tx, rx) = broadcast::channel(16);
+        let cache = Arc::new(LeaderboardCache::new(client.clone()));
+        let bus = EventBus::new(client, cache, "leaderboard:events:test".to_string(), instance_id.to_string(), tx);
+        (Arc::new(bus), rx)
+    }
+    
+    fn envelope(origin: &str, payload: &str) -> String {
+        serde_json::to_string(&Envelope {
+            origin: origin.to_string(),
+            payload: payload.to_string(),
+        })
+        .unwrap()
+    }
+    
+    #[test]
+    fn relay_skips_own_events() {
+        let (bus, mut rx) = bus(None, "replica-a");
+        
+        bus.relay_envelope(&envelope("replica-a", "own"));
+        assert_eq!(rx.try_recv(), Err(TryRecvError::Empty));
+        
+        bus.relay_envelope(&envelope("replica-b", "foreign"));
+        assert_eq!(rx.try_recv().unwrap(), "foreign");
+    }
+    
+    #[test]
+    fn relay_ignores_malformed_envelopes() {
+        let (bus, mut rx) = bus(None, "replica-a");
+        bus.relay_envelope("{\"payload\":\"no origin\"}");
+        bus.relay_envelope("not json");
+        assert_eq!(rx.try_recv(), Err(TryRecvError::Empty));
+    }
+    
+    #[tokio::test]
+    async fn publish_without_redis_stays_local() {
+        let (bus, mut rx) = bus(None, "replica-a");
+        assert!(!bus.is_distributed());
+        
+        bus.publish("event".to_string()).await;
+        assert_eq!(rx.try_recv().unwrap(), "event");
+        assert_eq!(rx.try_recv(), Err(TryRecvError::Empty));
+    }
+    
+    // Butuh redis-server lokal: `redis-server --port 6379` lalu `cargo test -- --ignored event_bus`.
+    // Alamat lain lewat TEST_REDIS_URL.
+    #[tokio::test]
+    #[ignore]
+    async fn events_cross_replicas_through_redis() {
+        let url = std::env::var("TEST_REDIS_URL").unwrap_or_else(|_| "redis://127.0.0.1:6379".to_string());
+        let (a, mut rx_a) = bus(Some(Client::open(url.as_str()).unwrap()), "replica-a");
+        let (b, mut rx_b) = bus(Some(Client::open(url.as_str()).unwrap()), "replica-b");
+        for relay in [a.clone(), b.clone()] {
+            tokio::spawn(async move { relay.run_relay().await });
+        }
+        
+        // Subscribe berjalan di background; publish ulang sampai replica b menerimanya
+        let mut published = 0;
+        let received = tokio::time::timeout(Duration::from_secs(5), async {
+            loop {
+                a.publish("leaderboard_updated".to_string()).await;
+                published += 1;
+                if let Ok(Ok(payload)) = tokio::time::timeout(Duration::from_millis(200), rx_b.recv()).await {
+                    return payload;
+                }
+            }
+        })
+        .await
+        .expect("replica b never received the event");
+        assert_eq!(received, "leaderboard_updated");
+        
+        // Replica a hanya menerima salinan lokal tiap publish, bukan salinan yang kembali dari Redis
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        let mut local = 0;
+        while rx_a.try_recv().is_ok() {
+            local += 1;
+        }
+        assert_eq!(local, published);
+        
+        b.publish("from_b".to_string()).await;
+        let received = tokio::time::timeout(Duration::from_secs(2), rx_a.recv()).await.unwrap().unwrap();
+        assert_eq!(received, "from_b");
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        assert_eq!(rx_a.try_recv(), Err(TryRecvError::Empty));
+    }
+}
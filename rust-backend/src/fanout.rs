@@ -0,0 +1,212 @@
+// src/fanout.rs
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex as StdMutex};
+use bytestring::ByteString;
+use tokio::sync::{broadcast, mpsc, Mutex};
+use tokio::sync::mpsc::error::TrySendError;
+use crate::metrics::metrics;
+use crate::models::RankChange;
+use crate::repository::LeaderboardRepository;
+use crate::websocket::{build_update, ClientSession, LeaderboardEvent, LeaderboardView, ServerMessage};
+
+// Pesan yang sudah diserialisasi; clone hanya menaikkan reference count
+pub type Frame = ByteString;
+
+// Kapasitas antrean per klien (default). Klien yang antreannya penuh dianggap terlalu lambat dan diputus,
+// supaya tidak menahan klien lain atau menumpuk memori.
+pub const CLIENT_QUEUE_CAPACITY: usize = 64;
+
+struct Subscriber {
+    session: Arc<Mutex<ClientSession>>,
+    tx: mpsc::Sender<Frame>,
+}
+
+// Satu publisher untuk semua koneksi WebSocket: setiap refresh, tiap kombinasi (view, seq) yang
+// sedang diikuti dihitung dan diserialisasi sekali, lalu frame yang sama dibagikan ke semua pelanggannya.
+pub struct Publisher {
+    repo: Arc<LeaderboardRepository>,
+    subscribers: StdMutex<HashMap<u64, Subscriber>>,
+    next_id: AtomicU64,
+    queue_capacity: usize,
+}
+
+impl Publisher {
+    pub fn new(repo: Arc<LeaderboardRepository>) -> Self {
+        Self {
+            repo,
+            subscribers: StdMutex::new(HashMap::new()),
+            next_id: AtomicU64::new(1),
+            queue_capacity: CLIENT_QUEUE_CAPACITY,
+        }
+    }
+    
+    pub fn with_queue_capacity(mut self, queue_capacity: usize) -> Self {
+        self.queue_capacity = queue_capacity.max(1);
+        self
+    }
+    
+    // Daftarkan koneksi; receiver ditutup kalau klien diputus karena terlalu lambat
+    pub fn register(&self, session: Arc<Mutex<ClientSession>>) -> (u64, mpsc::Receiver<Frame>) {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let (tx, rx) = mpsc::channel(self.queue_capacity);
+        let mut subscribers = self.subscribers.lock().unwrap();
+        subscribers.insert(id, Subscriber { session, tx });
+        metrics().websocket_sessions.set(subscribers.len() as i64);
+        (id, rx)
+    }
+    
+    pub fn unregister(&self, id: u64) {
+        let mut subscribers = self.subscribers.lock().unwrap();
+        subscribers.remove(&id);
+        metrics().websocket_sessions.set(subscribers.len() as i64);
+    }
+    
+    pub fn subscriber_count(&self) -> usize {
+        self.subscribers.lock().unwrap().len()
+    }
+    
+    // Kirim balasan ke satu klien lewat antrean yang sama dengan broadcast, supaya urutannya terjaga
+    pub fn send(&self, id: u64, messages: Vec<ServerMessage>) -> bool {
+        self.deliver(id, messages.iter().map(encode).collect())
+    }
+    
+    // Loop utama: satu receiver `ws_tx` untuk semua koneksi
+    pub async fn run(self: Arc<Self>, mut rx: broadcast::Receiver<String>) {
+        loop {
+            match rx.recv().await {
+                Ok(msg) => match serde_json::from_str::<LeaderboardEvent>(&msg) {
+                    Ok(LeaderboardEvent::LeaderboardUpdated { version }) => {
+                        self.publish(version).await;
+                    }
+                    Err(e) => log::warn!("Ignoring unknown leaderboard event: {:?}", e),
+                },
+                // Event yang terlewat tidak perlu diulang satu per satu: patch dari seq klien ke snapshot terbaru
+                Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                    log::warn!("WebSocket publisher lagged by {} events, resyncing", skipped);
+                    metrics().websocket_lagged_events.inc_by(skipped);
+                    match self.repo.get_latest_snapshot().await {
+                        Ok(Some(latest)) => self.publish(latest.id).await,
+                        Ok(None) => {}
+                        Err(e) => log::error!("Failed to load latest snapshot: {:?}", e),
+                    }
+                }
+                Err(broadcast::error::RecvError::Closed) => break,
+            }
+        }
+    }
+    
+    async fn publish(&self, version: i64) {
+        let subscribers: Vec<(u64, Arc<Mutex<ClientSession>>)> = self.subscribers
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(id, subscriber)| (*id, subscriber.session.clone()))
+            .collect();
+        
+        // Posisi unik yang perlu dihitung, dan wallet yang di-watch per timeframe.
+        // Session yang sedang dikunci handler (perintah subscribe yang masih query ke database) dilewati;
+        // seq-nya tidak maju, jadi klien itu menyusul lewat patch di refresh berikutnya.
+        let mut updates: HashMap<(LeaderboardView, i64), Option<Frame>> = HashMap::new();
+        let mut watched: HashMap<String, HashSet<String>> = HashMap::new();
+        for (_, session) in &subscribers {
+            let Ok(session) = session.try_lock() else {
+                continue;
+            };
+            let (view, seq) = session.position();
+            if seq >= version {
+                continue;
+            }
+            if let Some(view) = view {
+                updates.entry((view.clone(), seq)).or_insert(None);
+            }
+            let (timeframe, wallets) = session.watchlist();
+            if !wallets.is_empty() {
+                watched.entry(timeframe.to_string()).or_default().extend(wallets.iter().cloned());
+            }
+        }
+        
+        for ((view, seq), frame) in updates.iter_mut() {
+            match build_update(&self.repo, view, Some(*seq), version).await {
+                Ok(message) => *frame = Some(encode(&message)),
+                Err(e) => log::error!("Failed to build leaderboard patch: {:?}", e),
+            }
+        }
+        
+        // Perubahan rank hanya dimuat untuk wallet yang sedang di-watch, bukan seluruh leaderboard
+        let mut changes: Vec<RankChange> = Vec::new();
+        for (timeframe, wallets) in watched {
+            let wallets: Vec<String> = wallets.into_iter().collect();
+            match self.repo.get_rank_changes_for_wallets(version, &timeframe, &wallets).await {
+                Ok(found) => changes.extend(found),
+                Err(e) => log::error!("Failed to load rank changes: {:?}", e),
+            }
+        }
+        
+        let mut rank_frames: HashMap<usize, Frame> = HashMap::new();
+        
+        for (id, session) in subscribers {
+            let Ok(mut session) = session.try_lock() else {
+                continue;
+            };
+            let (view, seq) = session.position();
+            if seq >= version {
+                continue;
+            }
+            
+            let mut frames = Vec::new();
+            if let Some(view) = view {
+                match updates.get(&(view.clone(), seq)) {
+                    Some(Some(frame)) => frames.push(frame.clone()),
+                    // Gagal dihitung (atau view diganti di tengah jalan): coba lagi dari seq yang sama di refresh berikutnya
+                    _ => continue,
+                }
+            }
+            
+            for (index, change) in changes.iter().enumerate() {
+                if session.watches(change) {
+                    let frame = rank_frames.entry(index).or_insert_with(|| {
+                        encode(&ServerMessage::RankChanged { seq: version, data: change.clone() })
+                    });
+                    frames.push(frame.clone());
+                }
+            }
+            
+            session.advance(version);
+            drop(session);
+            self.deliver(id, frames);
+        }
+    }
+    
+    // Masukkan frame ke antrean klien tanpa menunggu; antrean penuh berarti klien diputus
+    fn deliver(&self, id: u64, frames: Vec<Frame>) -> bool {
+        let mut subscribers = self.subscribers.lock().unwrap();
+        let Some(subscriber) = subscribers.get(&id) else {
+            return false;
+        };
+        
+        for frame in frames {
+            match subscriber.tx.try_send(frame) {
+                Ok(()) => {}
+                Err(TrySendError::Full(_)) => {
+                    log::warn!("Dropping slow WebSocket client {}", id);
+                    metrics().websocket_slow_client_drops.inc();
+                    subscribers.remove(&id);
+                    metrics().websocket_sessions.set(subscribers.len() as i64);
+                    return false;
+                }
+                Err(TrySendError::Closed(_)) => {
+                    subscribers.remove(&id);
+                    metrics().websocket_sessions.set(subscribers.len() as i64);
+                    return false;
+                }
+            }
+        }
+        
+        true
+    }
+}
+
+fn encode(message: &ServerMessage) -> Frame {
+    ByteString::from(serde_json::json!(message).to_string())
+}
@@ -0,0 +1,203 @@
+// src/health.rs
+// Readiness: setiap dependency dicek paralel dengan timeout, hasilnya per komponen (status + latensi).
+// Database dan migration wajib "up". Redis opsional (tanpa Redis request langsung ke database),
+// dan data basi (refresh / ingestion) hanya "degraded" karena replica tetap bisa melayani data lama
+// dan replica lain juga tidak bisa memperbaikinya.
+use chrono::Utc;
+use serde::Serialize;
+use serde_json::{json, Map, Value};
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use sqlx::PgPool;
+use crate::cache::LeaderboardCache;
+use crate::config::HealthConfig;
+use crate::repository::{HealthRepository, JobRepository, LeaderboardRepository};
+
+// Komponen yang menentukan apakah replica boleh menerima traffic
+const CRITICAL: &[&str] = &["database", "migrations"];
+
+const INGEST_JOB: &str = "ingest_trades";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ComponentStatus {
+    Up,
+    Degraded,
+    Down,
+    // Dimatikan lewat konfigurasi
+    Disabled,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ComponentHealth {
+    pub status: ComponentStatus,
+    pub latency_ms: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    #[serde(flatten)]
+    pub details: Map<String, Value>,
+}
+
+#[derive(Debug)]
+pub struct Readiness {
+    pub ready: bool,
+    pub checks: BTreeMap<&'static str, ComponentHealth>,
+}
+
+type CheckResult = Result<(ComponentStatus, Map<String, Value>), String>;
+
+pub struct HealthChecker {
+    repo: HealthRepository,
+    jobs: JobRepository,
+    leaderboard_repo: Arc<LeaderboardRepository>,
+    cache: Arc<LeaderboardCache>,
+    config: HealthConfig,
+    // Versi migration yang dibawa binary ini
+    expected_migrations: Vec<i64>,
+}
+
+impl HealthChecker {
+    pub fn new(
+        pool: PgPool,
+        leaderboard_repo: Arc<LeaderboardRepository>,
+        cache: Arc<LeaderboardCache>,
+        config: HealthConfig,
+        expected_migrations: Vec<i64>,
+    ) -> Self {
+        Self {
+            repo: HealthRepository::new(pool.clone()),
+            jobs: JobRepository::new(pool),
+            leaderboard_repo,
+            cache,
+            config,
+            expected_migrations,
+        }
+    }
+    
+    pub async fn check(&self) -> Readiness {
+        let timeout = Duration::from_millis(self.config.check_timeout_ms);
+        
+        let (database, migrations, redis, refresh, ingestion) = tokio::join!(
+            timed(timeout, self.check_database()),
+            timed(timeout, self.check_migrations()),
+            timed(timeout, self.check_redis()),
+            timed(timeout, self.check_refresh()),
+            timed(timeout, self.check_ingestion()),
+        );
+        
+        let checks = BTreeMap::from([
+            ("database", database),
+            ("migrations", migrations),
+            ("redis", redis),
+            ("leaderboard_refresh", refresh),
+            ("ingestion", ingestion),
+        ]);
+        let ready = checks
+            .iter()
+            .filter(|(name, _)| CRITICAL.contains(*name))
+            .all(|(_, check)| check.status == ComponentStatus::Up);
+        
+        Readiness { ready, checks }
+    }
+    
+    async fn check_database(&self) -> CheckResult {
+        self.repo.ping().await.map_err(|e| e.to_string())?;
+        Ok((ComponentStatus::Up, Map::new()))
+    }
+    
+    // Migration yang belum dijalankan berarti schema belum cocok dengan kode ini
+    async fn check_migrations(&self) -> CheckResult {
+        let applied = self.repo.applied_migrations().await.map_err(|e| e.to_string())?;
+        let pending: Vec<i64> = self
+            .expected_migrations
+            .iter()
+            .filter(|version| !applied.contains(version))
+            .copied()
+            .collect();
+        
+        let status = if pending.is_empty() { ComponentStatus::Up } else { ComponentStatus::Down };
+        Ok((status, details(json!({
+            "applied": applied.len(),
+            "latest": applied.last(),
+            "pending": pending,
+        }))))
+    }
+    
+    async fn check_redis(&self) -> CheckResult {
+        if !self.cache.is_enabled() {
+            return Ok((ComponentStatus::Disabled, Map::new()));
+        }
+        
+        // Saat circuit terbuka ping tidak dikirim dan langsung dilaporkan down
+        self.cache
+            .ping()
+            .await
+            .map_err(|e| format!("{} (circuit {})", e, self.cache.circuit_state()))?;
+        Ok((ComponentStatus::Up, details(json!({ "circuit": self.cache.circuit_state() }))))
+    }
+    
+    // Umur snapshot terakhir = kapan materialized view terakhir berhasil di-refresh
+    async fn check_refresh(&self) -> CheckResult {
+        let snapshot = self.leaderboard_repo.get_latest_snapshot().await.map_err(|e| e.to_string())?;
+        let age_secs = snapshot.as_ref().map(|s| (Utc::now() - s.created_at).num_seconds());
+        
+        let status = match age_secs {
+            Some(age) if age <= self.config.max_refresh_age_secs => ComponentStatus::Up,
+            _ => ComponentStatus::Degraded,
+        };
+        Ok((status, details(json!({
+            "last_refresh_at": snapshot.as_ref().map(|s| s.created_at),
+            "age_secs": age_secs,
+            "max_age_secs": self.config.max_refresh_age_secs,
+        }))))
+    }
+    
+    // Lag = waktu sejak job ingestion terakhir selesai dengan sukses
+    async fn check_ingestion(&self) -> CheckResult {
+        let last_success = self.jobs.last_success(INGEST_JOB).await.map_err(|e| e.to_string())?;
+        let paused = self.jobs.is_paused(INGEST_JOB).await.map_err(|e| e.to_string())?;
+        let lag_secs = last_success.map(|at| (Utc::now() - at).num_seconds());
+        
+        let status = match lag_secs {
+            Some(lag) if lag <= self.config.max_ingestion_lag_secs => ComponentStatus::Up,
+            _ => ComponentStatus::Degraded,
+        };
+        Ok((status, details(json!({
+            "last_success_at": last_success,
+            "lag_secs": lag_secs,
+            "max_lag_secs": self.config.max_ingestion_lag_secs,
+            "paused": paused,
+        }))))
+    }
+}
+
+// Jalankan satu check dengan timeout dan catat latensinya; error/timeout = "down"
+async fn timed<F>(timeout: Duration, check: F) -> ComponentHealth
+where
+    F: Future<Output = CheckResult>,
+{
+    let started = Instant::now();
+    let result = tokio::time::timeout(timeout, check).await;
+    let latency_ms = started.elapsed().as_millis() as u64;
+    
+    let (status, error, details) = match result {
+        Ok(Ok((status, details))) => (status, None, details),
+        Ok(Err(e)) => (ComponentStatus::Down, Some(e), Map::new()),
+        Err(_) => (
+            ComponentStatus::Down,
+            Some(format!("timed out after {}ms", timeout.as_millis())),
+            Map::new(),
+        ),
+    };
+    
+    ComponentHealth { status, latency_ms, error, details }
+}
+
+fn details(value: Value) -> Map<String, Value> {
+    match value {
+        Value::Object(map) => map,
+        _ => Map::new(),
+    }
+}
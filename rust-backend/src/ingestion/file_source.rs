@@ -0,0 +1,228 @@
+// src/ingestion/file_source.rs
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use super::source::{RawCashFlow, RawFill, RawPosition, TradeSource};
+use super::IngestError;
+
+// Satu baris JSON per record, contoh:
+// {"type":"fill","fill_id":"1","wallet_address":"0xabc...","symbol":"BTC-USD","side":"buy","price":"42000","quantity":"0.5","executed_at":"2024-01-01T00:00:00Z"}
+// {"type":"cash_flow","flow_id":"d1","wallet_address":"0xabc...","kind":"deposit","amount":"10000","occurred_at":"2024-01-01T00:00:00Z"}
+// {"type":"position","wallet_address":"0xabc...","symbol":"BTC-USD","quantity":"0.5","entry_price":"42000","mark_price":"43000"}
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum FileRecord {
+    Fill(RawFill),
+    Position(RawPosition),
+    CashFlow(RawCashFlow),
+}
+
+// TradeSource yang membaca file JSON Lines lokal (export exchange, fixture, replay)
+pub struct FileTradeSource {
+    name: String,
+    path: PathBuf,
+}
+
+impl FileTradeSource {
+    pub fn new(name: impl Into<String>, path: impl Into<PathBuf>) -> Self {
+        Self {
+            name: name.into(),
+            path: path.into(),
+        }
+    }
+    
+    async fn read_records(&self) -> Result<Vec<FileRecord>, IngestError> {
+        let content = tokio::fs::read_to_string(&self.path).await?;
+        
+        content
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty())
+            .map(|(index, line)| {
+                serde_json::from_str(line).map_err(|e| {
+                    IngestError::Invalid(format!("{}:{}: {}", self.path.display(), index + 1, e))
+                })
+            })
+            .collect()
+    }
+}
+
+#[async_trait]
+impl TradeSource for FileTradeSource {
+    fn name(&self) -> &str {
+        &self.name
+    }
+    
+    async fn fetch_fills(&self, since: Option<DateTime<Utc>>) -> Result<Vec<RawFill>, IngestError> {
+        let fills = self
+            .read_records()
+            .await?
+            .into_iter()
+            .filter_map(|record| match record {
+                FileRecord::Fill(fill) => Some(fill),
+                _ => None,
+            })
+            .filter(|fill| since.map_or(true, |since| fill.executed_at >= since))
+            .collect();
+        
+        Ok(fills)
+    }
+    
+    async fn fetch_positions(&self) -> Result<Vec<RawPosition>, IngestError> {
+        // Record posisi yang muncul belakangan menimpa yang sebelumnya
+        let mut positions: HashMap<(String, String), RawPosition> = HashMap::new();
+        
+        for record in self.read_records().await? {
+            if let FileRecord::Position(position) = record {
+                positions.insert(
+                    (position.wallet_address.clone(), position.symbol.clone()),
+                    position,
+                );
+            }
+        }
+        
+        Ok(positions.into_values().collect())
+    }
+    
+    async fn fetch_cash_flows(&self, since: Option<DateTime<Utc>>) -> Result<Vec<RawCashFlow>, IngestError> {
+        let cash_flows = self
+            .read_records()
+            .await?
+            .into_iter()
+            .filter_map(|record| match record {
+                FileRecord::CashFlow(flow) => Some(flow),
+                _ => None,
+            })
+            .filter(|flow| since.map_or(true, |since| flow.occurred_at >= since))
+            .collect();
+        
+        Ok(cash_flows)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bigdecimal::BigDecimal;
+    use std::path::Path;
+    
+    const WALLET: &str = "0x1111111111111111111111111111111111111111";
+    
+    // File sementara yang dihapus lagi setelah test selesai
+    struct Fixture(PathBuf);
+    
+    impl Fixture {
+        fn new(name: &str, lines: &[String]) -> Self {
+            let path = std::env::temp_dir().join(format!("file-source-{}-{}.jsonl", name, std::process::id()));
+            std::fs::write(&path, lines.join("\n")).unwrap();
+            Self(path)
+        }
+        
+        fn path(&self) -> &Path {
+            &self.0
+        }
+    }
+    
+    impl Drop for Fixture {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_file(&self.0);
+        }
+    }
+    
+    fn fill(id: &str, executed_at: &str) -> String {
+        format!(
+            r#"{{"type":"fill","fill_id":"{}","wallet_address":"{}","symbol":"BTC-USD","side":"buy","price":"42000","quantity":"0.5","executed_at":"{}"}}"#,
+            id, WALLET, executed_at
+        )
+    }
+    
+    fn cash_flow(id: &str, occurred_at: &str) -> String {
+        format!(
+            r#"{{"type":"cash_flow","flow_id":"{}","wallet_address":"{}","kind":"deposit","amount":"10000","occurred_at":"{}"}}"#,
+            id, WALLET, occurred_at
+        )
+    }
+    
+    fn position(quantity: &str, mark_price: &str) -> String {
+        format!(
+            r#"{{"type":"position","wallet_address":"{}","symbol":"BTC-USD","quantity":"{}","entry_price":"42000","mark_price":"{}"}}"#,
+            WALLET, quantity, mark_price
+        )
+    }
+    
+    fn time(value: &str) -> DateTime<Utc> {
+        value.parse().unwrap()
+    }
+    
+    #[tokio::test]
+    async fn reads_each_record_type() {
+        let fixture = Fixture::new("records", &[
+            fill("1", "2024-01-01T00:00:00Z"),
+            cash_flow("d1", "2024-01-01T00:00:00Z"),
+            String::new(),
+            position("0.5", "43000"),
+            fill("2", "2024-01-02T00:00:00Z"),
+        ]);
+        let source = FileTradeSource::new("file", fixture.path());
+        
+        let fills = source.fetch_fills(None).await.unwrap();
+        assert_eq!(fills.iter().map(|fill| fill.fill_id.as_str()).collect::<Vec<_>>(), vec!["1", "2"]);
+        assert_eq!(source.fetch_cash_flows(None).await.unwrap().len(), 1);
+        assert_eq!(source.fetch_positions().await.unwrap().len(), 1);
+    }
+    
+    #[tokio::test]
+    async fn since_is_inclusive() {
+        let fixture = Fixture::new("since", &[
+            fill("1", "2024-01-01T00:00:00Z"),
+            fill("2", "2024-01-02T00:00:00Z"),
+            fill("3", "2024-01-02T00:00:00Z"),
+            cash_flow("d1", "2024-01-01T00:00:00Z"),
+            cash_flow("d2", "2024-01-02T00:00:00Z"),
+        ]);
+        let source = FileTradeSource::new("file", fixture.path());
+        
+        let fills = source.fetch_fills(Some(time("2024-01-02T00:00:00Z"))).await.unwrap();
+        assert_eq!(fills.iter().map(|fill| fill.fill_id.as_str()).collect::<Vec<_>>(), vec!["2", "3"]);
+        
+        let cash_flows = source.fetch_cash_flows(Some(time("2024-01-02T00:00:00Z"))).await.unwrap();
+        assert_eq!(cash_flows.len(), 1);
+        assert_eq!(cash_flows[0].flow_id, "d2");
+    }
+    
+    #[tokio::test]
+    async fn later_position_record_wins() {
+        let fixture = Fixture::new("positions", &[
+            position("0.5", "43000"),
+            position("1.5", "44000"),
+        ]);
+        let source = FileTradeSource::new("file", fixture.path());
+        
+        let positions = source.fetch_positions().await.unwrap();
+        assert_eq!(positions.len(), 1);
+        assert_eq!(positions[0].quantity, "1.5".parse::<BigDecimal>().unwrap());
+        assert_eq!(positions[0].mark_price, "44000".parse::<BigDecimal>().unwrap());
+    }
+    
+    #[tokio::test]
+    async fn invalid_line_reports_line_number() {
+        let fixture = Fixture::new("invalid", &[
+            fill("1", "2024-01-01T00:00:00Z"),
+            r#"{"type":"fill","fill_id":"2"}"#.to_string(),
+        ]);
+        let source = FileTradeSource::new("file", fixture.path());
+        
+        match source.fetch_fills(None).await {
+            Err(IngestError::Invalid(message)) => assert!(message.contains(":2:"), "{}", message),
+            other => panic!("expected invalid record error, got {:?}", other.map(|fills| fills.len())),
+        }
+    }
+    
+    #[tokio::test]
+    async fn missing_file_is_io_error() {
+        let source = FileTradeSource::new("file", std::env::temp_dir().join("file-source-does-not-exist.jsonl"));
+        assert!(matches!(source.fetch_fills(None).await, Err(IngestError::Io(_))));
+    }
+}
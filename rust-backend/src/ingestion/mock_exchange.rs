@@ -0,0 +1,329 @@
+// src/ingestion/mock_exchange.rs
+use async_trait::async_trait;
+use bigdecimal::{BigDecimal, Zero};
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use super::source::{RawCashFlow, RawFill, RawPosition, TradeSource};
+use super::IngestError;
+
+const MOCK_SYMBOLS: [(&str, i64); 2] = [("BTC-USD", 4_200_000), ("ETH-USD", 230_000)];
+// Modal awal setiap wallet simulasi
+const MOCK_INITIAL_DEPOSIT: i64 = 100_000;
+// Fill tertua dibuang setelah batas ini supaya memori tidak tumbuh terus selama proses berjalan
+const MOCK_MAX_FILLS: usize = 10_000;
+
+// Exchange in-process untuk development dan test.
+// Fill dibangkitkan secara deterministik (seeded) setiap kali `fetch_fills` dipanggil.
+pub struct MockExchange {
+    name: String,
+    state: Mutex<MockState>,
+}
+
+struct MockState {
+    fills: Vec<RawFill>,
+    positions: HashMap<(String, String), RawPosition>,
+    cash_flows: Vec<RawCashFlow>,
+    max_fills: usize,
+    simulation: Option<Simulation>,
+}
+
+struct Simulation {
+    wallets: Vec<String>,
+    rng: u64,
+    // Harga dalam sen per simbol
+    prices: Vec<i64>,
+    next_fill_id: u64,
+}
+
+impl MockExchange {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            state: Mutex::new(MockState {
+                fills: Vec::new(),
+                positions: HashMap::new(),
+                cash_flows: Vec::new(),
+                max_fills: MOCK_MAX_FILLS,
+                simulation: None,
+            }),
+        }
+    }
+    
+    // Exchange yang membangkitkan satu ronde fill untuk setiap wallet per fetch
+    pub fn simulated(name: impl Into<String>, wallets: Vec<String>, seed: u64) -> Self {
+        let exchange = Self::new(name);
+        exchange.state.lock().unwrap().simulation = Some(Simulation {
+            wallets,
+            rng: seed,
+            prices: MOCK_SYMBOLS.iter().map(|(_, price)| *price).collect(),
+            next_fill_id: 1,
+        });
+        exchange
+    }
+}
+
+impl MockState {
+    fn simulate_round(&mut self, now: DateTime<Utc>) {
+        let Some(simulation) = self.simulation.as_mut() else {
+            return;
+        };
+        
+        // Ronde pertama: setor modal awal untuk setiap wallet
+        if simulation.next_fill_id == 1 {
+            for wallet in &simulation.wallets {
+                self.cash_flows.push(RawCashFlow {
+                    flow_id: format!("sim-deposit-{}", wallet),
+                    wallet_address: wallet.clone(),
+                    kind: "deposit".to_string(),
+                    amount: BigDecimal::from(MOCK_INITIAL_DEPOSIT),
+                    occurred_at: now,
+                });
+            }
+        }
+        
+        // Random walk harga, maksimal +/- 1% per ronde
+        for index in 0..simulation.prices.len() {
+            let step = (simulation.next_random() % 201) as i64 - 100;
+            let price = simulation.prices[index];
+            simulation.prices[index] = (price + price * step / 10_000).max(1);
+        }
+        
+        let mut new_fills = Vec::new();
+        for wallet in simulation.wallets.clone() {
+            let symbol_index = (simulation.next_random() % MOCK_SYMBOLS.len() as u64) as usize;
+            let side = if simulation.next_random() % 2 == 0 { "buy" } else { "sell" };
+            // Quantity 0.01 - 1.00
+            let quantity = BigDecimal::from(simulation.next_random() % 100 + 1) / BigDecimal::from(100);
+            let price = BigDecimal::from(simulation.prices[symbol_index]) / BigDecimal::from(100);
+            
+            new_fills.push(RawFill {
+                fill_id: format!("sim-{}-{}", now.timestamp_millis(), simulation.next_fill_id),
+                wallet_address: wallet,
+                symbol: MOCK_SYMBOLS[symbol_index].0.to_string(),
+                side: side.to_string(),
+                price,
+                quantity,
+                fee: None,
+                realized_pnl: None,
+                executed_at: now,
+            });
+            simulation.next_fill_id += 1;
+        }
+        
+        for fill in &new_fills {
+            self.apply_fill(fill);
+        }
+        self.fills.extend(new_fills);
+        if self.fills.len() > self.max_fills {
+            let excess = self.fills.len() - self.max_fills;
+            self.fills.drain(..excess);
+        }
+        
+        // Update mark price semua posisi terbuka
+        let prices: HashMap<&str, BigDecimal> = MOCK_SYMBOLS
+            .iter()
+            .zip(&self.simulation.as_ref().unwrap().prices)
+            .map(|((symbol, _), price)| (*symbol, BigDecimal::from(*price) / BigDecimal::from(100)))
+            .collect();
+        for position in self.positions.values_mut() {
+            if let Some(price) = prices.get(position.symbol.as_str()) {
+                position.mark_price = price.clone();
+            }
+        }
+    }
+    
+    fn apply_fill(&mut self, fill: &RawFill) {
+        let signed_quantity = if fill.side == "buy" {
+            fill.quantity.clone()
+        } else {
+            -fill.quantity.clone()
+        };
+        
+        let position = self
+            .positions
+            .entry((fill.wallet_address.clone(), fill.symbol.clone()))
+            .or_insert_with(|| RawPosition {
+                wallet_address: fill.wallet_address.clone(),
+                symbol: fill.symbol.clone(),
+                quantity: BigDecimal::zero(),
+                entry_price: fill.price.clone(),
+                mark_price: fill.price.clone(),
+            });
+        
+        let new_quantity = &position.quantity + &signed_quantity;
+        let same_direction = position.quantity.is_zero()
+            || (position.quantity > BigDecimal::zero()) == (signed_quantity > BigDecimal::zero());
+        
+        if same_direction {
+            // Menambah posisi: entry price jadi rata-rata tertimbang
+            position.entry_price = (&position.entry_price * position.quantity.abs()
+                + &fill.price * fill.quantity.clone())
+                / new_quantity.abs();
+        } else if new_quantity.is_zero() || (new_quantity > BigDecimal::zero()) != (position.quantity > BigDecimal::zero()) {
+            // Posisi ditutup atau berbalik arah
+            position.entry_price = fill.price.clone();
+        }
+        position.quantity = new_quantity;
+        position.mark_price = fill.price.clone();
+    }
+}
+
+impl Simulation {
+    // LCG sederhana supaya hasil simulasi bisa diulang dengan seed yang sama
+    fn next_random(&mut self) -> u64 {
+        self.rng = self.rng.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+        self.rng >> 33
+    }
+}
+
+#[async_trait]
+impl TradeSource for MockExchange {
+    fn name(&self) -> &str {
+        &self.name
+    }
+    
+    async fn fetch_fills(&self, since: Option<DateTime<Utc>>) -> Result<Vec<RawFill>, IngestError> {
+        let mut state = self.state.lock().unwrap();
+        state.simulate_round(Utc::now());
+        
+        Ok(state
+            .fills
+            .iter()
+            .filter(|fill| since.map_or(true, |since| fill.executed_at >= since))
+            .cloned()
+            .collect())
+    }
+    
+    async fn fetch_positions(&self) -> Result<Vec<RawPosition>, IngestError> {
+        let state = self.state.lock().unwrap();
+        Ok(state
+            .positions
+            .values()
+            .filter(|position| !position.quantity.is_zero())
+            .cloned()
+            .collect())
+    }
+    
+    async fn fetch_cash_flows(&self, since: Option<DateTime<Utc>>) -> Result<Vec<RawCashFlow>, IngestError> {
+        let state = self.state.lock().unwrap();
+        Ok(state
+            .cash_flows
+            .iter()
+            .filter(|flow| since.map_or(true, |since| flow.occurred_at >= since))
+            .cloned()
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    
+    fn wallets() -> Vec<String> {
+        vec![
+            "0x1111111111111111111111111111111111111111".to_string(),
+            "0x2222222222222222222222222222222222222222".to_string(),
+        ]
+    }
+    
+    fn at(seconds: i64) -> DateTime<Utc> {
+        Utc.timestamp_opt(1_700_000_000 + seconds, 0).unwrap()
+    }
+    
+    fn simulate(exchange: &MockExchange, rounds: i64) {
+        let mut state = exchange.state.lock().unwrap();
+        for round in 0..rounds {
+            state.simulate_round(at(round));
+        }
+    }
+    
+    #[tokio::test]
+    async fn fetch_fills_generates_one_fill_per_wallet_per_round() {
+        let exchange = MockExchange::simulated("mock", wallets(), 7);
+        
+        let first = exchange.fetch_fills(None).await.unwrap();
+        assert_eq!(first.len(), 2);
+        let second = exchange.fetch_fills(None).await.unwrap();
+        assert_eq!(second.len(), 4);
+        
+        let mut ids: Vec<&str> = second.iter().map(|fill| fill.fill_id.as_str()).collect();
+        ids.sort();
+        ids.dedup();
+        assert_eq!(ids.len(), 4);
+    }
+    
+    #[test]
+    fn same_seed_produces_same_fills() {
+        let a = MockExchange::simulated("mock", wallets(), 42);
+        let b = MockExchange::simulated("mock", wallets(), 42);
+        simulate(&a, 5);
+        simulate(&b, 5);
+        
+        let a = a.state.lock().unwrap();
+        let b = b.state.lock().unwrap();
+        assert_eq!(a.fills.len(), b.fills.len());
+        for (left, right) in a.fills.iter().zip(&b.fills) {
+            assert_eq!(left.fill_id, right.fill_id);
+            assert_eq!(left.symbol, right.symbol);
+            assert_eq!(left.side, right.side);
+            assert_eq!(left.price, right.price);
+            assert_eq!(left.quantity, right.quantity);
+        }
+    }
+    
+    #[tokio::test]
+    async fn since_is_inclusive() {
+        let exchange = MockExchange::simulated("mock", wallets(), 1);
+        simulate(&exchange, 3);
+        
+        let fills = exchange.fetch_fills(Some(at(1))).await.unwrap();
+        // Ronde 1 dan 2 dari simulasi, ditambah ronde baru yang dibangkitkan oleh fetch ini
+        assert_eq!(fills.len(), 6);
+        assert!(fills.iter().all(|fill| fill.executed_at >= at(1)));
+        assert_eq!(fills.iter().filter(|fill| fill.executed_at == at(1)).count(), 2);
+    }
+    
+    #[tokio::test]
+    async fn first_round_deposits_initial_capital_once() {
+        let exchange = MockExchange::simulated("mock", wallets(), 3);
+        simulate(&exchange, 4);
+        
+        let cash_flows = exchange.fetch_cash_flows(None).await.unwrap();
+        assert_eq!(cash_flows.len(), 2);
+        assert!(cash_flows.iter().all(|flow| flow.kind == "deposit"));
+        assert!(cash_flows.iter().all(|flow| flow.amount == BigDecimal::from(MOCK_INITIAL_DEPOSIT)));
+        assert!(exchange.fetch_cash_flows(Some(at(1))).await.unwrap().is_empty());
+    }
+    
+    #[tokio::test]
+    async fn positions_match_net_fill_quantity() {
+        let exchange = MockExchange::simulated("mock", wallets(), 11);
+        simulate(&exchange, 20);
+        
+        let mut net: HashMap<(String, String), BigDecimal> = HashMap::new();
+        for fill in &exchange.state.lock().unwrap().fills {
+            let quantity = if fill.side == "buy" { fill.quantity.clone() } else { -fill.quantity.clone() };
+            *net.entry((fill.wallet_address.clone(), fill.symbol.clone())).or_insert_with(BigDecimal::zero) += quantity;
+        }
+        
+        let positions = exchange.fetch_positions().await.unwrap();
+        assert_eq!(positions.len(), net.values().filter(|quantity| !quantity.is_zero()).count());
+        for position in positions {
+            assert_eq!(net[&(position.wallet_address, position.symbol)], position.quantity);
+        }
+    }
+    
+    #[test]
+    fn fills_are_capped() {
+        let exchange = MockExchange::simulated("mock", wallets(), 5);
+        exchange.state.lock().unwrap().max_fills = 10;
+        simulate(&exchange, 8);
+        
+        let state = exchange.state.lock().unwrap();
+        assert_eq!(state.fills.len(), 10);
+        // Yang dibuang adalah fill tertua
+        assert!(state.fills.iter().all(|fill| fill.executed_at >= at(3)));
+    }
+}
@@ -0,0 +1,276 @@
+// src/ingestion/mod.rs
+pub mod file_source;
+pub mod mock_exchange;
+pub mod normalize;
+pub mod source;
+
+use bigdecimal::BigDecimal;
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+use uuid::Uuid;
+use crate::calculation::{timeframe_window_start, CostBasisMethod, Fill, PnlCalculator};
+use crate::leader::Fence;
+use crate::metrics::metrics;
+use crate::models::{TradeSide, WalletAddress, TIMEFRAMES};
+use crate::repository::TradeRepository;
+use self::normalize::{normalize_cash_flow, normalize_fill, normalize_position};
+use self::source::TradeSource;
+
+// Nilai kolom ingestion_cursors.kind
+const CURSOR_FILLS: &str = "fills";
+const CURSOR_CASH_FLOWS: &str = "cash_flows";
+
+#[derive(Debug)]
+pub enum IngestError {
+    Io(std::io::Error),
+    Database(sqlx::Error),
+    // Untuk source eksternal (API exchange); source bawaan (file, mock) belum memakainya
+    #[allow(dead_code)]
+    Source(String),
+    Invalid(String),
+}
+
+impl fmt::Display for IngestError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IngestError::Io(e) => write!(f, "io error: {}", e),
+            IngestError::Database(e) => write!(f, "database error: {}", e),
+            IngestError::Source(msg) => write!(f, "source error: {}", msg),
+            IngestError::Invalid(msg) => write!(f, "invalid record: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for IngestError {}
+
+impl From<std::io::Error> for IngestError {
+    fn from(e: std::io::Error) -> Self {
+        IngestError::Io(e)
+    }
+}
+
+impl From<sqlx::Error> for IngestError {
+    fn from(e: sqlx::Error) -> Self {
+        IngestError::Database(e)
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct IngestReport {
+    pub fills_received: usize,
+    pub fills_inserted: u64,
+    pub fills_rejected: usize,
+    pub cash_flows_inserted: u64,
+    pub positions_updated: usize,
+    pub failed_sources: usize,
+}
+
+#[derive(Debug, Default)]
+pub struct PerformanceReport {
+    pub rows: i64,
+    pub failed_traders: usize,
+}
+
+// Tarik fill & posisi dari semua TradeSource, normalisasi, simpan ke trades/positions,
+// lalu hitung ulang trader_performance dari data tersebut
+pub struct IngestionPipeline {
+    sources: Vec<Arc<dyn TradeSource>>,
+    trade_repo: Arc<TradeRepository>,
+    calculator: PnlCalculator,
+}
+
+impl IngestionPipeline {
+    pub fn new(trade_repo: Arc<TradeRepository>) -> Self {
+        Self {
+            sources: Vec::new(),
+            trade_repo,
+            calculator: PnlCalculator::new(CostBasisMethod::Fifo),
+        }
+    }
+    
+    pub fn with_cost_basis(mut self, method: CostBasisMethod) -> Self {
+        self.calculator = PnlCalculator::new(method);
+        self
+    }
+    
+    pub fn with_source(mut self, source: Arc<dyn TradeSource>) -> Self {
+        self.sources.push(source);
+        self
+    }
+    
+    // Tarik data dari semua sumber; performa dihitung terpisah lewat recompute_performance
+    pub async fn ingest_all(&self, fence: Fence) -> IngestReport {
+        let mut report = IngestReport::default();
+        
+        // Satu sumber gagal tidak menghentikan sumber lain
+        for source in &self.sources {
+            if let Err(e) = self.ingest_source(source.as_ref(), &mut report, fence).await {
+                log::error!("Ingestion from source {} failed: {}", source.name(), e);
+                metrics().ingest_source_failures.with_label_values(&[source.name()]).inc();
+                report.failed_sources += 1;
+            }
+        }
+        
+        report
+    }
+    
+    // Snapshot trader_performance baru untuk setiap trader aktif dan setiap timeframe.
+    // Satu trader gagal tidak menghentikan trader lain; kegagalan dicatat dan dihitung di report.
+    pub async fn recompute_performance(&self, fence: Fence) -> Result<PerformanceReport, IngestError> {
+        let marks = self.trade_repo.get_marks().await?;
+        let now = Utc::now();
+        let mut report = PerformanceReport::default();
+        
+        for trader_id in self.trade_repo.get_active_trader_ids().await? {
+            match self.recompute_trader(trader_id, &marks, now, fence).await {
+                Ok(rows) => report.rows += rows,
+                Err(e) => {
+                    log::error!("Performance recomputation for trader {} failed: {}", trader_id, e);
+                    metrics().performance_failures.inc();
+                    report.failed_traders += 1;
+                }
+            }
+        }
+        
+        metrics().performance_rows.inc_by(report.rows as u64);
+        Ok(report)
+    }
+    
+    async fn recompute_trader(
+        &self,
+        trader_id: Uuid,
+        marks: &HashMap<String, BigDecimal>,
+        now: DateTime<Utc>,
+        fence: Fence,
+    ) -> Result<i64, IngestError> {
+        let fills: Vec<Fill> = self
+            .trade_repo
+            .get_trades(trader_id)
+            .await?
+            .into_iter()
+            .filter_map(|trade| {
+                Some(Fill {
+                    side: TradeSide::parse(&trade.side)?,
+                    symbol: trade.symbol,
+                    price: trade.price,
+                    quantity: trade.quantity,
+                    fee: trade.fee,
+                    executed_at: trade.executed_at,
+                })
+            })
+            .collect();
+        let cash_flows = self.trade_repo.get_cash_flows(trader_id).await?;
+        
+        let reports: Vec<_> = TIMEFRAMES
+            .iter()
+            .map(|timeframe| {
+                let pnl = self.calculator.calculate(
+                    &fills,
+                    &cash_flows,
+                    marks,
+                    timeframe_window_start(timeframe, now),
+                );
+                (*timeframe, pnl)
+            })
+            .collect();
+        
+        Ok(self.trade_repo.insert_performance(trader_id, &reports, now, fence).await? as i64)
+    }
+    
+    async fn trader_id(
+        &self,
+        trader_ids: &mut HashMap<WalletAddress, Uuid>,
+        wallet_address: &WalletAddress,
+    ) -> Result<Uuid, IngestError> {
+        if let Some(id) = trader_ids.get(wallet_address) {
+            return Ok(*id);
+        }
+        let id = self.trade_repo.ensure_trader(wallet_address).await?;
+        trader_ids.insert(wallet_address.clone(), id);
+        Ok(id)
+    }
+    
+    async fn ingest_source(
+        &self,
+        source: &dyn TradeSource,
+        report: &mut IngestReport,
+        fence: Fence,
+    ) -> Result<(), IngestError> {
+        // Cursor inklusif (executed_at >= cursor): fill lain dengan timestamp yang sama tetapi datang belakangan
+        // tetap terambil, sedangkan fill yang sudah tersimpan di-dedup lewat (source, external_id)
+        let cursor = self.trade_repo.get_cursor(source.name(), CURSOR_FILLS).await?;
+        let raw_fills = source.fetch_fills(cursor).await?;
+        let raw_positions = source.fetch_positions().await?;
+        report.fills_received += raw_fills.len();
+        metrics().ingest_fills.with_label_values(&[source.name(), "received"]).inc_by(raw_fills.len() as u64);
+        
+        let mut trader_ids = HashMap::new();
+        let mut latest: Option<DateTime<Utc>> = cursor;
+        
+        for raw in raw_fills {
+            let fill = match normalize_fill(raw) {
+                Ok(fill) => fill,
+                Err(e) => {
+                    log::warn!("Skipping fill from {}: {}", source.name(), e);
+                    metrics().ingest_fills.with_label_values(&[source.name(), "rejected"]).inc();
+                    report.fills_rejected += 1;
+                    continue;
+                }
+            };
+            
+            let trader_id = self.trader_id(&mut trader_ids, &fill.wallet_address).await?;
+            
+            let inserted = self.trade_repo.insert_trade(source.name(), trader_id, &fill).await?;
+            metrics().ingest_fills.with_label_values(&[source.name(), "inserted"]).inc_by(inserted);
+            report.fills_inserted += inserted;
+            latest = latest.max(Some(fill.executed_at));
+        }
+        
+        let mut positions = Vec::new();
+        for raw in raw_positions {
+            let position = match normalize_position(raw) {
+                Ok(position) => position,
+                Err(e) => {
+                    log::warn!("Skipping position from {}: {}", source.name(), e);
+                    continue;
+                }
+            };
+            
+            let trader_id = self.trader_id(&mut trader_ids, &position.wallet_address).await?;
+            positions.push((trader_id, position));
+        }
+        
+        self.trade_repo.replace_positions(source.name(), &positions, fence).await?;
+        report.positions_updated += positions.len();
+        
+        if let Some(latest) = latest {
+            self.trade_repo.set_cursor(source.name(), CURSOR_FILLS, latest).await?;
+        }
+        
+        // Cash flow punya cursor sendiri karena waktunya tidak sejalan dengan fill
+        let cash_flow_cursor = self.trade_repo.get_cursor(source.name(), CURSOR_CASH_FLOWS).await?;
+        let mut latest_cash_flow = cash_flow_cursor;
+        
+        for raw in source.fetch_cash_flows(cash_flow_cursor).await? {
+            let cash_flow = match normalize_cash_flow(raw) {
+                Ok(cash_flow) => cash_flow,
+                Err(e) => {
+                    log::warn!("Skipping cash flow from {}: {}", source.name(), e);
+                    continue;
+                }
+            };
+            
+            let trader_id = self.trader_id(&mut trader_ids, &cash_flow.wallet_address).await?;
+            report.cash_flows_inserted += self.trade_repo.insert_cash_flow(source.name(), trader_id, &cash_flow).await?;
+            latest_cash_flow = latest_cash_flow.max(Some(cash_flow.occurred_at));
+        }
+        
+        if let Some(latest) = latest_cash_flow {
+            self.trade_repo.set_cursor(source.name(), CURSOR_CASH_FLOWS, latest).await?;
+        }
+        
+        Ok(())
+    }
+}
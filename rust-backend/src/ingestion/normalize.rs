@@ -0,0 +1,126 @@
+// src/ingestion/normalize.rs
+use bigdecimal::{BigDecimal, Zero};
+use chrono::{DateTime, Utc};
+use crate::calculation::CashFlowKind;
+use crate::models::{TradeSide, WalletAddress};
+use super::source::{RawCashFlow, RawFill, RawPosition};
+use super::IngestError;
+
+#[derive(Debug, Clone)]
+pub struct NormalizedFill {
+    pub external_id: String,
+    pub wallet_address: WalletAddress,
+    pub symbol: String,
+    pub side: TradeSide,
+    pub price: BigDecimal,
+    pub quantity: BigDecimal,
+    pub fee: BigDecimal,
+    pub realized_pnl: BigDecimal,
+    pub executed_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone)]
+pub struct NormalizedCashFlow {
+    pub external_id: String,
+    pub wallet_address: WalletAddress,
+    pub kind: CashFlowKind,
+    pub amount: BigDecimal,
+    pub occurred_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone)]
+pub struct NormalizedPosition {
+    pub wallet_address: WalletAddress,
+    pub symbol: String,
+    pub quantity: BigDecimal,
+    pub entry_price: BigDecimal,
+    pub mark_price: BigDecimal,
+}
+
+pub fn normalize_fill(raw: RawFill) -> Result<NormalizedFill, IngestError> {
+    let external_id = raw.fill_id.trim().to_string();
+    if external_id.is_empty() {
+        return Err(IngestError::Invalid("fill without id".to_string()));
+    }
+    
+    let side = TradeSide::parse(&raw.side).ok_or_else(|| {
+        IngestError::Invalid(format!("fill {}: unknown side '{}'", external_id, raw.side))
+    })?;
+    
+    if raw.price <= BigDecimal::zero() || raw.quantity <= BigDecimal::zero() {
+        return Err(IngestError::Invalid(format!("fill {}: price and quantity must be positive", external_id)));
+    }
+    
+    Ok(NormalizedFill {
+        wallet_address: normalize_wallet(&raw.wallet_address)?,
+        symbol: normalize_symbol(&raw.symbol)?,
+        side,
+        price: raw.price,
+        quantity: raw.quantity,
+        // Fee selalu disimpan sebagai biaya positif
+        fee: raw.fee.map(|fee| fee.abs()).unwrap_or_else(BigDecimal::zero),
+        realized_pnl: raw.realized_pnl.unwrap_or_else(BigDecimal::zero),
+        executed_at: raw.executed_at,
+        external_id,
+    })
+}
+
+pub fn normalize_position(raw: RawPosition) -> Result<NormalizedPosition, IngestError> {
+    if raw.entry_price < BigDecimal::zero() || raw.mark_price < BigDecimal::zero() {
+        return Err(IngestError::Invalid(format!("position {}: negative price", raw.symbol)));
+    }
+    
+    Ok(NormalizedPosition {
+        wallet_address: normalize_wallet(&raw.wallet_address)?,
+        symbol: normalize_symbol(&raw.symbol)?,
+        quantity: raw.quantity,
+        entry_price: raw.entry_price,
+        mark_price: raw.mark_price,
+    })
+}
+
+pub fn normalize_cash_flow(raw: RawCashFlow) -> Result<NormalizedCashFlow, IngestError> {
+    let external_id = raw.flow_id.trim().to_string();
+    if external_id.is_empty() {
+        return Err(IngestError::Invalid("cash flow without id".to_string()));
+    }
+    
+    let kind = match raw.kind.trim().to_lowercase().as_str() {
+        "deposit" => CashFlowKind::Deposit,
+        "withdrawal" | "withdraw" => CashFlowKind::Withdrawal,
+        other => {
+            return Err(IngestError::Invalid(format!("cash flow {}: unknown kind '{}'", external_id, other)));
+        }
+    };
+    
+    if raw.amount <= BigDecimal::zero() {
+        return Err(IngestError::Invalid(format!("cash flow {}: amount must be positive", external_id)));
+    }
+    
+    Ok(NormalizedCashFlow {
+        wallet_address: normalize_wallet(&raw.wallet_address)?,
+        kind,
+        amount: raw.amount,
+        occurred_at: raw.occurred_at,
+        external_id,
+    })
+}
+
+// Chain dideteksi dari format alamat (0x.. = EVM, bc1.. = Bitcoin, base58 = Solana)
+fn normalize_wallet(wallet_address: &str) -> Result<WalletAddress, IngestError> {
+    WalletAddress::parse(wallet_address, None)
+        .map_err(|e| IngestError::Invalid(format!("wallet '{}': {}", wallet_address, e)))
+}
+
+// "btc-usd", "BTC/USD" dan "btcusd " semuanya jadi "BTCUSD"
+fn normalize_symbol(symbol: &str) -> Result<String, IngestError> {
+    let symbol: String = symbol
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .map(|c| c.to_ascii_uppercase())
+        .collect();
+    if symbol.is_empty() || symbol.len() > 32 {
+        return Err(IngestError::Invalid("invalid symbol".to_string()));
+    }
+    Ok(symbol)
+}
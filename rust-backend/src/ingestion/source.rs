@@ -0,0 +1,61 @@
+// src/ingestion/source.rs
+use async_trait::async_trait;
+use bigdecimal::BigDecimal;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use super::IngestError;
+
+// Fill mentah seperti yang dikirim sumber, belum dinormalisasi
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RawFill {
+    pub fill_id: String,
+    pub wallet_address: String,
+    pub symbol: String,
+    pub side: String,
+    pub price: BigDecimal,
+    pub quantity: BigDecimal,
+    #[serde(default)]
+    pub fee: Option<BigDecimal>,
+    #[serde(default)]
+    pub realized_pnl: Option<BigDecimal>,
+    pub executed_at: DateTime<Utc>,
+}
+
+// Snapshot posisi terbuka; quantity negatif berarti short
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RawPosition {
+    pub wallet_address: String,
+    pub symbol: String,
+    pub quantity: BigDecimal,
+    pub entry_price: BigDecimal,
+    pub mark_price: BigDecimal,
+}
+
+// Deposit atau withdrawal; amount selalu positif
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RawCashFlow {
+    pub flow_id: String,
+    pub wallet_address: String,
+    pub kind: String,
+    pub amount: BigDecimal,
+    pub occurred_at: DateTime<Utc>,
+}
+
+// Sumber data trading (exchange, smart contract events, file, ...)
+#[async_trait]
+pub trait TradeSource: Send + Sync {
+    // Nama unik sumber, dipakai sebagai kolom `source` dan key cursor
+    fn name(&self) -> &str;
+    
+    // Semua fill dengan executed_at >= since (atau semua fill kalau since = None).
+    // Batas inklusif supaya fill yang timestamp-nya sama dengan cursor tidak terlewat; duplikat di-dedup lewat fill_id
+    async fn fetch_fills(&self, since: Option<DateTime<Utc>>) -> Result<Vec<RawFill>, IngestError>;
+    
+    // Snapshot posisi terbuka saat ini
+    async fn fetch_positions(&self) -> Result<Vec<RawPosition>, IngestError>;
+    
+    // Deposit/withdrawal dengan occurred_at >= since; sumber tanpa data modal cukup pakai default
+    async fn fetch_cash_flows(&self, _since: Option<DateTime<Utc>>) -> Result<Vec<RawCashFlow>, IngestError> {
+        Ok(Vec::new())
+    }
+}
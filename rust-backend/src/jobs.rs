@@ -0,0 +1,127 @@
+// src/jobs.rs
+// Background job yang didaftarkan ke Scheduler
+use async_trait::async_trait;
+use chrono::Utc;
+use std::sync::Arc;
+use crate::cache::LeaderboardCache;
+use crate::event_bus::EventBus;
+use crate::ingestion::IngestionPipeline;
+use crate::leader::Fence;
+use crate::repository::{JobRepository, LeaderboardRepository, WalletRepository};
+use crate::scheduler::{Job, JobResult};
+use crate::websocket::LeaderboardEvent;
+
+// Refresh materialized view, bersihkan cache, lalu broadcast ke semua instance
+pub struct RefreshLeaderboardJob {
+    pub repo: Arc<LeaderboardRepository>,
+    pub cache: Arc<LeaderboardCache>,
+    pub events: Arc<EventBus>,
+}
+
+#[async_trait]
+impl Job for RefreshLeaderboardJob {
+    async fn run(&self, fence: Fence) -> JobResult {
+        let version = refresh_leaderboard(&self.repo, &self.cache, &self.events, fence).await?;
+        Ok(format!("refreshed to version {}", version))
+    }
+}
+
+// Endpoint REST dan mutation GraphQL tidak memanggil ini langsung, tapi men-trigger job lewat Scheduler
+// supaya tidak overlap dengan run terjadwal dan tetap memakai fencing token leader
+async fn refresh_leaderboard(
+    repo: &LeaderboardRepository,
+    cache: &LeaderboardCache,
+    events: &EventBus,
+    fence: Fence,
+) -> Result<i64, sqlx::Error> {
+    let version = repo.refresh_materialized_view(fence).await?;
+    
+    // Cache yang gagal dihapus tetap kedaluwarsa sendiri lewat TTL
+    if let Err(e) = cache.clear_pattern("leaderboard:*").await {
+        log::warn!("Failed to clear leaderboard cache: {}", e);
+    }
+    broadcast_refresh(events, version).await;
+    
+    Ok(version)
+}
+
+// Tarik fill, posisi & cash flow dari semua sumber
+pub struct IngestTradesJob {
+    pub pipeline: Arc<IngestionPipeline>,
+}
+
+#[async_trait]
+impl Job for IngestTradesJob {
+    async fn run(&self, fence: Fence) -> JobResult {
+        let report = self.pipeline.ingest_all(fence).await;
+        
+        Ok(format!(
+            "ingested {}/{} fills ({} rejected, {} cash flows, {} positions, {} failed sources)",
+            report.fills_inserted,
+            report.fills_received,
+            report.fills_rejected,
+            report.cash_flows_inserted,
+            report.positions_updated,
+            report.failed_sources
+        ))
+    }
+}
+
+// Hitung snapshot trader_performance baru dari trade yang sudah tersimpan
+pub struct PerformanceSnapshotJob {
+    pub pipeline: Arc<IngestionPipeline>,
+}
+
+#[async_trait]
+impl Job for PerformanceSnapshotJob {
+    async fn run(&self, fence: Fence) -> JobResult {
+        let report = self.pipeline.recompute_performance(fence).await?;
+        Ok(format!(
+            "wrote {} performance rows ({} failed traders)",
+            report.rows, report.failed_traders
+        ))
+    }
+}
+
+// Hapus nonce/session SIWE kedaluwarsa, riwayat job lama dan snapshot leaderboard lama
+pub struct CleanupJob {
+    pub wallet_repo: Arc<WalletRepository>,
+    pub job_repo: Arc<JobRepository>,
+    pub leaderboard_repo: Arc<LeaderboardRepository>,
+    pub job_run_retention_days: i64,
+    // 0 = snapshot disimpan selamanya (default, untuk audit payout kompetisi)
+    pub snapshot_retention_days: i64,
+}
+
+#[async_trait]
+impl Job for CleanupJob {
+    // Delete bersifat idempotent, jadi tidak perlu fencing token
+    async fn run(&self, _fence: Fence) -> JobResult {
+        let (nonces, sessions) = self.wallet_repo.delete_expired().await?;
+        let runs = self.job_repo
+            .delete_runs_before(Utc::now() - chrono::Duration::days(self.job_run_retention_days))
+            .await?;
+        let snapshots = if self.snapshot_retention_days > 0 {
+            self.leaderboard_repo
+                .delete_snapshots_before(Utc::now() - chrono::Duration::days(self.snapshot_retention_days))
+                .await?
+        } else {
+            0
+        };
+        
+        Ok(format!(
+            "deleted {} expired nonces, {} expired sessions, {} old job runs, {} old snapshots",
+            nonces, sessions, runs, snapshots
+        ))
+    }
+}
+
+// Broadcast versi baru hasil refresh ke semua instance. Payload sengaja hanya berisi versi:
+// patch per view dan perubahan rank wallet yang di-watch dihitung oleh Publisher di masing-masing instance.
+pub async fn broadcast_refresh(events: &EventBus, version: i64) {
+    let event = LeaderboardEvent::LeaderboardUpdated { version };
+    match serde_json::to_string(&event) {
+        Ok(payload) => events.publish(payload).await,
+        Err(e) => log::error!("Failed to serialize leaderboard event: {:?}", e),
+    }
+}
@@ -0,0 +1,117 @@
+// src/leader.rs
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use crate::repository::LeaseRepository;
+
+// Status lease satu job di instance ini (ditampilkan di /api/health)
+#[derive(Debug, Clone, Serialize)]
+pub struct LeadershipStatus {
+    pub leader: bool,
+    pub fencing_token: Option<i64>,
+    pub last_renewed_at: Option<DateTime<Utc>>,
+}
+
+// Bukti leadership yang dibawa job sampai ke transaksi tulisnya (lihat LeaseRepository::check_fence)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Fence {
+    pub lease: &'static str,
+    pub token: i64,
+}
+
+// Leader election per job memakai lease di tabel leader_leases.
+// Lease diperpanjang setiap TTL/3; kalau perpanjangan gagal job langsung dihentikan,
+// jauh sebelum lease kedaluwarsa dan bisa diambil replica lain.
+pub struct LeaderElection {
+    leases: LeaseRepository,
+    instance_id: String,
+    lease_ttl: Duration,
+    status: RwLock<BTreeMap<&'static str, LeadershipStatus>>,
+}
+
+impl LeaderElection {
+    pub fn new(leases: LeaseRepository, instance_id: String, lease_ttl: Duration) -> Self {
+        Self {
+            leases,
+            instance_id,
+            lease_ttl,
+            status: RwLock::new(BTreeMap::new()),
+        }
+    }
+    
+    pub fn instance_id(&self) -> &str {
+        &self.instance_id
+    }
+    
+    pub fn status(&self) -> BTreeMap<&'static str, LeadershipStatus> {
+        self.status.read().unwrap().clone()
+    }
+    
+    // Jalankan `job` hanya selama instance ini memegang lease `name`.
+    // Job di-spawn ulang setiap kali leadership didapat kembali, dengan fencing token lease tersebut.
+    // Saat leadership hilang task-nya di-abort; eksekusi yang sedang berjalan harus berada di dalam task itu
+    // supaya ikut berhenti.
+    pub async fn run<F, Fut>(self: Arc<Self>, name: &'static str, job: F)
+    where
+        F: Fn(Fence) -> Fut + Send + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        let mut interval = tokio::time::interval(self.lease_ttl / 3);
+        let mut running: Option<JoinHandle<()>> = None;
+        
+        self.set_status(name, None);
+        
+        loop {
+            interval.tick().await;
+            
+            let token = match self.leases.try_acquire(name, &self.instance_id, self.lease_ttl.as_secs() as i64).await {
+                Ok(token) => token,
+                Err(e) => {
+                    log::error!("Failed to renew lease {}: {:?}", name, e);
+                    None
+                }
+            };
+            self.set_status(name, token);
+            
+            if running.as_ref().is_some_and(|handle| handle.is_finished()) {
+                log::warn!("Job {} stopped unexpectedly", name);
+                running = None;
+            }
+            
+            match (token, running.take()) {
+                (Some(token), None) => {
+                    log::info!("Instance {} is now leader for {} (fencing token {})", self.instance_id, name, token);
+                    running = Some(tokio::spawn(job(Fence { lease: name, token })));
+                }
+                (None, Some(handle)) => {
+                    log::warn!("Instance {} lost leadership for {}, stopping job", self.instance_id, name);
+                    handle.abort();
+                }
+                (_, handle) => running = handle,
+            }
+        }
+    }
+    
+    // Dipanggil saat shutdown
+    pub async fn release_all(&self) {
+        match self.leases.release_all(&self.instance_id).await {
+            Ok(0) => {}
+            Ok(released) => log::info!("Released {} leader leases", released),
+            Err(e) => log::error!("Failed to release leader leases: {:?}", e),
+        }
+    }
+    
+    fn set_status(&self, name: &'static str, token: Option<i64>) {
+        let mut status = self.status.write().unwrap();
+        let previous = status.get(name).and_then(|s| s.last_renewed_at);
+        status.insert(name, LeadershipStatus {
+            leader: token.is_some(),
+            fencing_token: token,
+            last_renewed_at: if token.is_some() { Some(Utc::now()) } else { previous },
+        });
+    }
+}
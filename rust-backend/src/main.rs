@@ -4,26 +4,73 @@ mod repository;
 mod cache;
 mod websocket;
 mod models;
+mod ingestion;
+mod calculation;
+mod auth;
+mod siwe;
+mod pagination;
+mod fanout;
+mod event_bus;
+mod leader;
+mod scheduler;
+mod jobs;
+mod config;
+mod error;
+mod metrics;
+mod circuit_breaker;
+mod memory_cache;
+mod telemetry;
+mod health;
 
 use actix_cors::Cors;
-use actix_web::{web, App, HttpServer, middleware::Logger};
-use async_graphql::{EmptyMutation, EmptySubscription, Schema};
-use async_graphql_actix_web::{GraphQL, GraphQLSubscription};
+use actix_web::{web, App, HttpServer};
+use actix_web::dev::Service;
+use async_graphql::Schema;
+use async_graphql_actix_web::{GraphQLRequest, GraphQLResponse, GraphQLSubscription};
+use tracing_actix_web::TracingLogger;
 use sqlx::postgres::PgPoolOptions;
 use dotenv::dotenv;
 use std::env;
 use std::sync::Arc;
 use tokio::sync::broadcast;
-use crate::schema::{LeaderboardQuery, LeaderboardMutation};
-use crate::repository::LeaderboardRepository;
+use crate::schema::{operation_label, LeaderboardQuery, LeaderboardMutation, LeaderboardSubscription, LeaderboardSchema};
+use crate::auth::{ApiKeyStore, RequireRole, Role};
+use crate::repository::{JobRepository, LeaderboardRepository, LeaseRepository, TradeRepository, WalletRepository};
+use crate::siwe::{session_token_hash, SiweMessage, NONCE_TTL_MINUTES};
+use crate::calculation::CostBasisMethod;
+use crate::ingestion::IngestionPipeline;
+use crate::ingestion::file_source::FileTradeSource;
+use crate::ingestion::mock_exchange::MockExchange;
 use crate::cache::LeaderboardCache;
+use crate::models::{validate_bucket, validate_timeframe, Chain, WalletAddress};
+use crate::websocket::{ClientSession, LeaderboardView};
+use crate::fanout::Publisher;
+use crate::event_bus::EventBus;
+use crate::leader::LeaderElection;
+use crate::scheduler::{JobSpec, Scheduler};
+use crate::jobs::{CleanupJob, IngestTradesJob, PerformanceSnapshotJob, RefreshLeaderboardJob};
+use crate::pagination::LeaderboardCursor;
+use crate::config::{CliOptions, Config};
+use crate::error::AppError;
+use crate::metrics::metrics;
+use crate::health::HealthChecker;
+use crate::circuit_breaker::CircuitBreaker;
+use crate::memory_cache::MemoryCache;
+
+// Migration ikut dikompilasi ke binary; versinya juga dipakai readiness check
+static MIGRATOR: sqlx::migrate::Migrator = sqlx::migrate!("./migrations");
 
 // State yang akan dibagikan ke seluruh aplikasi
 pub struct AppState {
     pool: sqlx::PgPool,
     leaderboard_repo: Arc<LeaderboardRepository>,
     cache: Arc<LeaderboardCache>,
-    ws_tx: broadcast::Sender<String>,
+    publisher: Arc<Publisher>,
+    leadership: Arc<LeaderElection>,
+    scheduler: Arc<Scheduler>,
+    wallet_repo: Arc<WalletRepository>,
+    health: Arc<HealthChecker>,
+    config: Arc<Config>,
 }
 
 #[tokio::main]
@@ -31,18 +78,35 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     // Load environment variables
     dotenv().ok();
     
-    // Setup logging
-    env_logger::init_from_env(env_logger::Env::new().default_filter_or("info"));
-    
-    // Database connection pool
-    let database_url = env::var("DATABASE_URL")
-        .expect("DATABASE_URL must be set");
+    // Konfigurasi: file TOML + override environment, divalidasi sebelum apa pun dijalankan
+    let config = match CliOptions::parse(env::args().skip(1))
+        .and_then(|cli| Config::load(cli.config_path.as_deref()).map(|config| (cli, config)))
+    {
+        Ok((cli, config)) if cli.print_config => {
+            print!("{}", config.redacted().to_toml());
+            return Ok(());
+        }
+        Ok((_, config)) => Arc::new(config),
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(2);
+        }
+    };
+    
+    // Setup logging & tracing; guard di-drop di akhir main supaya span terakhir sempat dikirim
+    let _telemetry = match telemetry::init(&config.telemetry) {
+        Ok(guard) => guard,
+        Err(e) => {
+            eprintln!("Failed to initialize telemetry: {}", e);
+            std::process::exit(2);
+        }
+    };
     
     log::info!("Connecting to database...");
     
     let pool = PgPoolOptions::new()
-        .max_connections(20)
-        .connect(&database_url)
+        .max_connections(config.database.max_connections)
+        .connect(&config.database.url)
         .await
         .expect("Failed to create pool");
     
@@ -50,94 +114,269 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     
     // Run migrations
     log::info!("Running database migrations...");
-    sqlx::migrate!("./migrations")
+    MIGRATOR
         .run(&pool)
         .await
         .expect("Failed to run migrations");
     
-    // Initialize Redis cache
-    let redis_url = env::var("REDIS_URL")
-        .unwrap_or_else(|_| "redis://localhost:6379".to_string());
-    
-    log::info!("Connecting to Redis at {}", redis_url);
-    let redis_client = redis::Client::open(redis_url)
-        .expect("Failed to connect to Redis");
+    // Alamat EVM lama (huruf kecil) diubah ke checksum EIP-55
+    match WalletRepository::new(pool.clone()).backfill_evm_checksums().await {
+        Ok(0) => {}
+        Ok(updated) => log::info!("Converted {} wallet addresses to EIP-55 checksum", updated),
+        Err(e) => log::error!("Failed to backfill wallet checksums: {:?}", e),
+    }
     
-    let cache = Arc::new(LeaderboardCache::new(redis_client));
+    // Redis opsional: URL yang tidak valid atau Redis yang mati tidak menghentikan startup,
+    // koneksi dibuat saat pertama dipakai
+    let redis_client = if config.redis.enabled {
+        log::info!("Using Redis at {}", config.redacted().redis.url);
+        match redis::Client::open(config.redis.url.as_str()) {
+            Ok(client) => Some(client),
+            Err(e) => {
+                log::error!("Invalid Redis URL, continuing without Redis: {}", e);
+                None
+            }
+        }
+    } else {
+        log::info!("Redis disabled, cache and cross-replica events are off");
+        None
+    };
+    
+    let cache = Arc::new(
+        LeaderboardCache::new(redis_client.clone())
+            .with_timeouts(
+                std::time::Duration::from_millis(config.redis.connect_timeout_ms),
+                std::time::Duration::from_millis(config.redis.command_timeout_ms),
+            )
+            .with_circuit_breaker(CircuitBreaker::new(
+                "redis",
+                config.redis.breaker_failure_threshold,
+                std::time::Duration::from_secs(config.redis.breaker_cooldown_secs),
+            ))
+            .with_memory_tier(MemoryCache::new(
+                config.cache.memory_max_entries,
+                std::time::Duration::from_secs(config.cache.memory_max_ttl_secs),
+            )),
+    );
     
     // Initialize repository
     let leaderboard_repo = Arc::new(LeaderboardRepository::new(pool.clone()));
     
+    // Identitas replica untuk relay event dan leader election
+    let instance_id = config
+        .cluster
+        .instance_id
+        .clone()
+        .unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+    
+    // Background job hanya dijalankan oleh replica yang memegang lease-nya
+    let leadership = Arc::new(LeaderElection::new(
+        LeaseRepository::new(pool.clone()),
+        instance_id.clone(),
+        std::time::Duration::from_secs(config.cluster.lease_ttl_secs),
+    ));
+    
     // Create WebSocket broadcast channel
-    let (ws_tx, _) = broadcast::channel(100);
+    let (ws_tx, _) = broadcast::channel(config.websocket.broadcast_capacity);
+    
+    // Satu publisher membagikan update ke semua koneksi /api/ws
+    let publisher = Arc::new(
+        Publisher::new(leaderboard_repo.clone()).with_queue_capacity(config.websocket.client_queue_capacity),
+    );
+    tokio::spawn(publisher.clone().run(ws_tx.subscribe()));
+    tokio::spawn(cache.clone().run_invalidation(ws_tx.subscribe()));
+    
+    // Event refresh diteruskan ke semua replica lewat Redis pub/sub (tanpa Redis hanya ke replica ini)
+    let events_channel = config.cluster.events_channel.clone();
+    let events = Arc::new(EventBus::new(
+        redis_client,
+        cache.clone(),
+        events_channel.clone(),
+        instance_id.clone(),
+        ws_tx.clone(),
+    ));
+    if events.is_distributed() {
+        log::info!("Instance {} relaying events on Redis channel {}", events.instance_id(), events_channel);
+        let events_clone = events.clone();
+        tokio::spawn(async move {
+            events_clone.run_relay().await;
+        });
+    }
+    
+    // Background job; jadwal diatur di bagian [jobs] konfigurasi
+    let wallet_repo = Arc::new(WalletRepository::new(pool.clone()));
+    let pipeline = Arc::new(build_ingestion_pipeline(&config, Arc::new(TradeRepository::new(pool.clone()))));
+    let scheduler = Arc::new(
+        Scheduler::new(JobRepository::new(pool.clone()), instance_id.clone())
+            .register(
+                "refresh_leaderboard",
+                job_spec(&config, "refresh_leaderboard"),
+                Arc::new(RefreshLeaderboardJob {
+                    repo: leaderboard_repo.clone(),
+                    cache: cache.clone(),
+                    events: events.clone(),
+                }),
+            )
+            .register(
+                "ingest_trades",
+                job_spec(&config, "ingest_trades"),
+                Arc::new(IngestTradesJob { pipeline: pipeline.clone() }),
+            )
+            .register(
+                "performance_snapshot",
+                job_spec(&config, "performance_snapshot"),
+                Arc::new(PerformanceSnapshotJob { pipeline }),
+            )
+            .register(
+                "cleanup",
+                job_spec(&config, "cleanup"),
+                Arc::new(CleanupJob {
+                    wallet_repo: wallet_repo.clone(),
+                    job_repo: Arc::new(JobRepository::new(pool.clone())),
+                    leaderboard_repo: leaderboard_repo.clone(),
+                    job_run_retention_days: config.jobs.run_retention_days,
+                    snapshot_retention_days: config.jobs.snapshot_retention_days,
+                }),
+            ),
+    );
+    scheduler.start(&leadership);
+    
+    let health = Arc::new(HealthChecker::new(
+        pool.clone(),
+        leaderboard_repo.clone(),
+        cache.clone(),
+        config.health.clone(),
+        MIGRATOR.iter().map(|migration| migration.version).collect(),
+    ));
     
     // Create shared state
     let app_state = web::Data::new(AppState {
         pool: pool.clone(),
         leaderboard_repo: leaderboard_repo.clone(),
         cache: cache.clone(),
-        ws_tx: ws_tx.clone(),
+        publisher: publisher.clone(),
+        leadership: leadership.clone(),
+        scheduler: scheduler.clone(),
+        wallet_repo: wallet_repo.clone(),
+        health,
+        config: config.clone(),
     });
     
-    // Start background task untuk refresh materialized view
-    let repo_clone = leaderboard_repo.clone();
-    let cache_clone = cache.clone();
-    let ws_tx_clone = ws_tx.clone();
-    
-    tokio::spawn(async move {
-        refresh_leaderboard_task(repo_clone, cache_clone, ws_tx_clone).await;
-    });
+    // API key untuk endpoint admin (refresh, manajemen trader)
+    let api_keys = ApiKeyStore::from_env_value(&config.auth.api_keys)
+        .expect("Invalid API_KEYS");
+    if api_keys.is_empty() {
+        log::warn!("API_KEYS is not set, admin endpoints will reject every request");
+    }
+    let api_keys = web::Data::new(api_keys);
     
-    // Start background task untuk update data trader
-    let pool_clone = pool.clone();
-    tokio::spawn(async move {
-        update_trader_data_task(pool_clone).await;
-    });
+    // Kalau cors.allowed_origins kosong semua origin diizinkan tanpa credentials
+    let cors_origins = config.cors.allowed_origins.clone();
     
     // Build GraphQL schema
     let schema = Schema::build(
-        LeaderboardQuery::default(),
-        LeaderboardMutation::default(),
-        EmptySubscription,
+        LeaderboardQuery,
+        LeaderboardMutation,
+        LeaderboardSubscription::default(),
     )
     .data(pool.clone())
     .data(leaderboard_repo.clone())
-    .data(cache.clone())
+    .data(ws_tx.clone())
+    .data(scheduler.clone())
+    .extension(async_graphql::extensions::Tracing)
     .finish();
     
     // Start HTTP server
-    let host = env::var("HOST").unwrap_or_else(|_| "0.0.0.0".to_string());
-    let port = env::var("PORT").unwrap_or_else(|_| "8080".to_string());
+    let host = config.server.host.clone();
+    let port = config.server.port;
     
     log::info!("Starting server at {}:{}", host, port);
     
-    HttpServer::new(move || {
-        let cors = Cors::default()
-            .allow_any_origin()
-            .allow_any_method()
-            .allow_any_header()
-            .supports_credentials()
-            .max_age(3600);
+    let server = HttpServer::new(move || {
+        let cors = if cors_origins.is_empty() {
+            Cors::default().allow_any_origin()
+        } else {
+            cors_origins
+                .iter()
+                .fold(Cors::default(), |cors, origin| cors.allowed_origin(origin))
+                .supports_credentials()
+        }
+        .allow_any_method()
+        .allow_any_header()
+        .max_age(3600);
         
         App::new()
             .app_data(app_state.clone())
+            .app_data(api_keys.clone())
             .app_data(web::Data::new(schema.clone()))
+            // Query/body yang tidak valid juga dijawab dengan problem details
+            .app_data(web::QueryConfig::default().error_handler(|err, _| AppError::invalid(err.to_string()).into()))
+            .app_data(web::JsonConfig::default().error_handler(|err, _| AppError::invalid(err.to_string()).into()))
             .wrap(cors)
-            .wrap(Logger::default())
+            // Span per request; melanjutkan trace dari header traceparent kalau ada
+            .wrap(TracingLogger::default())
+            // Latensi & status per pola route (bukan path mentah) untuk /metrics
+            .wrap_fn(|req, srv| {
+                let started = std::time::Instant::now();
+                let method = req.method().to_string();
+                let fut = srv.call(req);
+                async move {
+                    let result = fut.await;
+                    match &result {
+                        Ok(res) => metrics().observe_http(
+                            &method,
+                            res.request().match_pattern().as_deref(),
+                            res.status().as_u16(),
+                            started.elapsed(),
+                        ),
+                        Err(e) => metrics().observe_http(
+                            &method,
+                            None,
+                            e.as_response_error().status_code().as_u16(),
+                            started.elapsed(),
+                        ),
+                    }
+                    result
+                }
+            })
             .service(
                 web::resource("/graphql")
-                    .route(web::post().to(GraphQL::new(schema.clone())))
+                    .route(web::post().to(graphql_handler))
             )
             .service(
                 web::resource("/graphql/ws")
-                    .route(web::get().to(GraphQLSubscription::new(schema.clone())))
+                    .route(web::get().to(graphql_ws_handler))
             )
             .service(
                 web::scope("/api")
                     .route("/health", web::get().to(health_check))
+                    .route("/health/live", web::get().to(health_live))
+                    .route("/health/ready", web::get().to(health_ready))
                     .route("/leaderboard", web::get().to(get_leaderboard_rest))
-                    .route("/leaderboard/refresh", web::post().to(refresh_leaderboard))
+                    .route("/leaderboard/page", web::get().to(get_leaderboard_page_rest))
+                    .route("/leaderboard/snapshot", web::get().to(get_leaderboard_snapshot_rest))
+                    .route("/leaderboard/snapshots", web::get().to(list_leaderboard_snapshots_rest))
+                    .route("/leaderboard/movers", web::get().to(get_movers_rest))
+                    .service(
+                        web::resource("/leaderboard/refresh")
+                            .wrap(RequireRole::new(Role::Operator))
+                            .route(web::post().to(refresh_leaderboard))
+                    )
+                    .route("/traders/me/profile", web::put().to(update_trader_profile))
+                    .route("/traders/{wallet}/history", web::get().to(get_trader_history_rest))
+                    .route("/traders/{wallet}/snapshots", web::get().to(get_trader_snapshots_rest))
+                    .route("/auth/siwe/nonce", web::post().to(siwe_nonce))
+                    .route("/auth/siwe/verify", web::post().to(siwe_verify))
                     .route("/ws", web::get().to(websocket_endpoint))
+                    .service(
+                        web::scope("/admin")
+                            .wrap(RequireRole::new(Role::Admin))
+                            .route("/jobs", web::get().to(list_jobs))
+                            .route("/jobs/{name}/runs", web::get().to(get_job_runs))
+                            .route("/jobs/{name}/trigger", web::post().to(trigger_job))
+                            .route("/jobs/{name}/pause", web::post().to(pause_job))
+                            .route("/jobs/{name}/resume", web::post().to(resume_job))
+                    )
             )
             .service(
                 web::scope("/metrics")
@@ -145,81 +384,391 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             )
     })
     .bind(format!("{}:{}", host, port))?
-    .workers(4) // Jumlah worker threads
+    .workers(config.server.workers)
     .run()
-    .await?;
+    .await;
+    
+    // Lepas lease supaya replica lain bisa langsung mengambil alih job
+    leadership.release_all().await;
     
+    server?;
     Ok(())
 }
 
-// Handler untuk health check
-async fn health_check() -> actix_web::HttpResponse {
+// GraphQL handler; principal dari API key diteruskan ke resolver untuk RoleGuard
+async fn graphql_handler(
+    schema: web::Data<LeaderboardSchema>,
+    api_keys: web::Data<ApiKeyStore>,
+    req: actix_web::HttpRequest,
+    gql_request: GraphQLRequest,
+) -> GraphQLResponse {
+    let mut request = gql_request.into_inner();
+    
+    if let Some(principal) = api_keys.authenticate(req.headers()) {
+        request = request.data(principal);
+    }
+    
+    let operation = operation_label(&request);
+    let started = std::time::Instant::now();
+    let response = schema.execute(request).await;
+    metrics().observe_graphql(operation, response.is_ok(), started.elapsed());
+    
+    response.into()
+}
+
+// GraphQL subscription lewat WebSocket (protokol graphql-ws / graphql-transport-ws)
+async fn graphql_ws_handler(
+    schema: web::Data<LeaderboardSchema>,
+    req: actix_web::HttpRequest,
+    payload: web::Payload,
+) -> actix_web::Result<actix_web::HttpResponse> {
+    GraphQLSubscription::new(LeaderboardSchema::clone(&schema)).start(&req, payload)
+}
+
+// Handler untuk health check (info replica & leadership; tidak mengecek dependency, lihat /health/ready)
+async fn health_check(state: web::Data<AppState>) -> actix_web::HttpResponse {
     actix_web::HttpResponse::Ok()
         .content_type("application/json")
         .json(serde_json::json!({
             "status": "healthy",
             "timestamp": chrono::Utc::now().to_rfc3339(),
-            "service": "leaderboard-api"
+            "service": "leaderboard-api",
+            "instance_id": state.leadership.instance_id(),
+            "leadership": state.leadership.status()
         }))
 }
 
+// Liveness: proses hidup dan bisa menjawab request; sengaja tidak menyentuh dependency
+// supaya database/Redis yang down tidak membuat container di-restart terus
+async fn health_live(state: web::Data<AppState>) -> actix_web::HttpResponse {
+    actix_web::HttpResponse::Ok().json(serde_json::json!({
+        "status": "alive",
+        "timestamp": chrono::Utc::now().to_rfc3339(),
+        "instance_id": state.leadership.instance_id(),
+    }))
+}
+
+// Readiness: 503 kalau dependency wajib tidak sehat, supaya load balancer berhenti mengirim traffic
+async fn health_ready(state: web::Data<AppState>) -> actix_web::HttpResponse {
+    let readiness = state.health.check().await;
+    let mut response = if readiness.ready {
+        actix_web::HttpResponse::Ok()
+    } else {
+        actix_web::HttpResponse::ServiceUnavailable()
+    };
+    
+    response.json(serde_json::json!({
+        "status": if readiness.ready { "ready" } else { "not_ready" },
+        "timestamp": chrono::Utc::now().to_rfc3339(),
+        "instance_id": state.leadership.instance_id(),
+        "checks": readiness.checks,
+    }))
+}
+
 // REST API endpoint untuk leaderboard
 async fn get_leaderboard_rest(
     state: web::Data<AppState>,
     web::Query(params): web::Query<LeaderboardParams>,
-) -> actix_web::HttpResponse {
+) -> Result<actix_web::HttpResponse, AppError> {
     let limit = params.limit.unwrap_or(100);
     let offset = params.offset.unwrap_or(0);
     let sort_by = params.sort_by.as_deref().unwrap_or("roi");
     let sort_order = params.sort_order.as_deref().unwrap_or("desc");
+    let timeframe = validate_timeframe(params.timeframe.as_deref())?;
     
-    // Coba ambil dari cache dulu
-    let cache_key = format!("leaderboard:{}:{}:{}:{}", limit, offset, sort_by, sort_order);
+    // Coba ambil dari cache dulu; Redis yang bermasalah tidak menggagalkan request
+    let cache_key = format!("leaderboard:{}:{}:{}:{}:{}", timeframe, limit, offset, sort_by, sort_order);
     
-    if let Some(cached_data) = state.cache.get_cached_leaderboard(&cache_key).await {
-        return actix_web::HttpResponse::Ok()
-            .content_type("application/json")
-            .json(cached_data);
+    match state.cache.get_cached_leaderboard(&cache_key).await {
+        Ok(Some(body)) => {
+            return Ok(actix_web::HttpResponse::Ok()
+                .content_type("application/json")
+                .body(body));
+        }
+        Ok(None) => {}
+        Err(e) => log::warn!("Leaderboard cache read failed: {}", e),
     }
     
     // Jika tidak ada di cache, query dari database
-    match state.leaderboard_repo.get_leaderboard(limit, offset, sort_by, sort_order).await {
-        Ok(entries) => {
-            // Cache hasilnya
-            let cache_ttl = if offset == 0 { 10 } else { 30 }; // Cache lebih lama untuk halaman pertama
-            state.cache.cache_leaderboard(&cache_key, &entries, cache_ttl).await;
-            
-            actix_web::HttpResponse::Ok()
-                .content_type("application/json")
-                .json(entries)
-        }
-        Err(err) => {
-            log::error!("Failed to fetch leaderboard: {:?}", err);
-            actix_web::HttpResponse::InternalServerError()
-                .json(serde_json::json!({"error": "Failed to fetch leaderboard"}))
-        }
+    let entries = state.leaderboard_repo.get_leaderboard(limit, offset, sort_by, sort_order, timeframe).await?;
+    
+    // Cache hasilnya
+    let cache_ttl = if offset == 0 {
+        state.config.cache.first_page_ttl_secs
+    } else {
+        state.config.cache.page_ttl_secs
+    };
+    // Serialize sekali; body yang sama dikirim ke klien dan disimpan di kedua tier cache
+    let body = web::Bytes::from(
+        serde_json::to_vec(&entries).map_err(|e| AppError::Internal(format!("failed to serialize leaderboard: {}", e)))?,
+    );
+    if let Err(e) = state.cache.cache_leaderboard(&cache_key, body.clone(), cache_ttl).await {
+        log::warn!("Leaderboard cache write failed: {}", e);
     }
+    
+    Ok(actix_web::HttpResponse::Ok()
+        .content_type("application/json")
+        .body(body))
 }
 
-// Endpoint untuk refresh leaderboard manual
-async fn refresh_leaderboard(state: web::Data<AppState>) -> actix_web::HttpResponse {
-    match state.leaderboard_repo.refresh_materialized_view().await {
-        Ok(_) => {
-            // Clear cache setelah refresh
-            state.cache.clear_pattern("leaderboard:*").await;
-            
-            // Broadcast update via WebSocket
-            let _ = state.ws_tx.send("leaderboard_updated".to_string());
-            
-            actix_web::HttpResponse::Ok()
-                .json(serde_json::json!({"status": "refreshed"}))
-        }
-        Err(err) => {
-            log::error!("Failed to refresh leaderboard: {:?}", err);
-            actix_web::HttpResponse::InternalServerError()
-                .json(serde_json::json!({"error": "Failed to refresh leaderboard"}))
+// REST API endpoint untuk leaderboard dengan cursor pagination
+async fn get_leaderboard_page_rest(
+    state: web::Data<AppState>,
+    web::Query(params): web::Query<PageParams>,
+) -> Result<actix_web::HttpResponse, AppError> {
+    let first = params.limit.unwrap_or(100).clamp(1, 500);
+    let cursor = params.cursor.as_deref().map(LeaderboardCursor::decode).transpose()?;
+    let timeframe = validate_timeframe(params.timeframe.as_deref())?;
+    
+    let page = state.leaderboard_repo.get_leaderboard_page(
+        first,
+        cursor.as_ref(),
+        params.sort_by.as_deref().unwrap_or("roi"),
+        params.sort_order.as_deref().unwrap_or("desc"),
+        timeframe,
+    )
+    .await?;
+    
+    Ok(actix_web::HttpResponse::Ok()
+        .content_type("application/json")
+        .json(serde_json::json!({
+            "data": page.entries,
+            "next_cursor": if page.has_next_page { page.end_cursor() } else { None },
+            "has_next_page": page.has_next_page,
+            "total_count": page.total_count,
+            "version": page.version
+        })))
+}
+
+// REST API endpoint untuk leaderboard pada waktu tertentu (as_of) atau snapshot tertentu (snapshot_id)
+async fn get_leaderboard_snapshot_rest(
+    state: web::Data<AppState>,
+    web::Query(params): web::Query<SnapshotParams>,
+) -> Result<actix_web::HttpResponse, AppError> {
+    let timeframe = validate_timeframe(params.timeframe.as_deref())?;
+    let snapshot = match (params.as_of, params.snapshot_id) {
+        (Some(as_of), None) => state.leaderboard_repo.get_snapshot_as_of(as_of).await?,
+        (None, Some(snapshot_id)) => state.leaderboard_repo.get_snapshot(snapshot_id).await?,
+        _ => return Err(AppError::invalid("Exactly one of as_of or snapshot_id is required")),
+    };
+    
+    let snapshot = snapshot.ok_or_else(|| AppError::NotFound("No snapshot found".to_string()))?;
+    let entries = state.leaderboard_repo
+        .get_snapshot_entries(
+            snapshot.id,
+            timeframe,
+            params.limit.unwrap_or(100).clamp(1, 500),
+            params.offset.unwrap_or(0).max(0),
+        )
+        .await?;
+    
+    Ok(actix_web::HttpResponse::Ok()
+        .content_type("application/json")
+        .json(serde_json::json!({"snapshot": snapshot, "data": entries})))
+}
+
+// REST API endpoint untuk daftar snapshot, terbaru lebih dulu
+async fn list_leaderboard_snapshots_rest(
+    state: web::Data<AppState>,
+    web::Query(params): web::Query<SnapshotListParams>,
+) -> Result<actix_web::HttpResponse, AppError> {
+    let limit = params.limit.unwrap_or(50).clamp(1, 500);
+    let snapshots = state.leaderboard_repo.list_snapshots(limit, params.before).await?;
+    
+    Ok(actix_web::HttpResponse::Ok()
+        .content_type("application/json")
+        .json(snapshots))
+}
+
+// REST API endpoint untuk kenaikan & penurunan rank terbesar dalam window (default 24 jam)
+async fn get_movers_rest(
+    state: web::Data<AppState>,
+    web::Query(params): web::Query<MoversParams>,
+) -> Result<actix_web::HttpResponse, AppError> {
+    let window_hours = params.window_hours.unwrap_or(24).clamp(1, 24 * 90);
+    let since = chrono::Utc::now() - chrono::Duration::hours(window_hours);
+    let limit = params.limit.unwrap_or(10).clamp(1, 100);
+    let timeframe = validate_timeframe(params.timeframe.as_deref())?;
+    
+    let movers = state.leaderboard_repo.get_movers(timeframe, since, limit).await?;
+    
+    Ok(actix_web::HttpResponse::Ok()
+        .content_type("application/json")
+        .json(movers))
+}
+
+// REST API endpoint untuk time-series performa trader
+async fn get_trader_history_rest(
+    state: web::Data<AppState>,
+    wallet: web::Path<String>,
+    web::Query(params): web::Query<HistoryParams>,
+) -> Result<actix_web::HttpResponse, AppError> {
+    let to = params.to.unwrap_or_else(chrono::Utc::now);
+    let from = params.from.unwrap_or(to - chrono::Duration::days(7));
+    
+    if from >= to {
+        return Err(AppError::invalid("`from` must be before `to`"));
+    }
+    
+    let wallet = WalletAddress::parse(&wallet, None)?;
+    let timeframe = validate_timeframe(params.timeframe.as_deref())?;
+    let bucket = validate_bucket(params.bucket.as_deref())?;
+    
+    let points = state.leaderboard_repo.get_trader_history(wallet.as_str(), timeframe, from, to, bucket).await?;
+    
+    Ok(actix_web::HttpResponse::Ok()
+        .content_type("application/json")
+        .json(points))
+}
+
+// REST API endpoint untuk baris trader_performance mentah di balik satu baris leaderboard
+async fn get_trader_snapshots_rest(
+    state: web::Data<AppState>,
+    wallet: web::Path<String>,
+    web::Query(params): web::Query<TraderSnapshotsParams>,
+) -> Result<actix_web::HttpResponse, AppError> {
+    let wallet = WalletAddress::parse(&wallet, None)?;
+    let timeframe = validate_timeframe(params.timeframe.as_deref())?;
+    let limit = params.limit.unwrap_or(20).clamp(1, 200);
+    
+    let snapshots = state.leaderboard_repo.get_snapshot_history(wallet.as_str(), timeframe, limit).await?;
+    
+    Ok(actix_web::HttpResponse::Ok()
+        .content_type("application/json")
+        .json(snapshots))
+}
+
+// SIWE langkah 1: buat nonce sekali pakai yang harus dimasukkan ke pesan yang ditandatangani
+async fn siwe_nonce(state: web::Data<AppState>) -> Result<actix_web::HttpResponse, AppError> {
+    let nonce = uuid::Uuid::new_v4().simple().to_string();
+    let expires_at = chrono::Utc::now() + chrono::Duration::minutes(NONCE_TTL_MINUTES);
+    
+    state.wallet_repo.create_nonce(&nonce, expires_at).await?;
+    
+    Ok(actix_web::HttpResponse::Ok()
+        .json(serde_json::json!({"nonce": nonce, "expires_at": expires_at})))
+}
+
+// SIWE langkah 2: verifikasi signature, klaim trader dan buat session pemilik wallet
+async fn siwe_verify(
+    state: web::Data<AppState>,
+    web::Json(body): web::Json<SiweVerifyRequest>,
+) -> Result<actix_web::HttpResponse, AppError> {
+    let message = SiweMessage::parse(&body.message)?;
+    message
+        .verify_and_consume(
+            &body.message,
+            &body.signature,
+            &state.config.auth.siwe_domain,
+            &state.config.auth.siwe_chain_ids,
+            state.wallet_repo.as_ref(),
+            chrono::Utc::now(),
+        )
+        .await?;
+    
+    // SIWE selalu EVM; alamat disimpan dalam bentuk checksum EIP-55
+    let wallet_address = WalletAddress::parse(&message.address, Some(Chain::Evm))?;
+    let session_token = format!("{}{}", uuid::Uuid::new_v4().simple(), uuid::Uuid::new_v4().simple());
+    let expires_at = chrono::Utc::now() + chrono::Duration::days(7);
+    
+    let trader_id = state.wallet_repo.claim_trader(&wallet_address).await?;
+    state.wallet_repo
+        .create_session(&session_token_hash(&session_token), trader_id, expires_at)
+        .await?;
+    
+    Ok(actix_web::HttpResponse::Ok().json(serde_json::json!({
+        "wallet_address": wallet_address.as_str(),
+        "session_token": session_token,
+        "expires_at": expires_at
+    })))
+}
+
+// Pemilik wallet (session dari SIWE) bisa mengubah display name atau opt-out dari leaderboard
+async fn update_trader_profile(
+    state: web::Data<AppState>,
+    req: actix_web::HttpRequest,
+    web::Json(body): web::Json<ProfileUpdateRequest>,
+) -> Result<actix_web::HttpResponse, AppError> {
+    let token = req
+        .headers()
+        .get("authorization")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "));
+    
+    let session = match token {
+        Some(token) => state.wallet_repo.find_session(&session_token_hash(token.trim())).await?,
+        None => None,
+    };
+    let (trader_id, wallet_address) =
+        session.ok_or_else(|| AppError::Unauthorized("Missing or invalid session".to_string()))?;
+    
+    // Tidak dikirim = tidak diubah, string kosong = hapus display name
+    let display_name = body.display_name.as_deref().map(str::trim).map(|name| (!name.is_empty()).then_some(name));
+    if let Some(Some(name)) = display_name {
+        if name.chars().count() > 64 || name.chars().any(char::is_control) {
+            return Err(AppError::invalid("display_name must be at most 64 printable characters"));
         }
     }
+    
+    state.wallet_repo.update_profile(trader_id, display_name, body.opted_out).await?;
+    
+    Ok(actix_web::HttpResponse::Ok().json(serde_json::json!({
+        "wallet_address": wallet_address,
+        "display_name": display_name,
+        "opted_out": body.opted_out
+    })))
+}
+
+// Endpoint untuk refresh leaderboard manual: trigger job refresh_leaderboard, jadi tidak overlap dengan run terjadwal
+async fn refresh_leaderboard(state: web::Data<AppState>) -> Result<actix_web::HttpResponse, AppError> {
+    state.scheduler.trigger("refresh_leaderboard")?;
+    
+    Ok(actix_web::HttpResponse::Accepted()
+        .json(serde_json::json!({"status": "started"})))
+}
+
+// Endpoint admin: daftar job beserta status & run terakhir
+async fn list_jobs(state: web::Data<AppState>) -> Result<actix_web::HttpResponse, AppError> {
+    let jobs = state.scheduler.list().await?;
+    
+    Ok(actix_web::HttpResponse::Ok()
+        .content_type("application/json")
+        .json(jobs))
+}
+
+async fn get_job_runs(
+    state: web::Data<AppState>,
+    path: web::Path<String>,
+    web::Query(params): web::Query<JobRunsParams>,
+) -> Result<actix_web::HttpResponse, AppError> {
+    let limit = params.limit.unwrap_or(20).clamp(1, 200);
+    let runs = state.scheduler.runs(&path.into_inner(), limit).await?;
+    
+    Ok(actix_web::HttpResponse::Ok()
+        .content_type("application/json")
+        .json(runs))
+}
+
+// Jalankan job sekarang; hanya replica yang memegang lease job tersebut yang menerima trigger
+async fn trigger_job(state: web::Data<AppState>, path: web::Path<String>) -> Result<actix_web::HttpResponse, AppError> {
+    state.scheduler.trigger(&path.into_inner())?;
+    
+    Ok(actix_web::HttpResponse::Accepted()
+        .json(serde_json::json!({"status": "started"})))
+}
+
+async fn pause_job(state: web::Data<AppState>, path: web::Path<String>) -> Result<actix_web::HttpResponse, AppError> {
+    state.scheduler.set_paused(&path.into_inner(), true).await?;
+    
+    Ok(actix_web::HttpResponse::Ok()
+        .json(serde_json::json!({"status": "paused"})))
+}
+
+async fn resume_job(state: web::Data<AppState>, path: web::Path<String>) -> Result<actix_web::HttpResponse, AppError> {
+    state.scheduler.set_paused(&path.into_inner(), false).await?;
+    
+    Ok(actix_web::HttpResponse::Ok()
+        .json(serde_json::json!({"status": "resumed"})))
 }
 
 // WebSocket endpoint
@@ -227,223 +776,144 @@ async fn websocket_endpoint(
     req: actix_web::HttpRequest,
     stream: actix_web::web::Payload,
     state: web::Data<AppState>,
+    web::Query(params): web::Query<WsParams>,
 ) -> actix_web::Result<actix_web::HttpResponse> {
-    use actix_ws::{Message, ProtocolError};
-    use futures_util::{SinkExt, StreamExt};
+    use actix_ws::{CloseCode, CloseReason, Message, ProtocolError};
+    use futures_util::StreamExt;
     
+    // Timeframe divalidasi sebelum upgrade supaya nilai yang salah dijawab 400, bukan koneksi yang langsung ditutup
+    let timeframe = validate_timeframe(params.timeframe.as_deref())?;
     let (response, mut session, mut msg_stream) = actix_ws::handle(&req, stream)?;
     
-    // Subscribe ke broadcast channel
-    let mut rx = state.ws_tx.subscribe();
+    // View awal dipilih lewat query string, contoh: /api/ws?timeframe=weekly.
+    // Setelah itu klien bisa mengganti view & watchlist dengan perintah subscribe/unsubscribe.
+    let limits = state.config.websocket.session_limits();
+    let client = Arc::new(tokio::sync::Mutex::new(ClientSession::new(
+        LeaderboardView::new(Some(timeframe), None, None, None, None, limits.max_window),
+        limits,
+    )));
     
-    // Kirim data leaderboard awal
-    if let Ok(entries) = state.leaderboard_repo.get_leaderboard(100, 0, "roi", "desc").await {
-        let initial_data = serde_json::json!({
-            "type": "initial",
-            "data": entries
-        });
-        
-        if let Err(e) = session.send(Message::Text(initial_data.to_string())).await {
-            log::error!("Failed to send initial data: {:?}", e);
-        }
-    }
+    // Daftar ke publisher sebelum snapshot awal supaya tidak ada refresh yang terlewat
+    let (client_id, mut outbox) = state.publisher.register(client.clone());
+    
+    // Kirim snapshot awal, atau patch kalau klien resume dengan ?since=<seq>
+    let initial = client.lock().await.start(&state.leaderboard_repo, params.since).await;
+    state.publisher.send(client_id, initial);
     
     // Spawn task untuk handle WebSocket
     actix_rt::spawn(async move {
-        let mut interval = actix_rt::time::interval(std::time::Duration::from_secs(30));
-        
-        loop {
+        let close_reason = loop {
             tokio::select! {
-                // Broadcast messages
-                Ok(msg) = rx.recv() => {
-                    if let Err(e) = session.send(Message::Text(msg)).await {
-                        log::error!("Failed to send broadcast message: {:?}", e);
-                        break;
-                    }
-                }
-                
-                // Periodic updates
-                _ = interval.tick() => {
-                    if let Ok(entries) = state.leaderboard_repo.get_leaderboard(100, 0, "roi", "desc").await {
-                        let update_data = serde_json::json!({
-                            "type": "periodic_update",
-                            "data": entries
-                        });
-                        
-                        if let Err(e) = session.send(Message::Text(update_data.to_string())).await {
-                            log::error!("Failed to send periodic update: {:?}", e);
-                            break;
+                // Frame dari publisher (broadcast & balasan perintah)
+                frame = outbox.recv() => {
+                    match frame {
+                        Some(frame) => {
+                            if let Err(e) = session.text(frame).await {
+                                log::error!("Failed to send broadcast message: {:?}", e);
+                                break None;
+                            }
+                        }
+                        // Antrean ditutup publisher: klien terlalu lambat
+                        None => {
+                            break Some(CloseReason {
+                                code: CloseCode::Again,
+                                description: Some("client too slow".to_string()),
+                            });
                         }
                     }
                 }
                 
                 // Client messages
-                Some(msg) = msg_stream.next() => {
+                msg = msg_stream.next() => {
+                    let Some(msg) = msg else {
+                        break None;
+                    };
+                    
                     match msg {
                         Ok(Message::Ping(bytes)) => {
                             if let Err(e) = session.pong(&bytes).await {
                                 log::error!("Failed to send pong: {:?}", e);
-                                break;
+                                break None;
                             }
                         }
+                        // Balasan lewat antrean publisher; kalau antrean penuh, outbox ditutup di iterasi berikutnya
+                        Ok(Message::Text(text)) => {
+                            let messages = client.lock().await.handle_text(&state.leaderboard_repo, &text).await;
+                            state.publisher.send(client_id, messages);
+                        }
                         Ok(Message::Close(_)) => {
                             log::info!("WebSocket connection closed by client");
-                            break;
+                            break None;
                         }
                         Err(ProtocolError::Overflow) => {
                             log::warn!("WebSocket buffer overflow");
-                            break;
+                            break None;
                         }
                         Err(e) => {
                             log::error!("WebSocket error: {:?}", e);
-                            break;
+                            break None;
                         }
                         _ => {} // Ignore other messages
                     }
                 }
             }
-        }
+        };
         
+        state.publisher.unregister(client_id);
+        let _ = session.close(close_reason).await;
         log::info!("WebSocket connection ended");
     });
     
     Ok(response)
 }
 
-// Metrics endpoint untuk monitoring
-async fn metrics_endpoint(state: web::Data<AppState>) -> actix_web::HttpResponse {
-    use prometheus::{Encoder, TextEncoder};
-    
-    // Custom metrics
-    let leaderboard_requests = prometheus::IntCounter::new(
-        "leaderboard_requests_total",
-        "Total number of leaderboard requests"
-    ).unwrap();
+// Metrics endpoint untuk monitoring; gauge yang dihitung dari state lain diperbarui saat scrape
+async fn metrics_endpoint(state: web::Data<AppState>) -> Result<actix_web::HttpResponse, AppError> {
+    metrics().observe_pool(&state.pool);
+    metrics().websocket_sessions.set(state.publisher.subscriber_count() as i64);
     
-    let db_connection_gauge = prometheus::Gauge::new(
-        "db_connections",
-        "Current database connections"
-    ).unwrap();
+    let buffer = metrics()
+        .encode()
+        .map_err(|e| AppError::Internal(format!("failed to encode metrics: {}", e)))?;
     
-    // Update metrics
-    leaderboard_requests.inc();
-    
-    if let Ok(stats) = state.pool.acquire().await {
-        db_connection_gauge.set(stats.size() as f64);
-    }
-    
-    // Encode metrics
-    let encoder = TextEncoder::new();
-    let mut buffer = Vec::new();
-    
-    let metric_families = prometheus::gather();
-    encoder.encode(&metric_families, &mut buffer).unwrap();
-    
-    actix_web::HttpResponse::Ok()
+    Ok(actix_web::HttpResponse::Ok()
         .content_type(prometheus::TEXT_FORMAT)
-        .body(buffer)
+        .body(buffer))
 }
 
-// Background task untuk refresh materialized view
-async fn refresh_leaderboard_task(
-    repo: Arc<LeaderboardRepository>,
-    cache: Arc<LeaderboardCache>,
-    ws_tx: broadcast::Sender<String>,
-) {
-    let mut interval = tokio::time::interval(std::time::Duration::from_secs(60)); // Refresh setiap 1 menit
-    
-    loop {
-        interval.tick().await;
-        
-        log::info!("Refreshing materialized view...");
-        
-        match repo.refresh_materialized_view().await {
-            Ok(_) => {
-                log::info!("Materialized view refreshed successfully");
-                
-                // Clear cache
-                if let Err(e) = cache.clear_pattern("leaderboard:*").await {
-                    log::error!("Failed to clear cache: {:?}", e);
-                }
-                
-                // Broadcast update
-                let _ = ws_tx.send("leaderboard_updated".to_string());
-            }
-            Err(e) => {
-                log::error!("Failed to refresh materialized view: {:?}", e);
-            }
-        }
-    }
+// Jadwal sudah divalidasi saat konfigurasi dimuat
+fn job_spec(config: &Config, name: &str) -> JobSpec {
+    let spec = config.jobs.schedule(name).unwrap_or_default();
+    JobSpec::new(spec)
+        .unwrap_or_else(|e| panic!("Invalid schedule for job {}: {}", name, e))
+        .with_jitter(std::time::Duration::from_secs(config.jobs.jitter_secs))
+        .with_retries(
+            config.jobs.max_attempts,
+            std::time::Duration::from_secs(config.jobs.retry_backoff_secs),
+        )
 }
 
-// Background task untuk update data trader dari sumber eksternal
-async fn update_trader_data_task(pool: sqlx::PgPool) {
-    let mut interval = tokio::time::interval(std::time::Duration::from_secs(300)); // Update setiap 5 menit
+// Sumber trade diatur di bagian [ingestion] konfigurasi:
+// - file_path (INGEST_FILE_PATH): file JSON Lines berisi fill & posisi
+// - mock_wallets (MOCK_WALLETS): daftar wallet untuk mock exchange yang membangkitkan fill
+// cost_basis (COST_BASIS_METHOD: fifo, lifo, average) menentukan cara menghitung realized pnl
+fn build_ingestion_pipeline(config: &Config, trade_repo: Arc<TradeRepository>) -> IngestionPipeline {
+    let cost_basis = CostBasisMethod::parse(&config.ingestion.cost_basis).unwrap_or(CostBasisMethod::Fifo);
+    let mut pipeline = IngestionPipeline::new(trade_repo).with_cost_basis(cost_basis);
+    
+    if let Some(path) = &config.ingestion.file_path {
+        log::info!("Ingesting trades from file {}", path);
+        pipeline = pipeline.with_source(Arc::new(FileTradeSource::new("file", path.clone())));
+    }
     
-    loop {
-        interval.tick().await;
-        
-        log::info!("Updating trader data from external sources...");
-        
-        // Di sini Anda akan mengintegrasikan dengan:
-        // 1. Smart contract events (Ethereum/other chains)
-        // 2. Exchange APIs (Binance, FTX, etc.)
-        // 3. Trading platform APIs
+    if !config.ingestion.mock_wallets.is_empty() {
+        let wallets = config.ingestion.mock_wallets.clone();
         
-        // Contoh: Update dari mock data
-        match update_trader_performance(&pool).await {
-            Ok(updated_count) => {
-                log::info!("Updated {} trader records", updated_count);
-            }
-            Err(e) => {
-                log::error!("Failed to update trader data: {:?}", e);
-            }
-        }
+        log::info!("Simulating trades for {} wallets on mock exchange", wallets.len());
+        pipeline = pipeline.with_source(Arc::new(MockExchange::simulated("mock", wallets, config.ingestion.mock_seed)));
     }
-}
-
-// Fungsi helper untuk update trader performance
-async fn update_trader_performance(pool: &sqlx::PgPool) -> Result<i64, sqlx::Error> {
-    // Ini adalah contoh implementasi
-    // Di production, Anda akan mengambil data dari:
-    // 1. Blockchain RPC
-    // 2. Trading APIs
-    // 3. DeFi protocols
-    
-    let result = sqlx::query!(
-        r#"
-        WITH updated_traders AS (
-            SELECT 
-                t.id,
-                t.wallet_address,
-                -- Simulasi update data
-                (RANDOM() * 100000)::DECIMAL as new_account_value,
-                (RANDOM() * 50000 - 25000)::DECIMAL as new_pnl,
-                (RANDOM() * 200 - 100)::DECIMAL as new_roi,
-                (RANDOM() * 1000000)::DECIMAL as new_volume
-            FROM traders t
-            WHERE t.last_active > NOW() - INTERVAL '7 days'
-            LIMIT 1000
-        )
-        INSERT INTO trader_performance 
-            (trader_id, account_value, pnl, roi, volume, timeframe, calculated_at)
-        SELECT 
-            id, 
-            new_account_value,
-            new_pnl,
-            new_roi,
-            new_volume,
-            'daily',
-            NOW()
-        FROM updated_traders
-        ON CONFLICT DO NOTHING
-        RETURNING id
-        "#,
-    )
-    .fetch_all(pool)
-    .await?;
     
-    Ok(result.len() as i64)
+    pipeline
 }
 
 // Struct untuk query parameters REST API
@@ -453,113 +923,80 @@ struct LeaderboardParams {
     offset: Option<i32>,
     sort_by: Option<String>,
     sort_order: Option<String>,
+    timeframe: Option<String>,
 }
 
-// Implementasi untuk cache module
-mod cache {
-    use redis::{Client, AsyncCommands};
-    use serde::{Serialize, de::DeserializeOwned};
-    use std::time::Duration;
-    
-    pub struct LeaderboardCache {
-        client: Client,
-    }
-    
-    impl LeaderboardCache {
-        pub fn new(client: Client) -> Self {
-            Self { client }
-        }
-        
-        pub async fn get_cached_leaderboard<T: DeserializeOwned>(
-            &self, 
-            key: &str
-        ) -> Option<T> {
-            let mut conn = match self.client.get_async_connection().await {
-                Ok(conn) => conn,
-                Err(e) => {
-                    log::error!("Failed to connect to Redis: {:?}", e);
-                    return None;
-                }
-            };
-            
-            let data: String = match conn.get(key).await {
-                Ok(data) => data,
-                Err(e) => {
-                    log::debug!("Cache miss for key {}: {:?}", key, e);
-                    return None;
-                }
-            };
-            
-            serde_json::from_str(&data).ok()
-        }
-        
-        pub async fn cache_leaderboard<T: Serialize>(
-            &self, 
-            key: &str, 
-            data: &T,
-            ttl_seconds: usize
-        ) {
-            let mut conn = match self.client.get_async_connection().await {
-                Ok(conn) => conn,
-                Err(e) => {
-                    log::error!("Failed to connect to Redis: {:?}", e);
-                    return;
-                }
-            };
-            
-            let json = match serde_json::to_string(data) {
-                Ok(json) => json,
-                Err(e) => {
-                    log::error!("Failed to serialize data: {:?}", e);
-                    return;
-                }
-            };
-            
-            if let Err(e) = conn.set_ex(key, json, ttl_seconds).await {
-                log::error!("Failed to cache data: {:?}", e);
-            }
-        }
-        
-        pub async fn clear_pattern(&self, pattern: &str) {
-            let mut conn = match self.client.get_async_connection().await {
-                Ok(conn) => conn,
-                Err(e) => {
-                    log::error!("Failed to connect to Redis: {:?}", e);
-                    return;
-                }
-            };
-            
-            let keys: Vec<String> = match conn.keys(pattern).await {
-                Ok(keys) => keys,
-                Err(e) => {
-                    log::error!("Failed to get keys: {:?}", e);
-                    return;
-                }
-            };
-            
-            if !keys.is_empty() {
-                if let Err(e) = conn.del(keys).await {
-                    log::error!("Failed to delete keys: {:?}", e);
-                }
-            }
-        }
-        
-        pub async fn increment_counter(&self, key: &str) -> Option<i64> {
-            let mut conn = match self.client.get_async_connection().await {
-                Ok(conn) => conn,
-                Err(e) => {
-                    log::error!("Failed to connect to Redis: {:?}", e);
-                    return None;
-                }
-            };
-            
-            match conn.incr(key, 1).await {
-                Ok(val) => Some(val),
-                Err(e) => {
-                    log::error!("Failed to increment counter: {:?}", e);
-                    None
-                }
-            }
-        }
-    }
-}
\ No newline at end of file
+// Struct untuk query parameters WebSocket; `since` = seq terakhir yang diterima klien
+#[derive(serde::Deserialize)]
+struct WsParams {
+    timeframe: Option<String>,
+    since: Option<i64>,
+}
+
+// Struct untuk query parameters riwayat job
+#[derive(serde::Deserialize)]
+struct JobRunsParams {
+    limit: Option<i64>,
+}
+
+// Struct untuk query parameters movers API
+#[derive(serde::Deserialize)]
+struct MoversParams {
+    timeframe: Option<String>,
+    window_hours: Option<i64>,
+    limit: Option<i32>,
+}
+
+// Struct untuk query parameters cursor pagination
+#[derive(serde::Deserialize)]
+struct PageParams {
+    limit: Option<i32>,
+    cursor: Option<String>,
+    sort_by: Option<String>,
+    sort_order: Option<String>,
+    timeframe: Option<String>,
+}
+
+// Struct untuk query parameters snapshot API
+#[derive(serde::Deserialize)]
+struct SnapshotParams {
+    as_of: Option<chrono::DateTime<chrono::Utc>>,
+    snapshot_id: Option<i64>,
+    timeframe: Option<String>,
+    limit: Option<i32>,
+    offset: Option<i32>,
+}
+
+#[derive(serde::Deserialize)]
+struct SnapshotListParams {
+    limit: Option<i32>,
+    before: Option<i64>,
+}
+
+// Struct untuk query parameters history API
+#[derive(serde::Deserialize)]
+struct HistoryParams {
+    timeframe: Option<String>,
+    from: Option<chrono::DateTime<chrono::Utc>>,
+    to: Option<chrono::DateTime<chrono::Utc>>,
+    bucket: Option<String>,
+}
+
+// Struct untuk query parameters snapshot history API
+#[derive(serde::Deserialize)]
+struct TraderSnapshotsParams {
+    timeframe: Option<String>,
+    limit: Option<i32>,
+}
+
+#[derive(serde::Deserialize)]
+struct SiweVerifyRequest {
+    message: String,
+    signature: String,
+}
+
+#[derive(serde::Deserialize)]
+struct ProfileUpdateRequest {
+    display_name: Option<String>,
+    opted_out: Option<bool>,
+}
@@ -0,0 +1,173 @@
+// src/memory_cache.rs
+// Tier cache di memori proses (LRU + TTL) di depan Redis. Yang disimpan adalah body response
+// yang sudah di-serialize, jadi hit cukup satu lookup tanpa round-trip Redis maupun parsing JSON.
+use actix_web::web::Bytes;
+use lru::LruCache;
+use std::num::NonZeroUsize;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use crate::metrics::metrics;
+
+struct Entry {
+    body: Bytes,
+    expires_at: Instant,
+}
+
+pub struct MemoryCache {
+    // None = tier dimatikan (kapasitas 0)
+    entries: Option<Mutex<LruCache<String, Entry>>>,
+    // Batas umur entry; membatasi data basi kalau event invalidasi dari replica lain tidak sampai
+    max_ttl: Duration,
+}
+
+impl MemoryCache {
+    pub fn new(capacity: usize, max_ttl: Duration) -> Self {
+        Self {
+            entries: NonZeroUsize::new(capacity).map(|capacity| Mutex::new(LruCache::new(capacity))),
+            max_ttl,
+        }
+    }
+    
+    pub fn disabled() -> Self {
+        Self::new(0, Duration::ZERO)
+    }
+    
+    pub fn get(&self, key: &str) -> Option<Bytes> {
+        let mut entries = self.entries.as_ref()?.lock().unwrap();
+        
+        match entries.get(key) {
+            Some(entry) if entry.expires_at > Instant::now() => Some(entry.body.clone()),
+            Some(_) => {
+                entries.pop(key);
+                metrics().cache_memory_entries.set(entries.len() as i64);
+                None
+            }
+            None => None,
+        }
+    }
+    
+    // Entry paling lama tidak dipakai dibuang kalau kapasitas penuh
+    pub fn insert(&self, key: &str, body: Bytes, ttl: Duration) {
+        let Some(entries) = &self.entries else {
+            return;
+        };
+        let ttl = ttl.min(self.max_ttl);
+        if ttl.is_zero() {
+            return;
+        }
+        
+        let mut entries = entries.lock().unwrap();
+        entries.put(key.to_string(), Entry {
+            body,
+            expires_at: Instant::now() + ttl,
+        });
+        metrics().cache_memory_entries.set(entries.len() as i64);
+    }
+    
+    // Jumlah entry yang dihapus
+    pub fn invalidate_prefix(&self, prefix: &str) -> usize {
+        let Some(entries) = &self.entries else {
+            return 0;
+        };
+        let mut entries = entries.lock().unwrap();
+        
+        let keys: Vec<String> = entries
+            .iter()
+            .filter(|(key, _)| key.starts_with(prefix))
+            .map(|(key, _)| key.clone())
+            .collect();
+        for key in &keys {
+            entries.pop(key);
+        }
+        metrics().cache_memory_entries.set(entries.len() as i64);
+        keys.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread::sleep;
+    
+    const LONG: Duration = Duration::from_secs(60);
+    
+    fn body(value: &'static str) -> Bytes {
+        Bytes::from_static(value.as_bytes())
+    }
+    
+    #[test]
+    fn get_returns_inserted_body() {
+        let cache = MemoryCache::new(4, LONG);
+        cache.insert("leaderboard:daily", body("[1]"), LONG);
+        assert_eq!(cache.get("leaderboard:daily"), Some(body("[1]")));
+        assert_eq!(cache.get("leaderboard:weekly"), None);
+    }
+    
+    #[test]
+    fn entries_expire_after_ttl() {
+        let cache = MemoryCache::new(4, LONG);
+        cache.insert("short", body("a"), Duration::from_millis(20));
+        cache.insert("long", body("b"), LONG);
+        
+        sleep(Duration::from_millis(30));
+        assert_eq!(cache.get("short"), None);
+        assert_eq!(cache.get("long"), Some(body("b")));
+        // Entry kedaluwarsa langsung dibuang, bukan hanya disembunyikan
+        assert_eq!(cache.entries.as_ref().unwrap().lock().unwrap().len(), 1);
+    }
+    
+    #[test]
+    fn ttl_is_capped_by_max_ttl() {
+        let cache = MemoryCache::new(4, Duration::from_millis(20));
+        cache.insert("key", body("a"), LONG);
+        assert_eq!(cache.get("key"), Some(body("a")));
+        
+        sleep(Duration::from_millis(30));
+        assert_eq!(cache.get("key"), None);
+    }
+    
+    #[test]
+    fn zero_ttl_is_not_stored() {
+        let cache = MemoryCache::new(4, LONG);
+        cache.insert("key", body("a"), Duration::ZERO);
+        assert_eq!(cache.get("key"), None);
+    }
+    
+    #[test]
+    fn least_recently_used_entry_is_evicted_at_capacity() {
+        let cache = MemoryCache::new(2, LONG);
+        cache.insert("a", body("a"), LONG);
+        cache.insert("b", body("b"), LONG);
+        // "a" dipakai lagi, jadi "b" yang paling lama tidak dipakai
+        assert!(cache.get("a").is_some());
+        
+        cache.insert("c", body("c"), LONG);
+        assert_eq!(cache.get("a"), Some(body("a")));
+        assert_eq!(cache.get("b"), None);
+        assert_eq!(cache.get("c"), Some(body("c")));
+    }
+    
+    #[test]
+    fn invalidate_prefix_removes_matching_keys() {
+        let cache = MemoryCache::new(8, LONG);
+        cache.insert("leaderboard:daily:roi", body("1"), LONG);
+        cache.insert("leaderboard:weekly:pnl", body("2"), LONG);
+        cache.insert("trader:0xabc", body("3"), LONG);
+        
+        assert_eq!(cache.invalidate_prefix("leaderboard:"), 2);
+        assert_eq!(cache.get("leaderboard:daily:roi"), None);
+        assert_eq!(cache.get("leaderboard:weekly:pnl"), None);
+        assert_eq!(cache.get("trader:0xabc"), Some(body("3")));
+        assert_eq!(cache.invalidate_prefix("leaderboard:"), 0);
+    }
+    
+    #[test]
+    fn disabled_tier_stores_nothing() {
+        for cache in [MemoryCache::disabled(), MemoryCache::new(0, LONG)] {
+            assert!(cache.entries.is_none());
+            cache.insert("key", body("a"), LONG);
+            assert_eq!(cache.get("key"), None);
+            assert_eq!(cache.invalidate_prefix(""), 0);
+        }
+    }
+}
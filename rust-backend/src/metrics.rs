@@ -0,0 +1,241 @@
+// src/metrics.rs
+// Metric Prometheus yang dibuat & didaftarkan sekali saat startup, lalu dipakai ulang di seluruh aplikasi.
+// Label dibatasi ke nilai yang jumlahnya terbatas (pola route, nama source, keluarga key cache)
+// supaya jumlah time series tidak meledak.
+use prometheus::{
+    Encoder, Histogram, HistogramOpts, HistogramVec, IntCounter, IntCounterVec, IntGauge, Opts, Registry, TextEncoder,
+};
+use prometheus::core::Collector;
+use sqlx::PgPool;
+use std::sync::OnceLock;
+use std::time::Duration;
+
+// Bucket latensi request (detik)
+const LATENCY_BUCKETS: &[f64] = &[0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0];
+
+// Refresh materialized view bisa jauh lebih lama dari request biasa
+const REFRESH_BUCKETS: &[f64] = &[0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0, 120.0];
+
+// Method lain (extension method dari klien) dicatat sebagai "OTHER"
+const HTTP_METHODS: [&str; 7] = ["GET", "HEAD", "POST", "PUT", "PATCH", "DELETE", "OPTIONS"];
+
+// Keluarga key cache yang dikenal; perbarui kalau ada prefix key baru
+const CACHE_FAMILIES: [&str; 1] = ["leaderboard"];
+
+static METRICS: OnceLock<Metrics> = OnceLock::new();
+
+pub fn metrics() -> &'static Metrics {
+    METRICS.get_or_init(Metrics::new)
+}
+
+pub struct Metrics {
+    registry: Registry,
+    pub http_requests: IntCounterVec,
+    pub http_request_duration: HistogramVec,
+    pub graphql_operation_duration: HistogramVec,
+    pub cache_requests: IntCounterVec,
+    pub cache_circuit_open: IntGauge,
+    pub cache_memory_entries: IntGauge,
+    pub db_pool_connections: IntGauge,
+    pub db_pool_idle_connections: IntGauge,
+    pub db_pool_max_connections: IntGauge,
+    pub leaderboard_refresh_duration: Histogram,
+    pub leaderboard_refresh_failures: IntCounter,
+    pub websocket_sessions: IntGauge,
+    pub websocket_lagged_events: IntCounter,
+    pub websocket_slow_client_drops: IntCounter,
+    pub ingest_fills: IntCounterVec,
+    pub ingest_source_failures: IntCounterVec,
+    pub performance_rows: IntCounter,
+    pub performance_failures: IntCounter,
+}
+
+impl Metrics {
+    fn new() -> Self {
+        let registry = Registry::new();
+        
+        Self {
+            http_requests: register(&registry, IntCounterVec::new(
+                Opts::new("http_requests_total", "HTTP requests by route pattern and status"),
+                &["method", "route", "status"],
+            )),
+            http_request_duration: register(&registry, HistogramVec::new(
+                HistogramOpts::new("http_request_duration_seconds", "HTTP request latency by route pattern and status")
+                    .buckets(LATENCY_BUCKETS.to_vec()),
+                &["method", "route", "status"],
+            )),
+            graphql_operation_duration: register(&registry, HistogramVec::new(
+                HistogramOpts::new("graphql_operation_duration_seconds", "GraphQL operation latency")
+                    .buckets(LATENCY_BUCKETS.to_vec()),
+                &["operation", "result"],
+            )),
+            cache_requests: register(&registry, IntCounterVec::new(
+                Opts::new("cache_requests_total", "Cache lookups by key family and result (memory_hit, hit, miss, skipped, error)"),
+                &["family", "result"],
+            )),
+            cache_circuit_open: register(&registry, IntGauge::new(
+                "cache_circuit_open",
+                "1 while the Redis circuit breaker is open and the cache is bypassed",
+            )),
+            cache_memory_entries: register(&registry, IntGauge::new(
+                "cache_memory_entries",
+                "Entries in the in-process cache tier",
+            )),
+            db_pool_connections: register(&registry, IntGauge::new(
+                "db_pool_connections",
+                "Open database connections (idle and in use)",
+            )),
+            db_pool_idle_connections: register(&registry, IntGauge::new(
+                "db_pool_idle_connections",
+                "Idle database connections",
+            )),
+            db_pool_max_connections: register(&registry, IntGauge::new(
+                "db_pool_max_connections",
+                "Configured maximum database connections",
+            )),
+            leaderboard_refresh_duration: register(&registry, Histogram::with_opts(
+                HistogramOpts::new("leaderboard_refresh_duration_seconds", "Materialized view refresh duration")
+                    .buckets(REFRESH_BUCKETS.to_vec()),
+            )),
+            leaderboard_refresh_failures: register(&registry, IntCounter::new(
+                "leaderboard_refresh_failures_total",
+                "Failed materialized view refreshes",
+            )),
+            websocket_sessions: register(&registry, IntGauge::new(
+                "websocket_sessions_active",
+                "Open /api/ws sessions",
+            )),
+            websocket_lagged_events: register(&registry, IntCounter::new(
+                "websocket_broadcast_lagged_events_total",
+                "Leaderboard events skipped because the publisher lagged behind the broadcast channel",
+            )),
+            websocket_slow_client_drops: register(&registry, IntCounter::new(
+                "websocket_slow_client_drops_total",
+                "WebSocket clients disconnected because their send queue was full",
+            )),
+            ingest_fills: register(&registry, IntCounterVec::new(
+                Opts::new("ingest_fills_total", "Ingested fills by source and result (received, inserted, rejected)"),
+                &["source", "result"],
+            )),
+            ingest_source_failures: register(&registry, IntCounterVec::new(
+                Opts::new("ingest_source_failures_total", "Ingestion runs that failed for a source"),
+                &["source"],
+            )),
+            performance_rows: register(&registry, IntCounter::new(
+                "performance_rows_written_total",
+                "trader_performance rows written by recomputation",
+            )),
+            performance_failures: register(&registry, IntCounter::new(
+                "performance_recompute_failures_total",
+                "Traders whose performance recomputation failed",
+            )),
+            registry,
+        }
+    }
+    
+    pub fn observe_http(&self, method: &str, route: Option<&str>, status: u16, elapsed: Duration) {
+        // Request yang tidak cocok dengan route mana pun digabung supaya path acak tidak jadi label
+        let route = route.unwrap_or("unmatched");
+        let status = status.to_string();
+        let labels = [allowed(&HTTP_METHODS, method, "OTHER"), route, status.as_str()];
+        
+        self.http_requests.with_label_values(&labels).inc();
+        self.http_request_duration.with_label_values(&labels).observe(elapsed.as_secs_f64());
+    }
+    
+    // `operation` harus berasal dari daftar tetap (lihat schema::operation_label), bukan langsung dari klien
+    pub fn observe_graphql(&self, operation: &'static str, ok: bool, elapsed: Duration) {
+        self.graphql_operation_duration
+            .with_label_values(&[operation, if ok { "ok" } else { "error" }])
+            .observe(elapsed.as_secs_f64());
+    }
+    
+    // Keluarga key = segmen pertama, contoh "leaderboard:daily:100:0:roi:desc" -> "leaderboard"
+    pub fn observe_cache(&self, key: &str, result: &str) {
+        let family = allowed(&CACHE_FAMILIES, key.split(':').next().unwrap_or_default(), "other");
+        self.cache_requests.with_label_values(&[family, result]).inc();
+    }
+    
+    // Statistik pool dibaca saat scrape, tanpa mengambil koneksi
+    pub fn observe_pool(&self, pool: &PgPool) {
+        self.db_pool_connections.set(pool.size() as i64);
+        self.db_pool_idle_connections.set(pool.num_idle() as i64);
+        self.db_pool_max_connections.set(pool.options().get_max_connections() as i64);
+    }
+    
+    pub fn encode(&self) -> Result<Vec<u8>, prometheus::Error> {
+        let mut buffer = Vec::new();
+        TextEncoder::new().encode(&self.registry.gather(), &mut buffer)?;
+        Ok(buffer)
+    }
+}
+
+// Nilai label dari daftar tetap; selain itu `fallback`
+fn allowed(values: &[&'static str], value: &str, fallback: &'static str) -> &'static str {
+    values.iter().find(|allowed| **allowed == value).copied().unwrap_or(fallback)
+}
+
+fn register<T: Collector + Clone + 'static>(registry: &Registry, metric: Result<T, prometheus::Error>) -> T {
+    let metric = metric.expect("invalid metric definition");
+    registry
+        .register(Box::new(metric.clone()))
+        .expect("metric registered twice");
+    metric
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn http_count(method: &str, route: &str, status: &str) -> u64 {
+        metrics().http_requests.with_label_values(&[method, route, status]).get()
+    }
+    
+    fn cache_count(family: &str, result: &str) -> u64 {
+        metrics().cache_requests.with_label_values(&[family, result]).get()
+    }
+    
+    fn exported() -> String {
+        String::from_utf8(metrics().encode().unwrap()).unwrap()
+    }
+    
+    #[test]
+    fn http_uses_route_pattern_and_status() {
+        let before = http_count("GET", "/api/test/{wallet}", "200");
+        metrics().observe_http("GET", Some("/api/test/{wallet}"), 200, Duration::from_millis(3));
+        assert_eq!(http_count("GET", "/api/test/{wallet}", "200"), before + 1);
+    }
+    
+    #[test]
+    fn unmatched_requests_share_one_route_label() {
+        let before = http_count("POST", "unmatched", "404");
+        metrics().observe_http("POST", None, 404, Duration::from_millis(1));
+        metrics().observe_http("POST", None, 404, Duration::from_millis(1));
+        assert_eq!(http_count("POST", "unmatched", "404"), before + 2);
+    }
+    
+    #[test]
+    fn unknown_http_methods_are_folded() {
+        let before = http_count("OTHER", "/api/test/brew", "405");
+        metrics().observe_http("BREWCOFFEE", Some("/api/test/brew"), 405, Duration::from_millis(1));
+        assert_eq!(http_count("OTHER", "/api/test/brew", "405"), before + 1);
+        assert!(!exported().contains("BREWCOFFEE"));
+    }
+    
+    #[test]
+    fn cache_key_is_reduced_to_its_family() {
+        let before = cache_count("leaderboard", "memory_hit");
+        metrics().observe_cache("leaderboard:daily:100:0:roi:desc", "memory_hit");
+        assert_eq!(cache_count("leaderboard", "memory_hit"), before + 1);
+        assert!(!exported().contains("daily:100"));
+    }
+    
+    #[test]
+    fn unknown_cache_families_are_folded() {
+        let before = cache_count("other", "miss");
+        metrics().observe_cache("0xdeadbeefcafe:whatever", "miss");
+        metrics().observe_cache("", "miss");
+        assert_eq!(cache_count("other", "miss"), before + 2);
+        assert!(!exported().contains("0xdeadbeefcafe"));
+    }
+}
@@ -1,26 +1,307 @@
 // src/models.rs
 use serde::{Deserialize, Serialize};
-use async_graphql::SimpleObject;
+use async_graphql::{Enum, SimpleObject};
 use bigdecimal::BigDecimal;
 use chrono::{DateTime, Utc};
+use sha3::{Digest, Keccak256};
+use std::fmt;
+use crate::error::AppError;
 
-#[derive(Debug, Clone, Serialize, Deserialize, SimpleObject)]
+// Timeframe yang punya ranking sendiri di realtime_leaderboard
+pub const TIMEFRAMES: [&str; 4] = ["daily", "weekly", "monthly", "all_time"];
+
+// Normalisasi nama timeframe; alias dari frontend (24h, 7d, 30d, all) juga diterima.
+// Input klien sudah divalidasi lewat validate_timeframe, jadi di sini nilai lain jatuh ke daily.
+pub fn parse_timeframe(value: &str) -> &'static str {
+    timeframe_alias(value).unwrap_or("daily")
+}
+
+// Timeframe dari klien (REST, GraphQL, WebSocket): default daily, nama yang tidak dikenal ditolak
+pub fn validate_timeframe(value: Option<&str>) -> Result<&'static str, AppError> {
+    let Some(value) = value else {
+        return Ok("daily");
+    };
+    timeframe_alias(value).ok_or_else(|| {
+        AppError::invalid(format!("Unknown timeframe '{}', expected one of: {}", value, TIMEFRAMES.join(", ")))
+    })
+}
+
+// Ukuran bucket riwayat performa (argumen date_trunc di Postgres)
+pub const HISTORY_BUCKETS: [&str; 5] = ["minute", "hour", "day", "week", "month"];
+
+// Bucket dari klien: default hour, nama yang tidak dikenal ditolak seperti timeframe
+pub fn validate_bucket(value: Option<&str>) -> Result<&'static str, AppError> {
+    let Some(value) = value else {
+        return Ok("hour");
+    };
+    HISTORY_BUCKETS
+        .iter()
+        .find(|bucket| bucket.eq_ignore_ascii_case(value))
+        .copied()
+        .ok_or_else(|| {
+            AppError::invalid(format!("Unknown bucket '{}', expected one of: {}", value, HISTORY_BUCKETS.join(", ")))
+        })
+}
+
+fn timeframe_alias(value: &str) -> Option<&'static str> {
+    match value.to_lowercase().as_str() {
+        "daily" | "24h" => Some("daily"),
+        "weekly" | "7d" => Some("weekly"),
+        "monthly" | "30d" => Some("monthly"),
+        "all_time" | "all" => Some("all_time"),
+        _ => None,
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, Enum)]
+#[serde(rename_all = "lowercase")]
+pub enum Chain {
+    Evm,
+    Solana,
+    Bitcoin,
+}
+
+impl Chain {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Chain::Evm => "evm",
+            Chain::Solana => "solana",
+            Chain::Bitcoin => "bitcoin",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WalletAddressError {
+    Empty,
+    UnknownFormat,
+    InvalidFormat { chain: Chain, reason: String },
+    InvalidChecksum,
+}
+
+impl WalletAddressError {
+    // Kode stabil untuk response REST/GraphQL
+    pub fn code(&self) -> &'static str {
+        match self {
+            WalletAddressError::InvalidChecksum => "INVALID_WALLET_CHECKSUM",
+            _ => "INVALID_WALLET_ADDRESS",
+        }
+    }
+}
+
+impl fmt::Display for WalletAddressError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WalletAddressError::Empty => write!(f, "wallet address is empty"),
+            WalletAddressError::UnknownFormat => write!(f, "wallet address format is not recognized"),
+            WalletAddressError::InvalidFormat { chain, reason } => {
+                write!(f, "invalid {} address: {}", chain.as_str(), reason)
+            }
+            WalletAddressError::InvalidChecksum => write!(f, "wallet address has an invalid EIP-55 checksum"),
+        }
+    }
+}
+
+impl std::error::Error for WalletAddressError {}
+
+// Alamat wallet yang sudah divalidasi dan dalam bentuk kanonik:
+// - EVM: checksum EIP-55
+// - Solana: base58 apa adanya (case-sensitive)
+// - Bitcoin: bech32/bech32m huruf kecil
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct WalletAddress {
+    chain: Chain,
+    address: String,
+}
+
+impl WalletAddress {
+    // `chain = None` berarti chain dideteksi dari format alamat
+    pub fn parse(input: &str, chain: Option<Chain>) -> Result<Self, WalletAddressError> {
+        let input = input.trim();
+        if input.is_empty() {
+            return Err(WalletAddressError::Empty);
+        }
+        
+        let chain = match chain {
+            Some(chain) => chain,
+            None => detect_chain(input).ok_or(WalletAddressError::UnknownFormat)?,
+        };
+        
+        let address = match chain {
+            Chain::Evm => normalize_evm(input)?,
+            Chain::Solana => normalize_solana(input)?,
+            Chain::Bitcoin => normalize_bitcoin(input)?,
+        };
+        
+        Ok(Self { chain, address })
+    }
+    
+    pub fn chain(&self) -> Chain {
+        self.chain
+    }
+    
+    pub fn as_str(&self) -> &str {
+        &self.address
+    }
+}
+
+impl fmt::Display for WalletAddress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.address)
+    }
+}
+
+fn detect_chain(input: &str) -> Option<Chain> {
+    let lower = input.to_lowercase();
+    if lower.starts_with("0x") {
+        Some(Chain::Evm)
+    } else if lower.starts_with("bc1") || lower.starts_with("tb1") {
+        Some(Chain::Bitcoin)
+    } else if (32..=44).contains(&input.len()) {
+        Some(Chain::Solana)
+    } else {
+        None
+    }
+}
+
+fn normalize_evm(input: &str) -> Result<String, WalletAddressError> {
+    let invalid = |reason: &str| WalletAddressError::InvalidFormat {
+        chain: Chain::Evm,
+        reason: reason.to_string(),
+    };
+    
+    let hex_part = input
+        .strip_prefix("0x")
+        .or_else(|| input.strip_prefix("0X"))
+        .ok_or_else(|| invalid("must start with 0x"))?;
+    if hex_part.len() != 40 || !hex_part.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(invalid("must be 40 hex characters"));
+    }
+    
+    let checksummed = eip55_checksum(hex_part);
+    
+    // Huruf campuran berarti pengirim memakai checksum, jadi harus cocok persis
+    let has_lower = hex_part.chars().any(|c| c.is_ascii_lowercase());
+    let has_upper = hex_part.chars().any(|c| c.is_ascii_uppercase());
+    if has_lower && has_upper && checksummed[2..] != *hex_part {
+        return Err(WalletAddressError::InvalidChecksum);
+    }
+    
+    Ok(checksummed)
+}
+
+pub fn eip55_checksum(hex_part: &str) -> String {
+    let lower = hex_part.to_lowercase();
+    let hash = Keccak256::digest(lower.as_bytes());
+    
+    let checksummed: String = lower
+        .chars()
+        .enumerate()
+        .map(|(i, c)| {
+            let nibble = (hash[i / 2] >> if i % 2 == 0 { 4 } else { 0 }) & 0x0f;
+            if c.is_ascii_alphabetic() && nibble >= 8 {
+                c.to_ascii_uppercase()
+            } else {
+                c
+            }
+        })
+        .collect();
+    
+    format!("0x{}", checksummed)
+}
+
+fn normalize_solana(input: &str) -> Result<String, WalletAddressError> {
+    let bytes = bs58::decode(input).into_vec().map_err(|e| WalletAddressError::InvalidFormat {
+        chain: Chain::Solana,
+        reason: e.to_string(),
+    })?;
+    if bytes.len() != 32 {
+        return Err(WalletAddressError::InvalidFormat {
+            chain: Chain::Solana,
+            reason: "public key must be 32 bytes".to_string(),
+        });
+    }
+    Ok(input.to_string())
+}
+
+fn normalize_bitcoin(input: &str) -> Result<String, WalletAddressError> {
+    use bech32::FromBase32;
+    
+    let invalid = |reason: String| WalletAddressError::InvalidFormat {
+        chain: Chain::Bitcoin,
+        reason,
+    };
+    
+    let (hrp, data, variant) = bech32::decode(input).map_err(|e| invalid(e.to_string()))?;
+    if hrp != "bc" && hrp != "tb" {
+        return Err(invalid(format!("unknown network prefix '{}'", hrp)));
+    }
+    
+    let (version, program) = data.split_first().ok_or_else(|| invalid("missing witness version".to_string()))?;
+    let version = version.to_u8();
+    let program = Vec::<u8>::from_base32(program).map_err(|e| invalid(e.to_string()))?;
+    
+    // BIP-173: segwit v0 memakai bech32; BIP-350: v1+ memakai bech32m
+    let expected_variant = if version == 0 { bech32::Variant::Bech32 } else { bech32::Variant::Bech32m };
+    if version > 16 || variant != expected_variant {
+        return Err(invalid("wrong checksum variant for witness version".to_string()));
+    }
+    if program.len() < 2 || program.len() > 40 || (version == 0 && program.len() != 20 && program.len() != 32) {
+        return Err(invalid("invalid witness program length".to_string()));
+    }
+    
+    Ok(input.to_lowercase())
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, SimpleObject, sqlx::FromRow)]
 pub struct LeaderboardEntry {
     pub rank: i32,
     pub trader_wallet: String,
+    pub display_name: Option<String>,
     pub account_value: BigDecimal,
     pub pnl: BigDecimal,
     pub roi: BigDecimal,
     pub volume: BigDecimal,
     pub last_updated: DateTime<Utc>,
+    // Rank di snapshot sebelumnya; positif berarti naik
+    pub previous_rank: Option<i32>,
+    pub rank_change: Option<i32>,
+    pub is_new: bool,
+}
+
+// Perubahan rank satu trader antara dua snapshot
+#[derive(Debug, Clone, Serialize, Deserialize, SimpleObject, sqlx::FromRow)]
+pub struct RankChange {
+    pub timeframe: String,
+    pub trader_wallet: String,
+    pub display_name: Option<String>,
+    pub rank: i32,
+    pub previous_rank: Option<i32>,
+    pub rank_change: Option<i32>,
+    pub is_new: bool,
+}
+
+// Kenaikan & penurunan rank terbesar dalam satu window
+#[derive(Debug, Clone, Serialize, Deserialize, SimpleObject)]
+pub struct Movers {
+    pub timeframe: String,
+    pub since: DateTime<Utc>,
+    pub climbers: Vec<RankChange>,
+    pub fallers: Vec<RankChange>,
 }
 
+// Satu eksekusi job scheduler (tabel job_runs)
 #[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
-pub struct Trader {
-    pub id: uuid::Uuid,
-    pub wallet_address: String,
-    pub created_at: DateTime<Utc>,
-    pub last_active: DateTime<Utc>,
+pub struct JobRun {
+    pub id: i64,
+    pub job_name: String,
+    pub trigger: String,
+    pub status: String,
+    pub attempts: i32,
+    pub instance_id: String,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: Option<DateTime<Utc>>,
+    pub detail: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
@@ -33,4 +314,195 @@ pub struct TraderPerformance {
     pub volume: BigDecimal,
     pub calculated_at: DateTime<Utc>,
     pub timeframe: String,
-}
\ No newline at end of file
+}
+
+// Snapshot leaderboard yang tersimpan permanen; id = versi leaderboard saat refresh
+#[derive(Debug, Clone, Serialize, Deserialize, SimpleObject, sqlx::FromRow)]
+pub struct LeaderboardSnapshot {
+    pub id: i64,
+    pub created_at: DateTime<Utc>,
+    pub entry_count: i32,
+}
+
+// Satu titik time-series untuk grafik equity trader (nilai snapshot terakhir di setiap bucket)
+#[derive(Debug, Clone, Serialize, Deserialize, SimpleObject, sqlx::FromRow)]
+pub struct PerformancePoint {
+    pub bucket_start: DateTime<Utc>,
+    pub account_value: BigDecimal,
+    pub pnl: BigDecimal,
+    pub roi: BigDecimal,
+    pub volume: BigDecimal,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TradeSide {
+    Buy,
+    Sell,
+}
+
+impl TradeSide {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "buy" | "b" | "bid" | "long" => Some(TradeSide::Buy),
+            "sell" | "s" | "ask" | "short" => Some(TradeSide::Sell),
+            _ => None,
+        }
+    }
+    
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TradeSide::Buy => "buy",
+            TradeSide::Sell => "sell",
+        }
+    }
+}
+
+// Fill yang sudah dinormalisasi dan tersimpan di tabel trades
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct Trade {
+    pub id: uuid::Uuid,
+    pub trader_id: uuid::Uuid,
+    pub source: String,
+    pub external_id: String,
+    pub symbol: String,
+    pub side: String,
+    pub price: BigDecimal,
+    pub quantity: BigDecimal,
+    pub fee: BigDecimal,
+    pub realized_pnl: BigDecimal,
+    pub executed_at: DateTime<Utc>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    // Contoh dari EIP-55
+    const EIP55_VECTORS: [&str; 4] = [
+        "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed",
+        "0xfB6916095ca1df60bB79Ce92cE3Ea74c37c5d359",
+        "0xdbF03B407c01E7cD3CBea99509d93f8DDDC8C6FB",
+        "0xD1220A0cf47c7B9Be7A2E6BA89F429762e7b9aDb",
+    ];
+    
+    #[test]
+    fn timeframe_accepts_aliases_and_rejects_unknown() {
+        assert_eq!(validate_timeframe(None).unwrap(), "daily");
+        assert_eq!(validate_timeframe(Some("7D")).unwrap(), "weekly");
+        assert_eq!(validate_timeframe(Some("all")).unwrap(), "all_time");
+        assert_eq!(validate_timeframe(Some("hourly")).unwrap_err().code(), "INVALID_INPUT");
+    }
+    
+    #[test]
+    fn bucket_defaults_to_hour_and_rejects_unknown() {
+        assert_eq!(validate_bucket(None).unwrap(), "hour");
+        assert_eq!(validate_bucket(Some("Day")).unwrap(), "day");
+        assert_eq!(validate_bucket(Some("year")).unwrap_err().code(), "INVALID_INPUT");
+    }
+    
+    #[test]
+    fn evm_accepts_eip55_vectors() {
+        for vector in EIP55_VECTORS {
+            let wallet = WalletAddress::parse(vector, None).unwrap();
+            assert_eq!(wallet.chain(), Chain::Evm);
+            assert_eq!(wallet.as_str(), vector);
+        }
+    }
+    
+    #[test]
+    fn evm_checksums_single_case_input() {
+        for vector in EIP55_VECTORS {
+            let lower = format!("0x{}", vector[2..].to_lowercase());
+            let upper = format!("0x{}", vector[2..].to_uppercase());
+            assert_eq!(WalletAddress::parse(&lower, None).unwrap().as_str(), vector);
+            assert_eq!(WalletAddress::parse(&upper, Some(Chain::Evm)).unwrap().as_str(), vector);
+        }
+    }
+    
+    #[test]
+    fn evm_rejects_wrong_checksum() {
+        // Huruf terakhir seharusnya kecil
+        let err = WalletAddress::parse("0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAeD", None).unwrap_err();
+        assert_eq!(err, WalletAddressError::InvalidChecksum);
+        assert_eq!(err.code(), "INVALID_WALLET_CHECKSUM");
+    }
+    
+    #[test]
+    fn evm_rejects_bad_length_and_characters() {
+        for input in [
+            "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeA",
+            "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed00",
+            "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAeg",
+        ] {
+            let err = WalletAddress::parse(input, None).unwrap_err();
+            assert!(matches!(err, WalletAddressError::InvalidFormat { chain: Chain::Evm, .. }), "{}", input);
+        }
+    }
+    
+    #[test]
+    fn solana_accepts_base58_pubkeys() {
+        for pubkey in [
+            "11111111111111111111111111111111",
+            "TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA",
+        ] {
+            let wallet = WalletAddress::parse(pubkey, None).unwrap();
+            assert_eq!(wallet.chain(), Chain::Solana);
+            assert_eq!(wallet.as_str(), pubkey);
+        }
+    }
+    
+    #[test]
+    fn solana_rejects_invalid_base58_and_wrong_length() {
+        // '0' dan 'l' bukan karakter base58
+        for input in [
+            "0okenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA",
+            "TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5Dl",
+        ] {
+            let err = WalletAddress::parse(input, None).unwrap_err();
+            assert!(matches!(err, WalletAddressError::InvalidFormat { chain: Chain::Solana, .. }), "{}", input);
+        }
+        
+        // Base58 valid tapi hanya 31 byte
+        let err = WalletAddress::parse("1111111111111111111111111111111", Some(Chain::Solana)).unwrap_err();
+        assert!(matches!(err, WalletAddressError::InvalidFormat { chain: Chain::Solana, .. }));
+    }
+    
+    #[test]
+    fn bitcoin_accepts_bip173_segwit_v0() {
+        // P2WPKH (program 20 byte) dan P2WSH (program 32 byte) dari BIP-173
+        let wallet = WalletAddress::parse("BC1QW508D6QEJXTDG4Y5R3ZARVARY0C5XW7KV8F3T4", None).unwrap();
+        assert_eq!(wallet.chain(), Chain::Bitcoin);
+        assert_eq!(wallet.as_str(), "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4");
+        
+        let wallet = WalletAddress::parse("tb1qrp33g0q5c5txsp9arysrx4k6zdkfs4nce4xj0gdcccefvpysxf3q0sl5k7", None).unwrap();
+        assert_eq!(wallet.as_str(), "tb1qrp33g0q5c5txsp9arysrx4k6zdkfs4nce4xj0gdcccefvpysxf3q0sl5k7");
+    }
+    
+    #[test]
+    fn bitcoin_accepts_bip350_taproot() {
+        let input = "bc1p0xlxvlhemja6c4dqv22uapctqupfhlxm9h8z3k2e72q4k9hcz7vqzk5jj0";
+        assert_eq!(WalletAddress::parse(input, None).unwrap().as_str(), input);
+    }
+    
+    #[test]
+    fn bitcoin_rejects_bad_checksum_and_program_length() {
+        for input in [
+            // Checksum salah
+            "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t5",
+            // Program v0 16 byte (BIP-141 hanya mengizinkan 20 atau 32)
+            "BC1QR508D6QEJXTDG4Y5R3ZARVARYV98GJ9P",
+            // Huruf besar dan kecil bercampur
+            "tb1qrp33g0q5c5txsp9arysrx4k6zdkfs4nce4xj0gdcccefvpysxf3Q0sl5k7",
+        ] {
+            let err = WalletAddress::parse(input, None).unwrap_err();
+            assert!(matches!(err, WalletAddressError::InvalidFormat { chain: Chain::Bitcoin, .. }), "{}", input);
+        }
+    }
+    
+    #[test]
+    fn rejects_empty_and_unknown_formats() {
+        assert_eq!(WalletAddress::parse("  ", None).unwrap_err(), WalletAddressError::Empty);
+        assert_eq!(WalletAddress::parse("not-a-wallet", None).unwrap_err(), WalletAddressError::UnknownFormat);
+    }
+}
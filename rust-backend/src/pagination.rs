@@ -0,0 +1,198 @@
+// src/pagination.rs
+// Cursor opaque untuk keyset pagination leaderboard.
+// Cursor menyimpan versi leaderboard saat halaman dibuat; halaman berikutnya dibaca dari versi
+// yang sama (snapshot-nya) walaupun view sudah di-refresh di antaranya.
+use bigdecimal::BigDecimal;
+use std::fmt;
+use std::str::FromStr;
+use crate::models::LeaderboardEntry;
+
+#[derive(Debug)]
+pub enum PaginationError {
+    InvalidCursor,
+    CursorExpired,
+    Database(sqlx::Error),
+}
+
+impl PaginationError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            PaginationError::InvalidCursor => "INVALID_CURSOR",
+            PaginationError::CursorExpired => "CURSOR_EXPIRED",
+            PaginationError::Database(_) => "INTERNAL",
+        }
+    }
+}
+
+impl fmt::Display for PaginationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PaginationError::InvalidCursor => write!(f, "cursor is invalid or does not match the requested sort"),
+            PaginationError::CursorExpired => write!(f, "leaderboard version of this cursor is no longer retained, restart pagination from the first page"),
+            PaginationError::Database(e) => write!(f, "database error: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for PaginationError {}
+
+impl From<sqlx::Error> for PaginationError {
+    fn from(e: sqlx::Error) -> Self {
+        PaginationError::Database(e)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LeaderboardCursor {
+    pub version: i64,
+    pub timeframe: String,
+    pub sort_by: String,
+    pub sort_order: String,
+    pub last_value: BigDecimal,
+    pub last_wallet: String,
+    pub last_rank: i32,
+}
+
+impl LeaderboardCursor {
+    // Cursor yang menunjuk ke posisi setelah `entry`
+    pub fn after_entry(
+        entry: &LeaderboardEntry,
+        version: i64,
+        timeframe: &str,
+        sort_by: &str,
+        sort_order: &str,
+    ) -> Self {
+        let last_value = match sort_by {
+            "pnl" => entry.pnl.clone(),
+            "volume" => entry.volume.clone(),
+            _ => entry.roi.clone(),
+        };
+        
+        Self {
+            version,
+            timeframe: timeframe.to_string(),
+            sort_by: sort_by.to_string(),
+            sort_order: sort_order.to_string(),
+            last_value,
+            last_wallet: entry.trader_wallet.clone(),
+            last_rank: entry.rank,
+        }
+    }
+    
+    pub fn encode(&self) -> String {
+        hex::encode(format!(
+            "v1|{}|{}|{}|{}|{}|{}|{}",
+            self.version,
+            self.timeframe,
+            self.sort_by,
+            self.sort_order,
+            self.last_value,
+            self.last_rank,
+            self.last_wallet
+        ))
+    }
+    
+    pub fn decode(cursor: &str) -> Result<Self, PaginationError> {
+        let bytes = hex::decode(cursor).map_err(|_| PaginationError::InvalidCursor)?;
+        let text = String::from_utf8(bytes).map_err(|_| PaginationError::InvalidCursor)?;
+        let parts: Vec<&str> = text.splitn(8, '|').collect();
+        
+        match parts.as_slice() {
+            ["v1", version, timeframe, sort_by, sort_order, last_value, last_rank, last_wallet] => Ok(Self {
+                version: version.parse().map_err(|_| PaginationError::InvalidCursor)?,
+                timeframe: timeframe.to_string(),
+                sort_by: sort_by.to_string(),
+                sort_order: sort_order.to_string(),
+                last_value: BigDecimal::from_str(last_value).map_err(|_| PaginationError::InvalidCursor)?,
+                last_rank: last_rank.parse().map_err(|_| PaginationError::InvalidCursor)?,
+                last_wallet: last_wallet.to_string(),
+            }),
+            _ => Err(PaginationError::InvalidCursor),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct LeaderboardPage {
+    pub entries: Vec<LeaderboardEntry>,
+    pub has_next_page: bool,
+    pub total_count: i64,
+    pub version: i64,
+    // Parameter yang sudah dinormalisasi, dipakai untuk membuat cursor tiap baris
+    pub timeframe: String,
+    pub sort_by: String,
+    pub sort_order: String,
+}
+
+impl LeaderboardPage {
+    pub fn cursor_for(&self, entry: &LeaderboardEntry) -> String {
+        LeaderboardCursor::after_entry(entry, self.version, &self.timeframe, &self.sort_by, &self.sort_order)
+            .encode()
+    }
+    
+    pub fn end_cursor(&self) -> Option<String> {
+        self.entries.last().map(|entry| self.cursor_for(entry))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn cursor(last_wallet: &str) -> LeaderboardCursor {
+        LeaderboardCursor {
+            version: 42,
+            timeframe: "weekly".to_string(),
+            sort_by: "pnl".to_string(),
+            sort_order: "desc".to_string(),
+            last_value: BigDecimal::from_str("-1234.500000000000000001").unwrap(),
+            last_wallet: last_wallet.to_string(),
+            last_rank: 17,
+        }
+    }
+    
+    #[test]
+    fn encode_decode_round_trip() {
+        let original = cursor("0xabcdef0123456789abcdef0123456789abcdef01");
+        let decoded = LeaderboardCursor::decode(&original.encode()).unwrap();
+        assert_eq!(decoded, original);
+    }
+    
+    #[test]
+    fn wallet_containing_separator_survives_round_trip() {
+        // Wallet ada di field terakhir, jadi '|' di dalamnya tidak memecah cursor
+        let original = cursor("weird|wallet|name");
+        let decoded = LeaderboardCursor::decode(&original.encode()).unwrap();
+        assert_eq!(decoded.last_wallet, "weird|wallet|name");
+        assert_eq!(decoded, original);
+    }
+    
+    #[test]
+    fn bad_hex_is_invalid() {
+        assert!(matches!(LeaderboardCursor::decode("not-hex"), Err(PaginationError::InvalidCursor)));
+        assert!(matches!(LeaderboardCursor::decode("abc"), Err(PaginationError::InvalidCursor)));
+        // Hex valid tapi bukan UTF-8
+        assert!(matches!(LeaderboardCursor::decode("fffe"), Err(PaginationError::InvalidCursor)));
+    }
+    
+    #[test]
+    fn wrong_version_prefix_is_invalid() {
+        let encoded = cursor("0xabc").encode();
+        let text = String::from_utf8(hex::decode(encoded).unwrap()).unwrap();
+        let v2 = hex::encode(text.replacen("v1|", "v2|", 1));
+        assert!(matches!(LeaderboardCursor::decode(&v2), Err(PaginationError::InvalidCursor)));
+    }
+    
+    #[test]
+    fn malformed_fields_are_invalid() {
+        for text in [
+            "v1|42|weekly|pnl|desc|1.5|17",
+            "v1|latest|weekly|pnl|desc|1.5|17|0xabc",
+            "v1|42|weekly|pnl|desc|lots|17|0xabc",
+            "v1|42|weekly|pnl|desc|1.5|first|0xabc",
+        ] {
+            let result = LeaderboardCursor::decode(&hex::encode(text));
+            assert!(matches!(result, Err(PaginationError::InvalidCursor)), "{}", text);
+        }
+    }
+}
@@ -0,0 +1,24 @@
+// src/repository/health_repo.rs
+use sqlx::PgPool;
+
+pub struct HealthRepository {
+    pool: PgPool,
+}
+
+impl HealthRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+    
+    pub async fn ping(&self) -> Result<(), sqlx::Error> {
+        sqlx::query("SELECT 1").execute(&self.pool).await?;
+        Ok(())
+    }
+    
+    // Versi migration yang sudah berhasil dijalankan (tabel milik sqlx migrate)
+    pub async fn applied_migrations(&self) -> Result<Vec<i64>, sqlx::Error> {
+        sqlx::query_scalar("SELECT version FROM _sqlx_migrations WHERE success ORDER BY version")
+            .fetch_all(&self.pool)
+            .await
+    }
+}
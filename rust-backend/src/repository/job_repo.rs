@@ -0,0 +1,142 @@
+// src/repository/job_repo.rs
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+use crate::models::JobRun;
+
+pub struct JobRepository {
+    pool: PgPool,
+}
+
+impl JobRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+    
+    pub async fn start_run(&self, job_name: &str, trigger: &str, instance_id: &str) -> Result<i64, sqlx::Error> {
+        sqlx::query_scalar(
+            r#"
+            INSERT INTO job_runs (job_name, trigger, status, instance_id)
+            VALUES ($1, $2, 'running', $3)
+            RETURNING id
+            "#,
+        )
+        .bind(job_name)
+        .bind(trigger)
+        .bind(instance_id)
+        .fetch_one(&self.pool)
+        .await
+    }
+    
+    pub async fn finish_run(
+        &self,
+        run_id: i64,
+        status: &str,
+        attempts: i32,
+        detail: Option<&str>,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            r#"
+            UPDATE job_runs
+            SET status = $2, attempts = $3, detail = $4, finished_at = NOW()
+            WHERE id = $1
+            "#,
+        )
+        .bind(run_id)
+        .bind(status)
+        .bind(attempts)
+        .bind(detail)
+        .execute(&self.pool)
+        .await?;
+        
+        Ok(())
+    }
+    
+    // Run yang dilewati karena run sebelumnya masih berjalan
+    pub async fn record_skipped(
+        &self,
+        job_name: &str,
+        trigger: &str,
+        instance_id: &str,
+        detail: &str,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            r#"
+            INSERT INTO job_runs (job_name, trigger, status, instance_id, finished_at, detail)
+            VALUES ($1, $2, 'skipped', $3, NOW(), $4)
+            "#,
+        )
+        .bind(job_name)
+        .bind(trigger)
+        .bind(instance_id)
+        .bind(detail)
+        .execute(&self.pool)
+        .await?;
+        
+        Ok(())
+    }
+    
+    pub async fn recent_runs(&self, job_name: &str, limit: i64) -> Result<Vec<JobRun>, sqlx::Error> {
+        sqlx::query_as(
+            r#"
+            SELECT id, job_name, trigger, status, attempts, instance_id, started_at, finished_at, detail
+            FROM job_runs
+            WHERE job_name = $1
+            ORDER BY started_at DESC, id DESC
+            LIMIT $2
+            "#,
+        )
+        .bind(job_name)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await
+    }
+    
+    // Waktu selesai run sukses terakhir, untuk readiness (lag ingestion)
+    pub async fn last_success(&self, job_name: &str) -> Result<Option<DateTime<Utc>>, sqlx::Error> {
+        sqlx::query_scalar(
+            r#"
+            SELECT MAX(finished_at)
+            FROM job_runs
+            WHERE job_name = $1 AND status = 'succeeded'
+            "#,
+        )
+        .bind(job_name)
+        .fetch_one(&self.pool)
+        .await
+    }
+    
+    pub async fn is_paused(&self, job_name: &str) -> Result<bool, sqlx::Error> {
+        let paused: Option<bool> = sqlx::query_scalar("SELECT paused FROM job_settings WHERE job_name = $1")
+            .bind(job_name)
+            .fetch_optional(&self.pool)
+            .await?;
+        
+        Ok(paused.unwrap_or(false))
+    }
+    
+    pub async fn set_paused(&self, job_name: &str, paused: bool) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            r#"
+            INSERT INTO job_settings (job_name, paused, updated_at)
+            VALUES ($1, $2, NOW())
+            ON CONFLICT (job_name) DO UPDATE
+            SET paused = EXCLUDED.paused, updated_at = NOW()
+            "#,
+        )
+        .bind(job_name)
+        .bind(paused)
+        .execute(&self.pool)
+        .await?;
+        
+        Ok(())
+    }
+    
+    pub async fn delete_runs_before(&self, before: DateTime<Utc>) -> Result<u64, sqlx::Error> {
+        let result = sqlx::query("DELETE FROM job_runs WHERE started_at < $1 AND status <> 'running'")
+            .bind(before)
+            .execute(&self.pool)
+            .await?;
+        
+        Ok(result.rows_affected())
+    }
+}
@@ -1,7 +1,36 @@
 // src/repository/leaderboard_repo.rs
-use sqlx::{PgPool, Row};
-use bigdecimal::BigDecimal;
-use crate::models::LeaderboardEntry;
+use sqlx::PgPool;
+use tracing::instrument;
+use chrono::{DateTime, Utc};
+use crate::models::{
+    parse_timeframe, LeaderboardEntry, LeaderboardSnapshot, Movers, PerformancePoint, RankChange,
+    TraderPerformance,
+};
+use crate::leader::Fence;
+use crate::metrics::metrics;
+use crate::pagination::{LeaderboardCursor, LeaderboardPage, PaginationError};
+use super::LeaseRepository;
+
+// Kolom rank-change untuk baris live: dibandingkan dengan entry trader di snapshot versi saat ini
+// (snapshot itu ditulis di refresh yang sama, jadi previous_rank-nya adalah rank sebelum refresh)
+const RANK_CHANGE_COLUMNS: &str = r#"
+                se.previous_rank,
+                se.previous_rank - se.rank as rank_change,
+                (se.snapshot_id IS NOT NULL AND se.previous_rank IS NULL) as is_new"#;
+
+// Rank di snapshot selalu urutan roi DESC; untuk urutan lain previous_rank/rank_change
+// tidak sebanding dengan rank yang ditampilkan, jadi dikirim NULL (is_new tetap valid)
+const RANK_CHANGE_COLUMNS_UNRANKED: &str = r#"
+                NULL::INT as previous_rank,
+                NULL::INT as rank_change,
+                (se.snapshot_id IS NOT NULL AND se.previous_rank IS NULL) as is_new"#;
+
+const RANK_CHANGE_JOIN: &str = r#"
+            LEFT JOIN leaderboard_snapshot_entries se
+                ON se.snapshot_id = (SELECT version FROM leaderboard_state)
+               AND se.timeframe = rl.timeframe
+               AND se.chain = rl.chain
+               AND se.trader_wallet = rl.trader_wallet"#;
 
 pub struct LeaderboardRepository {
     pool: PgPool,
@@ -12,12 +41,14 @@ impl LeaderboardRepository {
         Self { pool }
     }
     
+    #[instrument(skip(self), err)]
     pub async fn get_leaderboard(
         &self,
         limit: i32,
         offset: i32,
         sort_by: &str,
         sort_order: &str,
+        timeframe: &str,
     ) -> Result<Vec<LeaderboardEntry>, sqlx::Error> {
         let order_clause = match sort_by {
             "pnl" => "pnl",
@@ -31,31 +62,680 @@ impl LeaderboardRepository {
         let query = format!(
             r#"
             SELECT 
-                ROW_NUMBER() OVER (ORDER BY {} {}) as rank,
+                (ROW_NUMBER() OVER (ORDER BY rl.{col} {dir}))::INT as rank,
+                rl.trader_wallet,
+                rl.display_name,
+                rl.account_value,
+                rl.pnl,
+                rl.roi,
+                rl.volume,
+                rl.last_updated,{changes}
+            FROM realtime_leaderboard rl{join}
+            WHERE rl.timeframe = $3
+            ORDER BY rl.{col} {dir}
+            LIMIT $1 OFFSET $2
+            "#,
+            col = order_clause,
+            dir = order_dir,
+            changes = rank_change_columns(order_clause, order_dir),
+            join = RANK_CHANGE_JOIN,
+        );
+        
+        sqlx::query_as(&query)
+            .bind(limit)
+            .bind(offset)
+            .bind(parse_timeframe(timeframe))
+            .fetch_all(&self.pool)
+            .await
+    }
+    
+    #[instrument(skip(self), err)]
+    pub async fn get_trader_by_wallet(
+        &self,
+        wallet_address: &str,
+        timeframe: &str,
+    ) -> Result<Option<LeaderboardEntry>, sqlx::Error> {
+        let query = format!(
+            r#"
+            SELECT 
+                rl.rank::INT as rank,
+                rl.trader_wallet,
+                rl.display_name,
+                rl.account_value,
+                rl.pnl,
+                rl.roi,
+                rl.volume,
+                rl.last_updated,{changes}
+            FROM realtime_leaderboard rl{join}
+            WHERE rl.trader_wallet = $1 AND rl.timeframe = $2
+            "#,
+            changes = RANK_CHANGE_COLUMNS,
+            join = RANK_CHANGE_JOIN,
+        );
+        
+        sqlx::query_as(&query)
+            .bind(wallet_address)
+            .bind(parse_timeframe(timeframe))
+            .fetch_optional(&self.pool)
+            .await
+    }
+    
+    #[instrument(skip(self), err)]
+    pub async fn get_total_traders(&self) -> Result<i32, sqlx::Error> {
+        sqlx::query_scalar("SELECT COUNT(*)::INT FROM traders")
+            .fetch_one(&self.pool)
+            .await
+    }
+    
+    // Riwayat snapshot di balik satu baris leaderboard, terbaru lebih dulu
+    #[instrument(skip(self), err)]
+    pub async fn get_snapshot_history(
+        &self,
+        wallet_address: &str,
+        timeframe: &str,
+        limit: i32,
+    ) -> Result<Vec<TraderPerformance>, sqlx::Error> {
+        sqlx::query_as(
+            r#"
+            SELECT 
+                tp.id,
+                tp.trader_id,
+                tp.account_value,
+                tp.pnl,
+                tp.roi,
+                tp.volume,
+                tp.calculated_at,
+                tp.timeframe
+            FROM trader_performance tp
+            INNER JOIN traders t ON t.id = tp.trader_id
+            WHERE t.wallet_address = $1 AND tp.timeframe = $2
+            ORDER BY tp.calculated_at DESC
+            LIMIT $3
+            "#,
+        )
+        .bind(wallet_address)
+        .bind(parse_timeframe(timeframe))
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await
+    }
+    
+    // Time-series performa trader; snapshot dikelompokkan per bucket dan diambil nilai terakhirnya
+    #[instrument(skip(self), err)]
+    pub async fn get_trader_history(
+        &self,
+        wallet_address: &str,
+        timeframe: &str,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+        bucket: &str,
+    ) -> Result<Vec<PerformancePoint>, sqlx::Error> {
+        // Bucket dari klien sudah divalidasi lewat validate_bucket; nilai lain jatuh ke hour
+        let bucket = match bucket {
+            "minute" => "minute",
+            "hour" => "hour",
+            "day" => "day",
+            "week" => "week",
+            "month" => "month",
+            _ => "hour",
+        };
+        
+        sqlx::query_as(
+            r#"
+            SELECT DISTINCT ON (bucket_start)
+                date_trunc($5, tp.calculated_at) as bucket_start,
+                tp.account_value,
+                tp.pnl,
+                tp.roi,
+                tp.volume
+            FROM trader_performance tp
+            INNER JOIN traders t ON t.id = tp.trader_id
+            WHERE t.wallet_address = $1
+              AND tp.timeframe = $2
+              AND tp.calculated_at >= $3
+              AND tp.calculated_at < $4
+            ORDER BY bucket_start, tp.calculated_at DESC
+            "#,
+        )
+        .bind(wallet_address)
+        .bind(parse_timeframe(timeframe))
+        .bind(from)
+        .bind(to)
+        .bind(bucket)
+        .fetch_all(&self.pool)
+        .await
+    }
+    
+    // Keyset pagination. Halaman pertama membaca versi leaderboard terbaru; halaman berikutnya tetap di versi
+    // cursor: kalau view sudah di-refresh, data dibaca dari snapshot versi tersebut, jadi tidak ada trader
+    // yang dobel atau terlewat. CursorExpired hanya kalau snapshot itu sudah dihapus retention.
+    #[instrument(skip(self), err)]
+    pub async fn get_leaderboard_page(
+        &self,
+        first: i32,
+        after: Option<&LeaderboardCursor>,
+        sort_by: &str,
+        sort_order: &str,
+        timeframe: &str,
+    ) -> Result<LeaderboardPage, PaginationError> {
+        let order_clause = match sort_by {
+            "pnl" => "pnl",
+            "roi" => "roi",
+            "volume" => "volume",
+            _ => "roi",
+        };
+        let sort_order = if sort_order.to_lowercase() == "asc" { "asc" } else { "desc" };
+        let (order_dir, comparison) = if sort_order == "asc" { ("ASC", ">") } else { ("DESC", "<") };
+        let timeframe = parse_timeframe(timeframe);
+        
+        if let Some(cursor) = after {
+            if cursor.sort_by != order_clause || cursor.sort_order != sort_order || cursor.timeframe != timeframe {
+                return Err(PaginationError::InvalidCursor);
+            }
+        }
+        
+        // Versi dan data dibaca dari snapshot transaksi yang sama
+        let mut tx = self.pool.begin().await?;
+        sqlx::query("SET TRANSACTION ISOLATION LEVEL REPEATABLE READ READ ONLY")
+            .execute(&mut *tx)
+            .await?;
+        
+        let current_version: i64 = sqlx::query_scalar("SELECT version FROM leaderboard_state")
+            .fetch_one(&mut *tx)
+            .await?;
+        
+        // Versi lama dibaca dari leaderboard_snapshot_entries; versi terbaru dari view
+        let snapshot_version = match after {
+            Some(cursor) if cursor.version != current_version => {
+                let exists: bool = sqlx::query_scalar("SELECT EXISTS (SELECT 1 FROM leaderboard_snapshots WHERE id = $1)")
+                    .bind(cursor.version)
+                    .fetch_one(&mut *tx)
+                    .await?;
+                if !exists {
+                    return Err(PaginationError::CursorExpired);
+                }
+                Some(cursor.version)
+            }
+            _ => None,
+        };
+        let version = snapshot_version.unwrap_or(current_version);
+        
+        let (source, changes) = match snapshot_version {
+            Some(_) => (
+                "leaderboard_snapshot_entries rl".to_string(),
+                snapshot_rank_change_columns(order_clause, order_dir),
+            ),
+            None => (
+                format!("realtime_leaderboard rl{}", RANK_CHANGE_JOIN),
+                rank_change_columns(order_clause, order_dir),
+            ),
+        };
+        let snapshot_filter = |param: usize| match snapshot_version {
+            Some(_) => format!("AND rl.snapshot_id = ${}", param),
+            None => String::new(),
+        };
+        
+        let count_query = format!(
+            "SELECT COUNT(*) FROM {} WHERE rl.timeframe = $1 {}",
+            source,
+            snapshot_filter(2)
+        );
+        let mut count = sqlx::query_scalar(&count_query).bind(timeframe);
+        if let Some(snapshot_version) = snapshot_version {
+            count = count.bind(snapshot_version);
+        }
+        let total_count: i64 = count.fetch_one(&mut *tx).await?;
+        
+        let query = format!(
+            r#"
+            SELECT 
+                ($3 + ROW_NUMBER() OVER (ORDER BY rl.{col} {dir}, rl.trader_wallet {dir}))::INT as rank,
+                rl.trader_wallet,
+                rl.display_name,
+                rl.account_value,
+                rl.pnl,
+                rl.roi,
+                rl.volume,
+                rl.last_updated,{changes}
+            FROM {source}
+            WHERE rl.timeframe = $1
+              AND ($4::NUMERIC IS NULL OR (rl.{col}, rl.trader_wallet) {cmp} ($4, $5))
+              {snapshot}
+            ORDER BY rl.{col} {dir}, rl.trader_wallet {dir}
+            LIMIT $2
+            "#,
+            col = order_clause,
+            dir = order_dir,
+            cmp = comparison,
+            changes = changes,
+            source = source,
+            snapshot = snapshot_filter(6),
+        );
+        
+        // Ambil satu baris ekstra untuk tahu apakah masih ada halaman berikutnya
+        let mut page = sqlx::query_as(&query)
+            .bind(timeframe)
+            .bind(first + 1)
+            .bind(after.map_or(0, |cursor| cursor.last_rank))
+            .bind(after.map(|cursor| cursor.last_value.clone()))
+            .bind(after.map(|cursor| cursor.last_wallet.clone()));
+        if let Some(snapshot_version) = snapshot_version {
+            page = page.bind(snapshot_version);
+        }
+        let mut entries: Vec<LeaderboardEntry> = page.fetch_all(&mut *tx).await?;
+        
+        tx.commit().await?;
+        
+        let has_next_page = entries.len() > first as usize;
+        entries.truncate(first as usize);
+        
+        Ok(LeaderboardPage {
+            entries,
+            has_next_page,
+            total_count,
+            version,
+            timeframe: timeframe.to_string(),
+            sort_by: order_clause.to_string(),
+            sort_order: sort_order.to_string(),
+        })
+    }
+    
+    // Snapshot terakhir yang dibuat pada atau sebelum `as_of`
+    #[instrument(skip(self), err)]
+    pub async fn get_snapshot_as_of(&self, as_of: DateTime<Utc>) -> Result<Option<LeaderboardSnapshot>, sqlx::Error> {
+        sqlx::query_as(
+            r#"
+            SELECT id, created_at, entry_count
+            FROM leaderboard_snapshots
+            WHERE created_at <= $1
+            ORDER BY created_at DESC, id DESC
+            LIMIT 1
+            "#,
+        )
+        .bind(as_of)
+        .fetch_optional(&self.pool)
+        .await
+    }
+    
+    #[instrument(skip(self), err)]
+    pub async fn get_snapshot(&self, snapshot_id: i64) -> Result<Option<LeaderboardSnapshot>, sqlx::Error> {
+        sqlx::query_as("SELECT id, created_at, entry_count FROM leaderboard_snapshots WHERE id = $1")
+            .bind(snapshot_id)
+            .fetch_optional(&self.pool)
+            .await
+    }
+    
+    #[instrument(skip(self), err)]
+    pub async fn get_latest_snapshot(&self) -> Result<Option<LeaderboardSnapshot>, sqlx::Error> {
+        sqlx::query_as("SELECT id, created_at, entry_count FROM leaderboard_snapshots ORDER BY id DESC LIMIT 1")
+            .fetch_optional(&self.pool)
+            .await
+    }
+    
+    // Daftar snapshot terbaru lebih dulu; `before` untuk halaman berikutnya
+    #[instrument(skip(self), err)]
+    pub async fn list_snapshots(
+        &self,
+        limit: i32,
+        before: Option<i64>,
+    ) -> Result<Vec<LeaderboardSnapshot>, sqlx::Error> {
+        sqlx::query_as(
+            r#"
+            SELECT id, created_at, entry_count
+            FROM leaderboard_snapshots
+            WHERE $2::BIGINT IS NULL OR id < $2
+            ORDER BY id DESC
+            LIMIT $1
+            "#,
+        )
+        .bind(limit)
+        .bind(before)
+        .fetch_all(&self.pool)
+        .await
+    }
+    
+    // Ranking seperti yang tersimpan di snapshot (urut rank)
+    #[instrument(skip(self), err)]
+    pub async fn get_snapshot_entries(
+        &self,
+        snapshot_id: i64,
+        timeframe: &str,
+        limit: i32,
+        offset: i32,
+    ) -> Result<Vec<LeaderboardEntry>, sqlx::Error> {
+        sqlx::query_as(
+            r#"
+            SELECT 
+                rank,
                 trader_wallet,
+                display_name,
                 account_value,
                 pnl,
                 roi,
                 volume,
-                last_updated
-            FROM realtime_leaderboard
-            ORDER BY {} {}
-            LIMIT $1 OFFSET $2
+                last_updated,
+                previous_rank,
+                previous_rank - rank as rank_change,
+                previous_rank IS NULL as is_new
+            FROM leaderboard_snapshot_entries
+            WHERE snapshot_id = $1 AND timeframe = $2
+            ORDER BY rank
+            LIMIT $3 OFFSET $4
             "#,
-            order_clause, order_dir, order_clause, order_dir
+        )
+        .bind(snapshot_id)
+        .bind(parse_timeframe(timeframe))
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(&self.pool)
+        .await
+    }
+    
+    // Potongan snapshot dengan urutan bebas; rank = posisi di urutan tersebut (seperti get_leaderboard)
+    #[instrument(skip(self), err)]
+    pub async fn get_snapshot_window(
+        &self,
+        snapshot_id: i64,
+        timeframe: &str,
+        sort_by: &str,
+        sort_order: &str,
+        limit: i32,
+        offset: i32,
+    ) -> Result<Vec<LeaderboardEntry>, sqlx::Error> {
+        let order_clause = match sort_by {
+            "pnl" => "pnl",
+            "roi" => "roi",
+            "volume" => "volume",
+            _ => "roi",
+        };
+        let order_dir = if sort_order.to_lowercase() == "asc" { "ASC" } else { "DESC" };
+        
+        let query = format!(
+            r#"
+            SELECT * FROM (
+                SELECT 
+                    ROW_NUMBER() OVER (ORDER BY {col} {dir}, trader_wallet {dir})::INT as rank,
+                    trader_wallet,
+                    display_name,
+                    account_value,
+                    pnl,
+                    roi,
+                    volume,
+                    last_updated,{changes}
+                FROM leaderboard_snapshot_entries
+                WHERE snapshot_id = $1 AND timeframe = $2
+            ) ranked
+            ORDER BY rank
+            LIMIT $3 OFFSET $4
+            "#,
+            col = order_clause,
+            dir = order_dir,
+            changes = snapshot_rank_change_columns(order_clause, order_dir),
         );
         
         sqlx::query_as(&query)
+            .bind(snapshot_id)
+            .bind(parse_timeframe(timeframe))
             .bind(limit)
             .bind(offset)
             .fetch_all(&self.pool)
             .await
     }
     
-    pub async fn refresh_materialized_view(&self) -> Result<(), sqlx::Error> {
+    // Entry beberapa wallet di satu snapshot (watchlist WebSocket)
+    #[instrument(skip(self, wallets), fields(wallets = wallets.len()), err)]
+    pub async fn get_snapshot_entries_for_wallets(
+        &self,
+        snapshot_id: i64,
+        timeframe: &str,
+        wallets: &[String],
+    ) -> Result<Vec<LeaderboardEntry>, sqlx::Error> {
+        sqlx::query_as(
+            r#"
+            SELECT 
+                rank,
+                trader_wallet,
+                display_name,
+                account_value,
+                pnl,
+                roi,
+                volume,
+                last_updated,
+                previous_rank,
+                previous_rank - rank as rank_change,
+                previous_rank IS NULL as is_new
+            FROM leaderboard_snapshot_entries
+            WHERE snapshot_id = $1 AND timeframe = $2 AND trader_wallet = ANY($3)
+            ORDER BY rank
+            "#,
+        )
+        .bind(snapshot_id)
+        .bind(parse_timeframe(timeframe))
+        .bind(wallets)
+        .fetch_all(&self.pool)
+        .await
+    }
+    
+    // Wallet dari `wallets` yang rank-nya berubah (atau baru masuk) di snapshot ini (watchlist WebSocket)
+    #[instrument(skip(self, wallets), fields(wallets = wallets.len()), err)]
+    pub async fn get_rank_changes_for_wallets(
+        &self,
+        snapshot_id: i64,
+        timeframe: &str,
+        wallets: &[String],
+    ) -> Result<Vec<RankChange>, sqlx::Error> {
+        sqlx::query_as(
+            r#"
+            SELECT 
+                timeframe,
+                trader_wallet,
+                display_name,
+                rank,
+                previous_rank,
+                previous_rank - rank as rank_change,
+                previous_rank IS NULL as is_new
+            FROM leaderboard_snapshot_entries
+            WHERE snapshot_id = $1
+              AND timeframe = $2
+              AND trader_wallet = ANY($3)
+              AND previous_rank IS DISTINCT FROM rank
+            ORDER BY rank
+            "#,
+        )
+        .bind(snapshot_id)
+        .bind(parse_timeframe(timeframe))
+        .bind(wallets)
+        .fetch_all(&self.pool)
+        .await
+    }
+    
+    // Kenaikan/penurunan rank terbesar antara snapshot terbaru dan snapshot pada `since`.
+    // Kalau history belum sepanjang window, dibandingkan dengan snapshot tertua.
+    #[instrument(skip(self), err)]
+    pub async fn get_movers(
+        &self,
+        timeframe: &str,
+        since: DateTime<Utc>,
+        limit: i32,
+    ) -> Result<Movers, sqlx::Error> {
+        let timeframe = parse_timeframe(timeframe);
+        let climbers = self.fetch_movers(timeframe, since, limit, ">", "DESC").await?;
+        let fallers = self.fetch_movers(timeframe, since, limit, "<", "ASC").await?;
+        
+        Ok(Movers {
+            timeframe: timeframe.to_string(),
+            since,
+            climbers,
+            fallers,
+        })
+    }
+    
+    async fn fetch_movers(
+        &self,
+        timeframe: &str,
+        since: DateTime<Utc>,
+        limit: i32,
+        comparison: &str,
+        order_dir: &str,
+    ) -> Result<Vec<RankChange>, sqlx::Error> {
+        let query = format!(
+            r#"
+            WITH base AS (
+                SELECT COALESCE(
+                    (SELECT id FROM leaderboard_snapshots WHERE created_at <= $2 ORDER BY created_at DESC, id DESC LIMIT 1),
+                    (SELECT MIN(id) FROM leaderboard_snapshots)
+                ) as id
+            )
+            SELECT 
+                cur.timeframe,
+                cur.trader_wallet,
+                cur.display_name,
+                cur.rank,
+                prev.rank as previous_rank,
+                prev.rank - cur.rank as rank_change,
+                FALSE as is_new
+            FROM leaderboard_snapshot_entries cur
+            INNER JOIN leaderboard_snapshot_entries prev
+                ON prev.snapshot_id = (SELECT id FROM base)
+               AND prev.timeframe = cur.timeframe
+               AND prev.chain = cur.chain
+               AND prev.trader_wallet = cur.trader_wallet
+            WHERE cur.snapshot_id = (SELECT MAX(id) FROM leaderboard_snapshots)
+              AND cur.timeframe = $1
+              AND prev.rank {cmp} cur.rank
+            ORDER BY rank_change {dir}, cur.rank
+            LIMIT $3
+            "#,
+            cmp = comparison,
+            dir = order_dir,
+        );
+        
+        sqlx::query_as(&query)
+            .bind(timeframe)
+            .bind(since)
+            .bind(limit)
+            .fetch_all(&self.pool)
+            .await
+    }
+    
+    // Hapus snapshot yang lebih lama dari `before` (entry ikut terhapus lewat ON DELETE CASCADE).
+    // Snapshot terbaru selalu disimpan karena refresh berikutnya membaca previous_rank darinya.
+    // Trigger di migrasi 011 menolak DELETE lain; flag retention hanya berlaku di transaksi ini.
+    #[instrument(skip(self), err)]
+    pub async fn delete_snapshots_before(&self, before: DateTime<Utc>) -> Result<u64, sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+        sqlx::query("SELECT set_config('leaderboard.snapshot_retention', 'on', true)")
+            .execute(&mut *tx)
+            .await?;
+        
+        let result = sqlx::query(
+            r#"
+            DELETE FROM leaderboard_snapshots
+            WHERE created_at < $1
+              AND id < (SELECT MAX(id) FROM leaderboard_snapshots)
+            "#,
+        )
+        .bind(before)
+        .execute(&mut *tx)
+        .await?;
+        
+        tx.commit().await?;
+        Ok(result.rows_affected())
+    }
+    
+    // Satu view berisi ranking semua timeframe, jadi satu refresh sudah mencakup daily/weekly/monthly/all_time.
+    // Setiap refresh juga menyimpan snapshot permanen bernomor versi baru, lengkap dengan rank
+    // tiap trader di snapshot sebelumnya. Return versi tersebut.
+    // Hanya dijalankan job refresh_leaderboard (juga saat di-trigger manual), jadi selalu membawa fencing token.
+    #[instrument(skip(self), err)]
+    pub async fn refresh_materialized_view(&self, fence: Fence) -> Result<i64, sqlx::Error> {
+        let timer = metrics().leaderboard_refresh_duration.start_timer();
+        let result = self.refresh_and_snapshot(fence).await;
+        
+        match &result {
+            Ok(_) => timer.observe_duration(),
+            Err(_) => {
+                timer.stop_and_discard();
+                metrics().leaderboard_refresh_failures.inc();
+            }
+        }
+        
+        result
+    }
+    
+    async fn refresh_and_snapshot(&self, fence: Fence) -> Result<i64, sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+        
         sqlx::query("REFRESH MATERIALIZED VIEW CONCURRENTLY realtime_leaderboard")
-            .execute(&self.pool)
+            .execute(&mut *tx)
             .await?;
-        Ok(())
+        
+        let version = sqlx::query_scalar(
+            "UPDATE leaderboard_state SET version = version + 1, refreshed_at = NOW() RETURNING version",
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+        
+        sqlx::query(
+            r#"
+            WITH snapshot AS (
+                INSERT INTO leaderboard_snapshots (id, created_at, entry_count)
+                VALUES ($1, NOW(), (SELECT COUNT(*) FROM realtime_leaderboard))
+                RETURNING id
+            )
+            INSERT INTO leaderboard_snapshot_entries
+                (snapshot_id, timeframe, rank, chain, trader_wallet, display_name,
+                 account_value, pnl, roi, volume, last_updated, previous_rank)
+            SELECT 
+                (SELECT id FROM snapshot),
+                rl.timeframe,
+                rl.rank,
+                rl.chain,
+                rl.trader_wallet,
+                rl.display_name,
+                rl.account_value,
+                rl.pnl,
+                rl.roi,
+                rl.volume,
+                rl.last_updated,
+                prev.rank
+            FROM realtime_leaderboard rl
+            LEFT JOIN leaderboard_snapshot_entries prev
+                ON prev.snapshot_id = (SELECT MAX(id) FROM leaderboard_snapshots WHERE id < $1)
+               AND prev.timeframe = rl.timeframe
+               AND prev.chain = rl.chain
+               AND prev.trader_wallet = rl.trader_wallet
+            "#,
+        )
+        .bind(version)
+        .execute(&mut *tx)
+        .await?;
+        
+        LeaseRepository::check_fence(&mut tx, fence).await?;
+        tx.commit().await?;
+        Ok(version)
     }
-}
\ No newline at end of file
+}
+
+fn rank_change_columns(order_clause: &str, order_dir: &str) -> &'static str {
+    if order_clause == "roi" && order_dir == "DESC" {
+        RANK_CHANGE_COLUMNS
+    } else {
+        RANK_CHANGE_COLUMNS_UNRANKED
+    }
+}
+
+// Sama seperti rank_change_columns, untuk query yang langsung membaca leaderboard_snapshot_entries
+fn snapshot_rank_change_columns(order_clause: &str, order_dir: &str) -> &'static str {
+    if order_clause == "roi" && order_dir == "DESC" {
+        r#"
+                    previous_rank,
+                    previous_rank - rank as rank_change,
+                    previous_rank IS NULL as is_new"#
+    } else {
+        r#"
+                    NULL::INT as previous_rank,
+                    NULL::INT as rank_change,
+                    previous_rank IS NULL as is_new"#
+    }
+}
@@ -0,0 +1,68 @@
+// src/repository/lease_repo.rs
+use sqlx::{PgConnection, PgPool};
+use crate::leader::Fence;
+
+pub struct LeaseRepository {
+    pool: PgPool,
+}
+
+impl LeaseRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+    
+    // Ambil atau perpanjang lease `name` untuk `holder`. Berhasil kalau lease sudah milik holder
+    // atau sudah kedaluwarsa; return fencing token, atau None kalau dipegang instance lain.
+    pub async fn try_acquire(
+        &self,
+        name: &str,
+        holder: &str,
+        ttl_seconds: i64,
+    ) -> Result<Option<i64>, sqlx::Error> {
+        sqlx::query_scalar(
+            r#"
+            INSERT INTO leader_leases (name, holder, fencing_token, acquired_at, expires_at)
+            VALUES ($1, $2, 1, NOW(), NOW() + make_interval(secs => $3))
+            ON CONFLICT (name) DO UPDATE
+            SET holder = EXCLUDED.holder,
+                fencing_token = CASE
+                    WHEN leader_leases.holder = EXCLUDED.holder THEN leader_leases.fencing_token
+                    ELSE leader_leases.fencing_token + 1
+                END,
+                acquired_at = CASE
+                    WHEN leader_leases.holder = EXCLUDED.holder THEN leader_leases.acquired_at
+                    ELSE NOW()
+                END,
+                expires_at = EXCLUDED.expires_at
+            WHERE leader_leases.holder = EXCLUDED.holder OR leader_leases.expires_at < NOW()
+            RETURNING fencing_token
+            "#,
+        )
+        .bind(name)
+        .bind(holder)
+        .bind(ttl_seconds as f64)
+        .fetch_optional(&self.pool)
+        .await
+    }
+    
+    // Lepas semua lease milik holder (saat shutdown) supaya replica lain tidak perlu menunggu TTL habis
+    pub async fn release_all(&self, holder: &str) -> Result<u64, sqlx::Error> {
+        let result = sqlx::query("UPDATE leader_leases SET expires_at = NOW() WHERE holder = $1 AND expires_at > NOW()")
+            .bind(holder)
+            .execute(&self.pool)
+            .await?;
+        
+        Ok(result.rows_affected())
+    }
+    
+    // Panggil tepat sebelum commit di transaksi tulis job. Gagal (dan transaksi dibatalkan) kalau lease
+    // sudah berpindah atau kedaluwarsa; baris lease tetap terkunci sampai transaksi selesai.
+    pub async fn check_fence(conn: &mut PgConnection, fence: Fence) -> Result<(), sqlx::Error> {
+        sqlx::query("SELECT check_leader_fence($1, $2)")
+            .bind(fence.lease)
+            .bind(fence.token)
+            .execute(conn)
+            .await?;
+        Ok(())
+    }
+}
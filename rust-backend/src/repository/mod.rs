@@ -0,0 +1,14 @@
+// src/repository/mod.rs
+mod health_repo;
+mod job_repo;
+mod leaderboard_repo;
+mod lease_repo;
+mod trade_repo;
+mod wallet_repo;
+
+pub use health_repo::HealthRepository;
+pub use job_repo::JobRepository;
+pub use leaderboard_repo::LeaderboardRepository;
+pub use lease_repo::LeaseRepository;
+pub use trade_repo::TradeRepository;
+pub use wallet_repo::WalletRepository;
@@ -0,0 +1,275 @@
+// src/repository/trade_repo.rs
+use sqlx::PgPool;
+use bigdecimal::BigDecimal;
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+use uuid::Uuid;
+use crate::calculation::{CashFlow, CashFlowKind, PnlReport};
+use crate::ingestion::normalize::{NormalizedCashFlow, NormalizedFill, NormalizedPosition};
+use crate::leader::Fence;
+use crate::models::{Trade, WalletAddress};
+use super::LeaseRepository;
+
+pub struct TradeRepository {
+    pool: PgPool,
+}
+
+impl TradeRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+    
+    // Buat trader baru kalau wallet belum terdaftar, kembalikan id-nya
+    pub async fn ensure_trader(&self, wallet_address: &WalletAddress) -> Result<Uuid, sqlx::Error> {
+        sqlx::query_scalar(
+            r#"
+            INSERT INTO traders (chain, wallet_address)
+            VALUES ($1, $2)
+            ON CONFLICT (chain, wallet_address) DO UPDATE
+            SET last_active = NOW()
+            RETURNING id
+            "#,
+        )
+        .bind(wallet_address.chain().as_str())
+        .bind(wallet_address.as_str())
+        .fetch_one(&self.pool)
+        .await
+    }
+    
+    // Return 1 kalau fill baru, 0 kalau sudah pernah di-ingest
+    pub async fn insert_trade(
+        &self,
+        source: &str,
+        trader_id: Uuid,
+        fill: &NormalizedFill,
+    ) -> Result<u64, sqlx::Error> {
+        let result = sqlx::query(
+            r#"
+            INSERT INTO trades
+                (trader_id, source, external_id, symbol, side, price, quantity, fee, realized_pnl, executed_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+            ON CONFLICT (source, external_id) DO NOTHING
+            "#,
+        )
+        .bind(trader_id)
+        .bind(source)
+        .bind(&fill.external_id)
+        .bind(&fill.symbol)
+        .bind(fill.side.as_str())
+        .bind(&fill.price)
+        .bind(&fill.quantity)
+        .bind(&fill.fee)
+        .bind(&fill.realized_pnl)
+        .bind(fill.executed_at)
+        .execute(&self.pool)
+        .await?;
+        
+        Ok(result.rows_affected())
+    }
+    
+    // Snapshot posisi dari satu sumber menggantikan snapshot sebelumnya. Dijaga fencing token supaya
+    // leader lama tidak menimpa snapshot yang lebih baru.
+    pub async fn replace_positions(
+        &self,
+        source: &str,
+        positions: &[(Uuid, NormalizedPosition)],
+        fence: Fence,
+    ) -> Result<(), sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+        
+        sqlx::query("DELETE FROM positions WHERE source = $1")
+            .bind(source)
+            .execute(&mut *tx)
+            .await?;
+        
+        for (trader_id, position) in positions {
+            sqlx::query(
+                r#"
+                INSERT INTO positions
+                    (trader_id, source, symbol, quantity, entry_price, mark_price, updated_at)
+                VALUES ($1, $2, $3, $4, $5, $6, NOW())
+                "#,
+            )
+            .bind(trader_id)
+            .bind(source)
+            .bind(&position.symbol)
+            .bind(&position.quantity)
+            .bind(&position.entry_price)
+            .bind(&position.mark_price)
+            .execute(&mut *tx)
+            .await?;
+        }
+        
+        LeaseRepository::check_fence(&mut tx, fence).await?;
+        tx.commit().await
+    }
+    
+    // `kind` memisahkan cursor per jenis data dari sumber yang sama ("fills", "cash_flows")
+    pub async fn get_cursor(&self, source: &str, kind: &str) -> Result<Option<DateTime<Utc>>, sqlx::Error> {
+        sqlx::query_scalar("SELECT last_executed_at FROM ingestion_cursors WHERE source = $1 AND kind = $2")
+            .bind(source)
+            .bind(kind)
+            .fetch_optional(&self.pool)
+            .await
+    }
+    
+    pub async fn set_cursor(
+        &self,
+        source: &str,
+        kind: &str,
+        last_executed_at: DateTime<Utc>,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            r#"
+            INSERT INTO ingestion_cursors (source, kind, last_executed_at, updated_at)
+            VALUES ($1, $2, $3, NOW())
+            ON CONFLICT (source, kind) DO UPDATE
+            SET last_executed_at = EXCLUDED.last_executed_at, updated_at = NOW()
+            "#,
+        )
+        .bind(source)
+        .bind(kind)
+        .bind(last_executed_at)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+    
+    // Return 1 kalau cash flow baru, 0 kalau sudah pernah di-ingest
+    pub async fn insert_cash_flow(
+        &self,
+        source: &str,
+        trader_id: Uuid,
+        cash_flow: &NormalizedCashFlow,
+    ) -> Result<u64, sqlx::Error> {
+        let kind = match cash_flow.kind {
+            CashFlowKind::Deposit => "deposit",
+            CashFlowKind::Withdrawal => "withdrawal",
+        };
+        
+        let result = sqlx::query(
+            r#"
+            INSERT INTO cash_flows (trader_id, source, external_id, kind, amount, occurred_at)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            ON CONFLICT (source, external_id) DO NOTHING
+            "#,
+        )
+        .bind(trader_id)
+        .bind(source)
+        .bind(&cash_flow.external_id)
+        .bind(kind)
+        .bind(&cash_flow.amount)
+        .bind(cash_flow.occurred_at)
+        .execute(&self.pool)
+        .await?;
+        
+        Ok(result.rows_affected())
+    }
+    
+    // Trader yang punya trade atau deposit
+    pub async fn get_active_trader_ids(&self) -> Result<Vec<Uuid>, sqlx::Error> {
+        sqlx::query_scalar(
+            r#"
+            SELECT trader_id FROM trades
+            UNION
+            SELECT trader_id FROM cash_flows
+            "#,
+        )
+        .fetch_all(&self.pool)
+        .await
+    }
+    
+    pub async fn get_trades(&self, trader_id: Uuid) -> Result<Vec<Trade>, sqlx::Error> {
+        sqlx::query_as(
+            r#"
+            SELECT 
+                id, trader_id, source, external_id, symbol, side,
+                price, quantity, fee, realized_pnl, executed_at
+            FROM trades
+            WHERE trader_id = $1
+            ORDER BY executed_at, external_id
+            "#,
+        )
+        .bind(trader_id)
+        .fetch_all(&self.pool)
+        .await
+    }
+    
+    pub async fn get_cash_flows(&self, trader_id: Uuid) -> Result<Vec<CashFlow>, sqlx::Error> {
+        let rows: Vec<(String, BigDecimal)> = sqlx::query_as(
+            r#"
+            SELECT kind, amount
+            FROM cash_flows
+            WHERE trader_id = $1
+            ORDER BY occurred_at, external_id
+            "#,
+        )
+        .bind(trader_id)
+        .fetch_all(&self.pool)
+        .await?;
+        
+        Ok(rows
+            .into_iter()
+            .map(|(kind, amount)| CashFlow {
+                kind: if kind == "withdrawal" { CashFlowKind::Withdrawal } else { CashFlowKind::Deposit },
+                amount,
+            })
+            .collect())
+    }
+    
+    // Mark price terbaru per simbol dari snapshot posisi semua sumber
+    pub async fn get_marks(&self) -> Result<HashMap<String, BigDecimal>, sqlx::Error> {
+        let rows: Vec<(String, BigDecimal)> = sqlx::query_as(
+            r#"
+            SELECT DISTINCT ON (symbol) symbol, mark_price
+            FROM positions
+            ORDER BY symbol, updated_at DESC
+            "#,
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        
+        Ok(rows.into_iter().collect())
+    }
+    
+    // Semua timeframe satu trader ditulis dalam satu transaksi yang dijaga fencing token job
+    pub async fn insert_performance(
+        &self,
+        trader_id: Uuid,
+        reports: &[(&str, PnlReport)],
+        calculated_at: DateTime<Utc>,
+        fence: Fence,
+    ) -> Result<u64, sqlx::Error> {
+        // roi disimpan sebagai DECIMAL(10, 4), jadi dibulatkan dan dibatasi supaya tidak overflow
+        let max_roi: BigDecimal = "999999.9999".parse().unwrap();
+        let mut tx = self.pool.begin().await?;
+        let mut rows = 0;
+        
+        for (timeframe, report) in reports {
+            let roi = report.roi.round(4).min(max_roi.clone()).max(-max_roi.clone());
+            
+            let result = sqlx::query(
+                r#"
+                INSERT INTO trader_performance 
+                    (trader_id, account_value, pnl, roi, volume, timeframe, calculated_at)
+                VALUES ($1, $2, $3, $4, $5, $6, $7)
+                ON CONFLICT DO NOTHING
+                "#,
+            )
+            .bind(trader_id)
+            .bind(&report.account_value)
+            .bind(&report.total_pnl)
+            .bind(roi)
+            .bind(&report.volume)
+            .bind(*timeframe)
+            .bind(calculated_at)
+            .execute(&mut *tx)
+            .await?;
+            rows += result.rows_affected();
+        }
+        
+        LeaseRepository::check_fence(&mut tx, fence).await?;
+        tx.commit().await?;
+        Ok(rows)
+    }
+}
@@ -0,0 +1,157 @@
+// src/repository/wallet_repo.rs
+use async_trait::async_trait;
+use sqlx::PgPool;
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+use crate::models::{eip55_checksum, WalletAddress};
+use crate::siwe::NonceStore;
+
+pub struct WalletRepository {
+    pool: PgPool,
+}
+
+impl WalletRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+    
+    pub async fn create_nonce(&self, nonce: &str, expires_at: DateTime<Utc>) -> Result<(), sqlx::Error> {
+        sqlx::query("INSERT INTO siwe_nonces (nonce, expires_at) VALUES ($1, $2)")
+            .bind(nonce)
+            .bind(expires_at)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+    
+    // Buat trader baru atau klaim trader yang sudah ada untuk wallet yang terverifikasi
+    pub async fn claim_trader(&self, wallet_address: &WalletAddress) -> Result<Uuid, sqlx::Error> {
+        sqlx::query_scalar(
+            r#"
+            INSERT INTO traders (chain, wallet_address, verified_at)
+            VALUES ($1, $2, NOW())
+            ON CONFLICT (chain, wallet_address) DO UPDATE
+            SET verified_at = NOW(), last_active = NOW()
+            RETURNING id
+            "#,
+        )
+        .bind(wallet_address.chain().as_str())
+        .bind(wallet_address.as_str())
+        .fetch_one(&self.pool)
+        .await
+    }
+    
+    pub async fn create_session(
+        &self,
+        token_hash: &str,
+        trader_id: Uuid,
+        expires_at: DateTime<Utc>,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query("INSERT INTO wallet_sessions (token_hash, trader_id, expires_at) VALUES ($1, $2, $3)")
+            .bind(token_hash)
+            .bind(trader_id)
+            .bind(expires_at)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+    
+    // Trader id dan wallet pemilik session yang masih berlaku
+    pub async fn find_session(&self, token_hash: &str) -> Result<Option<(Uuid, String)>, sqlx::Error> {
+        sqlx::query_as(
+            r#"
+            SELECT t.id, t.wallet_address
+            FROM wallet_sessions s
+            INNER JOIN traders t ON t.id = s.trader_id
+            WHERE s.token_hash = $1 AND s.expires_at > NOW()
+            "#,
+        )
+        .bind(token_hash)
+        .fetch_optional(&self.pool)
+        .await
+    }
+    
+    // Field yang None tidak diubah; `display_name = Some(None)` menghapus display name
+    pub async fn update_profile(
+        &self,
+        trader_id: Uuid,
+        display_name: Option<Option<&str>>,
+        opted_out: Option<bool>,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            r#"
+            UPDATE traders
+            SET display_name = CASE WHEN $2 THEN $3 ELSE display_name END,
+                opted_out = COALESCE($4, opted_out)
+            WHERE id = $1
+            "#,
+        )
+        .bind(trader_id)
+        .bind(display_name.is_some())
+        .bind(display_name.flatten())
+        .bind(opted_out)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+    
+    // Hapus nonce & session yang sudah kedaluwarsa; return (nonce, session) yang dihapus
+    pub async fn delete_expired(&self) -> Result<(u64, u64), sqlx::Error> {
+        let nonces = sqlx::query("DELETE FROM siwe_nonces WHERE expires_at < NOW()")
+            .execute(&self.pool)
+            .await?
+            .rows_affected();
+        let sessions = sqlx::query("DELETE FROM wallet_sessions WHERE expires_at < NOW()")
+            .execute(&self.pool)
+            .await?
+            .rows_affected();
+        
+        Ok((nonces, sessions))
+    }
+    
+    // Ubah alamat EVM huruf kecil (data lama / migrasi 009) ke checksum EIP-55
+    pub async fn backfill_evm_checksums(&self) -> Result<u64, sqlx::Error> {
+        let rows: Vec<(Uuid, String)> = sqlx::query_as(
+            r#"
+            SELECT id, wallet_address
+            FROM traders
+            WHERE chain = 'evm' AND wallet_address = LOWER(wallet_address) AND wallet_address ~ '[a-f]'
+            "#,
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        
+        let mut updated = 0;
+        for (id, wallet_address) in rows {
+            let checksummed = eip55_checksum(wallet_address.trim_start_matches("0x"));
+            updated += sqlx::query("UPDATE traders SET wallet_address = $2 WHERE id = $1")
+                .bind(id)
+                .bind(checksummed)
+                .execute(&self.pool)
+                .await?
+                .rows_affected();
+        }
+        
+        Ok(updated)
+    }
+}
+
+#[async_trait]
+impl NonceStore for WalletRepository {
+    // Tandai nonce terpakai; false kalau nonce tidak dikenal, kedaluwarsa atau sudah dipakai
+    async fn consume_nonce(&self, nonce: &str) -> Result<bool, sqlx::Error> {
+        let consumed: Option<String> = sqlx::query_scalar(
+            r#"
+            UPDATE siwe_nonces
+            SET consumed_at = NOW()
+            WHERE nonce = $1 AND consumed_at IS NULL AND expires_at > NOW()
+            RETURNING nonce
+            "#,
+        )
+        .bind(nonce)
+        .fetch_optional(&self.pool)
+        .await?;
+        
+        Ok(consumed.is_some())
+    }
+}
@@ -0,0 +1,621 @@
+// src/scheduler.rs
+// Scheduler kecil untuk background job: jadwal interval atau cron, tanpa overlap,
+// retry dengan backoff, dan riwayat eksekusi di tabel job_runs.
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex as StdMutex};
+use std::time::Duration;
+use tokio::sync::Notify;
+use tracing::Instrument;
+use crate::leader::{Fence, LeaderElection};
+use crate::models::JobRun;
+use crate::repository::JobRepository;
+
+pub type JobResult = Result<String, Box<dyn std::error::Error + Send + Sync>>;
+
+const MAX_RETRY_BACKOFF: Duration = Duration::from_secs(300);
+
+#[async_trait]
+pub trait Job: Send + Sync {
+    // Ok berisi ringkasan hasil yang disimpan di job_runs.detail.
+    // `fence` diteruskan ke transaksi tulis job supaya leader lama tidak bisa menimpa hasil leader baru.
+    async fn run(&self, fence: Fence) -> JobResult;
+}
+
+#[derive(Debug)]
+pub enum SchedulerError {
+    UnknownJob(String),
+    NotLeader(String),
+    // Trigger manual dilewati karena run sebelumnya masih berjalan
+    AlreadyRunning(String),
+    InvalidSchedule(String),
+    Database(sqlx::Error),
+}
+
+impl SchedulerError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            SchedulerError::UnknownJob(_) => "UNKNOWN_JOB",
+            SchedulerError::NotLeader(_) => "NOT_LEADER",
+            SchedulerError::AlreadyRunning(_) => "JOB_RUNNING",
+            SchedulerError::InvalidSchedule(_) => "INVALID_SCHEDULE",
+            SchedulerError::Database(_) => "INTERNAL",
+        }
+    }
+}
+
+impl fmt::Display for SchedulerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SchedulerError::UnknownJob(name) => write!(f, "unknown job: {}", name),
+            SchedulerError::NotLeader(name) => write!(f, "this instance is not the leader for job {}", name),
+            SchedulerError::AlreadyRunning(name) => write!(f, "job {} is already running", name),
+            SchedulerError::InvalidSchedule(msg) => write!(f, "invalid schedule: {}", msg),
+            SchedulerError::Database(e) => write!(f, "database error: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for SchedulerError {}
+
+impl From<sqlx::Error> for SchedulerError {
+    fn from(e: sqlx::Error) -> Self {
+        SchedulerError::Database(e)
+    }
+}
+
+// "every 60s" / "every 5m" / "every 1h", atau ekspresi cron dengan kolom detik ("0 */5 * * * *")
+#[derive(Debug, Clone)]
+pub enum Schedule {
+    Every(Duration),
+    Cron(Box<cron::Schedule>),
+}
+
+impl Schedule {
+    pub fn parse(spec: &str) -> Result<Self, SchedulerError> {
+        let spec = spec.trim();
+        
+        if let Some(every) = spec.strip_prefix("every ") {
+            let every = every.trim();
+            let (value, unit) = every.split_at(every.find(|c: char| !c.is_ascii_digit()).unwrap_or(every.len()));
+            let value: u64 = value
+                .parse()
+                .map_err(|_| SchedulerError::InvalidSchedule(format!("bad interval '{}'", spec)))?;
+            let seconds = match unit {
+                "s" | "" => Some(value),
+                "m" => value.checked_mul(60),
+                "h" => value.checked_mul(3600),
+                _ => return Err(SchedulerError::InvalidSchedule(format!("bad interval unit in '{}'", spec))),
+            }
+            .ok_or_else(|| SchedulerError::InvalidSchedule(format!("interval too large in '{}'", spec)))?;
+            if seconds == 0 {
+                return Err(SchedulerError::InvalidSchedule("interval must be positive".to_string()));
+            }
+            return Ok(Schedule::Every(Duration::from_secs(seconds)));
+        }
+        
+        cron::Schedule::from_str(spec)
+            .map(|schedule| Schedule::Cron(Box::new(schedule)))
+            .map_err(|e| SchedulerError::InvalidSchedule(format!("'{}': {}", spec, e)))
+    }
+    
+    pub fn next_after(&self, after: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        match self {
+            Schedule::Every(interval) => chrono::Duration::from_std(*interval).ok().map(|interval| after + interval),
+            Schedule::Cron(schedule) => schedule.after(&after).next(),
+        }
+    }
+}
+
+// Pengaturan satu job
+#[derive(Debug, Clone)]
+pub struct JobSpec {
+    pub spec: String,
+    pub schedule: Schedule,
+    pub max_attempts: u32,
+    pub retry_backoff: Duration,
+    pub jitter: Duration,
+}
+
+impl JobSpec {
+    pub fn new(spec: &str) -> Result<Self, SchedulerError> {
+        Ok(Self {
+            spec: spec.to_string(),
+            schedule: Schedule::parse(spec)?,
+            max_attempts: 3,
+            retry_backoff: Duration::from_secs(2),
+            jitter: Duration::from_secs(0),
+        })
+    }
+    
+    pub fn with_retries(mut self, max_attempts: u32, retry_backoff: Duration) -> Self {
+        self.max_attempts = max_attempts.max(1);
+        self.retry_backoff = retry_backoff;
+        self
+    }
+    
+    pub fn with_jitter(mut self, jitter: Duration) -> Self {
+        self.jitter = jitter;
+        self
+    }
+}
+
+struct JobEntry {
+    name: &'static str,
+    spec: JobSpec,
+    job: Arc<dyn Job>,
+    running: AtomicBool,
+    next_run: StdMutex<Option<DateTime<Utc>>>,
+    // Terisi selama job loop berjalan, yaitu selama replica ini leader job ini
+    fence: StdMutex<Option<Fence>>,
+    // Trigger manual diteruskan ke job loop supaya memakai fencing token yang sama
+    manual: Notify,
+}
+
+// Reset flag running walaupun eksekusi panic atau dibatalkan
+struct RunningGuard<'a>(&'a AtomicBool);
+
+impl Drop for RunningGuard<'_> {
+    fn drop(&mut self) {
+        self.0.store(false, Ordering::SeqCst);
+    }
+}
+
+// Hapus status leader job saat job loop berhenti (leadership hilang)
+struct LeadershipGuard<'a>(&'a JobEntry);
+
+impl Drop for LeadershipGuard<'_> {
+    fn drop(&mut self) {
+        *self.0.fence.lock().unwrap() = None;
+        *self.0.next_run.lock().unwrap() = None;
+    }
+}
+
+// Run yang dibatalkan di tengah jalan (task di-abort) tetap dicatat sebagai failed di job_runs
+struct UnfinishedRun {
+    scheduler: Arc<Scheduler>,
+    name: &'static str,
+    run_id: Option<i64>,
+    attempts: u32,
+}
+
+impl Drop for UnfinishedRun {
+    fn drop(&mut self) {
+        let Some(run_id) = self.run_id.take() else {
+            return;
+        };
+        let Ok(runtime) = tokio::runtime::Handle::try_current() else {
+            return;
+        };
+        
+        let scheduler = self.scheduler.clone();
+        let (name, attempts) = (self.name, self.attempts);
+        log::warn!("Job {} cancelled after losing leadership", name);
+        runtime.spawn(async move {
+            if let Err(e) = scheduler.runs
+                .finish_run(run_id, "failed", attempts as i32, Some("cancelled: leadership lost"))
+                .await
+            {
+                log::error!("Failed to record cancelled run of job {}: {:?}", name, e);
+            }
+        });
+    }
+}
+
+// Status job untuk endpoint admin
+#[derive(Debug, Serialize)]
+pub struct JobStatus {
+    pub name: &'static str,
+    pub schedule: String,
+    pub paused: bool,
+    pub running: bool,
+    // Hanya terisi di replica yang sedang menjadi leader job ini
+    pub next_run: Option<DateTime<Utc>>,
+    pub last_run: Option<JobRun>,
+}
+
+pub struct Scheduler {
+    jobs: BTreeMap<&'static str, Arc<JobEntry>>,
+    runs: JobRepository,
+    instance_id: String,
+}
+
+impl Scheduler {
+    pub fn new(runs: JobRepository, instance_id: String) -> Self {
+        Self {
+            jobs: BTreeMap::new(),
+            runs,
+            instance_id,
+        }
+    }
+    
+    pub fn register(mut self, name: &'static str, spec: JobSpec, job: Arc<dyn Job>) -> Self {
+        self.jobs.insert(name, Arc::new(JobEntry {
+            name,
+            spec,
+            job,
+            running: AtomicBool::new(false),
+            next_run: StdMutex::new(None),
+            fence: StdMutex::new(None),
+            manual: Notify::new(),
+        }));
+        self
+    }
+    
+    // Setiap job punya lease sendiri, jadi job yang berbeda bisa berjalan di replica yang berbeda
+    pub fn start(self: &Arc<Self>, leadership: &Arc<LeaderElection>) {
+        for name in self.jobs.keys().copied() {
+            let scheduler = self.clone();
+            tokio::spawn(leadership.clone().run(name, move |fence| scheduler.clone().job_loop(name, fence)));
+        }
+    }
+    
+    // Jalankan job sekarang lewat job loop replica ini; hanya bisa di replica yang memegang lease job tersebut.
+    // AlreadyRunning kalau dilewati karena run sebelumnya masih berjalan (tetap dicatat sebagai skipped).
+    pub fn trigger(self: &Arc<Self>, name: &str) -> Result<(), SchedulerError> {
+        let entry = self.entry(name)?;
+        if entry.fence.lock().unwrap().is_none() {
+            return Err(SchedulerError::NotLeader(entry.name.to_string()));
+        }
+        
+        if entry.running.load(Ordering::SeqCst) {
+            log::warn!("Job {} is still running, skipping manual run", entry.name);
+            let scheduler = self.clone();
+            tokio::spawn(async move {
+                if let Err(e) = scheduler.runs
+                    .record_skipped(entry.name, "manual", &scheduler.instance_id, "previous run still in progress")
+                    .await
+                {
+                    log::error!("Failed to record skipped run for job {}: {:?}", entry.name, e);
+                }
+            });
+            return Err(SchedulerError::AlreadyRunning(name.to_string()));
+        }
+        
+        entry.manual.notify_one();
+        Ok(())
+    }
+    
+    pub async fn set_paused(&self, name: &str, paused: bool) -> Result<(), SchedulerError> {
+        let entry = self.entry(name)?;
+        self.runs.set_paused(entry.name, paused).await?;
+        Ok(())
+    }
+    
+    pub async fn list(&self) -> Result<Vec<JobStatus>, SchedulerError> {
+        let mut jobs = Vec::with_capacity(self.jobs.len());
+        
+        for entry in self.jobs.values() {
+            // Dibaca sebelum query supaya lock tidak tertahan melewati await
+            let next_run = *entry.next_run.lock().unwrap();
+            jobs.push(JobStatus {
+                name: entry.name,
+                schedule: entry.spec.spec.clone(),
+                paused: self.runs.is_paused(entry.name).await?,
+                running: entry.running.load(Ordering::SeqCst),
+                next_run,
+                last_run: self.runs.recent_runs(entry.name, 1).await?.into_iter().next(),
+            });
+        }
+        
+        Ok(jobs)
+    }
+    
+    pub async fn runs(&self, name: &str, limit: i64) -> Result<Vec<JobRun>, SchedulerError> {
+        let entry = self.entry(name)?;
+        Ok(self.runs.recent_runs(entry.name, limit).await?)
+    }
+    
+    fn entry(&self, name: &str) -> Result<Arc<JobEntry>, SchedulerError> {
+        self.jobs
+            .get(name)
+            .cloned()
+            .ok_or_else(|| SchedulerError::UnknownJob(name.to_string()))
+    }
+    
+    // Loop jadwal satu job; hanya berjalan selama replica ini leader untuk job tersebut.
+    // Run dieksekusi langsung di task ini (tidak di-spawn), jadi ikut berhenti saat LeaderElection meng-abort task-nya.
+    async fn job_loop(self: Arc<Self>, name: &'static str, fence: Fence) {
+        let Some(entry) = self.jobs.get(name).cloned() else {
+            return;
+        };
+        *entry.fence.lock().unwrap() = Some(fence);
+        let _leadership = LeadershipGuard(&entry);
+        
+        loop {
+            let now = Utc::now();
+            let Some(next) = entry.spec.schedule.next_after(now) else {
+                log::warn!("Job {} has no upcoming run for schedule '{}'", name, entry.spec.spec);
+                return;
+            };
+            let next = next + jitter(entry.spec.jitter);
+            *entry.next_run.lock().unwrap() = Some(next);
+            
+            let trigger = tokio::select! {
+                _ = tokio::time::sleep((next - now).to_std().unwrap_or_default()) => "schedule",
+                _ = entry.manual.notified() => "manual",
+            };
+            
+            // Trigger manual tetap dijalankan walaupun job di-pause
+            if trigger == "schedule" {
+                match self.runs.is_paused(name).await {
+                    Ok(false) => {}
+                    Ok(true) => {
+                        log::debug!("Job {} is paused, skipping run", name);
+                        continue;
+                    }
+                    Err(e) => {
+                        log::error!("Failed to read pause state for job {}: {:?}", name, e);
+                        continue;
+                    }
+                }
+            }
+            
+            self.execute(&entry, trigger, fence).await;
+        }
+    }
+    
+    // Satu eksekusi job dengan retry. Karena dijalankan berurutan di job loop, run tidak pernah overlap.
+    async fn execute(self: &Arc<Self>, entry: &JobEntry, trigger: &'static str, fence: Fence) {
+        entry.running.store(true, Ordering::SeqCst);
+        let _guard = RunningGuard(&entry.running);
+        
+        // Root span sendiri per run; query SQL/Redis di dalam job menjadi child span-nya
+        let span = tracing::info_span!(
+            "job.run",
+            job = entry.name,
+            trigger,
+            fencing_token = fence.token,
+            attempts = tracing::field::Empty,
+            status = tracing::field::Empty,
+        );
+        
+        async move {
+            let mut unfinished = UnfinishedRun {
+                scheduler: self.clone(),
+                name: entry.name,
+                run_id: None,
+                attempts: 0,
+            };
+            
+            unfinished.run_id = match self.runs.start_run(entry.name, trigger, &self.instance_id).await {
+                Ok(run_id) => Some(run_id),
+                Err(e) => {
+                    log::error!("Failed to record start of job {}: {:?}", entry.name, e);
+                    None
+                }
+            };
+            
+            let result = run_with_retries(entry, fence, &mut unfinished.attempts).await;
+            let attempts = unfinished.attempts;
+            
+            let (status, detail) = match &result {
+                Ok(detail) => {
+                    log::info!("Job {} succeeded: {}", entry.name, detail);
+                    ("succeeded", detail.as_str())
+                }
+                Err(e) => {
+                    log::error!("Job {} failed after {} attempts: {}", entry.name, attempts, e);
+                    ("failed", e.as_str())
+                }
+            };
+            
+            let span = tracing::Span::current();
+            span.record("attempts", attempts);
+            span.record("status", status);
+            
+            if let Some(run_id) = unfinished.run_id.take() {
+                if let Err(e) = self.runs.finish_run(run_id, status, attempts as i32, Some(detail)).await {
+                    log::error!("Failed to record result of job {}: {:?}", entry.name, e);
+                }
+            }
+        }.instrument(span).await
+    }
+}
+
+// Jalankan job sampai berhasil atau max_attempts habis. `attempts` ikut diperbarui tiap percobaan
+// supaya run yang dibatalkan di tengah retry tetap tercatat dengan jumlah percobaan yang benar.
+async fn run_with_retries(entry: &JobEntry, fence: Fence, attempts: &mut u32) -> Result<String, String> {
+    let mut backoff = entry.spec.retry_backoff;
+    loop {
+        *attempts += 1;
+        match entry.job.run(fence).await {
+            Ok(detail) => return Ok(detail),
+            Err(e) if *attempts < entry.spec.max_attempts => {
+                log::warn!(
+                    "Job {} failed (attempt {}/{}), retrying in {:?}: {}",
+                    entry.name, attempts, entry.spec.max_attempts, backoff, e
+                );
+                tokio::time::sleep(backoff).await;
+                backoff = next_backoff(backoff);
+            }
+            Err(e) => return Err(e.to_string()),
+        }
+    }
+}
+
+// Backoff berlipat dua setiap retry, paling lama 5 menit
+fn next_backoff(backoff: Duration) -> Duration {
+    backoff.saturating_mul(2).min(MAX_RETRY_BACKOFF)
+}
+
+// Jitter acak sederhana (0..max) supaya job antar replica/restart tidak jalan serentak
+fn jitter(max: Duration) -> chrono::Duration {
+    let max_ms = max.as_millis() as u64;
+    if max_ms == 0 {
+        return chrono::Duration::zero();
+    }
+    chrono::Duration::milliseconds((Utc::now().timestamp_subsec_nanos() as u64 % max_ms) as i64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::AtomicU32;
+    use std::time::Instant;
+    use chrono::TimeZone;
+    use sqlx::postgres::PgPoolOptions;
+    
+    const FENCE: Fence = Fence { lease: "test_job", token: 7 };
+    
+    // Gagal `failures` kali dulu, setelah itu berhasil
+    struct FlakyJob {
+        failures: u32,
+        calls: AtomicU32,
+    }
+    
+    #[async_trait]
+    impl Job for FlakyJob {
+        async fn run(&self, fence: Fence) -> JobResult {
+            assert_eq!(fence, FENCE);
+            let call = self.calls.fetch_add(1, Ordering::SeqCst) + 1;
+            if call <= self.failures {
+                return Err(format!("failure #{}", call).into());
+            }
+            Ok(format!("ok after {} calls", call))
+        }
+    }
+    
+    fn entry(failures: u32, max_attempts: u32, backoff: Duration) -> (JobEntry, Arc<FlakyJob>) {
+        let job = Arc::new(FlakyJob { failures, calls: AtomicU32::new(0) });
+        let entry = JobEntry {
+            name: "test_job",
+            spec: JobSpec::new("every 60s").unwrap().with_retries(max_attempts, backoff),
+            job: job.clone(),
+            running: AtomicBool::new(false),
+            next_run: StdMutex::new(None),
+            fence: StdMutex::new(None),
+            manual: Notify::new(),
+        };
+        (entry, job)
+    }
+    
+    // Pool tidak pernah benar-benar terhubung; catatan skipped yang gagal ditulis hanya di-log
+    fn scheduler() -> Arc<Scheduler> {
+        let pool = PgPoolOptions::new()
+            .acquire_timeout(Duration::from_millis(50))
+            .connect_lazy("postgres://localhost:1/unused")
+            .unwrap();
+        let (entry, _) = entry(0, 1, Duration::ZERO);
+        Arc::new(
+            Scheduler::new(JobRepository::new(pool), "test-instance".to_string())
+                .register("test_job", entry.spec, entry.job),
+        )
+    }
+    
+    fn every(spec: &str) -> Duration {
+        match Schedule::parse(spec).unwrap() {
+            Schedule::Every(interval) => interval,
+            Schedule::Cron(_) => panic!("expected interval for '{}'", spec),
+        }
+    }
+    
+    #[test]
+    fn parse_intervals() {
+        assert_eq!(every("every 60s"), Duration::from_secs(60));
+        assert_eq!(every("  every 45 "), Duration::from_secs(45));
+        assert_eq!(every("every 5m"), Duration::from_secs(300));
+        assert_eq!(every("every 2h"), Duration::from_secs(7200));
+    }
+    
+    #[test]
+    fn parse_rejects_bad_intervals() {
+        for spec in ["every 0s", "every 10d", "every s", "every -5m", "every 18446744073709551615m", "every 9999999999999999h"] {
+            assert!(
+                matches!(Schedule::parse(spec), Err(SchedulerError::InvalidSchedule(_))),
+                "{} should be rejected",
+                spec
+            );
+        }
+    }
+    
+    #[test]
+    fn parse_cron() {
+        let schedule = Schedule::parse("0 30 3 * * *").unwrap();
+        let after = Utc.with_ymd_and_hms(2024, 5, 1, 12, 0, 0).unwrap();
+        assert_eq!(schedule.next_after(after), Some(Utc.with_ymd_and_hms(2024, 5, 2, 3, 30, 0).unwrap()));
+        
+        assert!(matches!(Schedule::parse("not a cron"), Err(SchedulerError::InvalidSchedule(_))));
+    }
+    
+    #[test]
+    fn interval_next_after() {
+        let after = Utc.with_ymd_and_hms(2024, 5, 1, 12, 0, 0).unwrap();
+        let next = Schedule::parse("every 5m").unwrap().next_after(after);
+        assert_eq!(next, Some(Utc.with_ymd_and_hms(2024, 5, 1, 12, 5, 0).unwrap()));
+    }
+    
+    #[test]
+    fn backoff_doubles_up_to_limit() {
+        assert_eq!(next_backoff(Duration::from_secs(2)), Duration::from_secs(4));
+        assert_eq!(next_backoff(Duration::from_secs(200)), MAX_RETRY_BACKOFF);
+        assert_eq!(next_backoff(Duration::MAX), MAX_RETRY_BACKOFF);
+    }
+    
+    #[tokio::test]
+    async fn retries_until_success_with_backoff() {
+        let (entry, job) = entry(2, 3, Duration::from_millis(20));
+        let mut attempts = 0;
+        let started = Instant::now();
+        
+        let result = run_with_retries(&entry, FENCE, &mut attempts).await;
+        
+        assert_eq!(result, Ok("ok after 3 calls".to_string()));
+        assert_eq!(attempts, 3);
+        assert_eq!(job.calls.load(Ordering::SeqCst), 3);
+        // 20ms sebelum retry pertama, 40ms sebelum retry kedua
+        assert!(started.elapsed() >= Duration::from_millis(60), "{:?}", started.elapsed());
+    }
+    
+    #[tokio::test]
+    async fn gives_up_after_max_attempts() {
+        let (entry, job) = entry(10, 3, Duration::from_millis(1));
+        let mut attempts = 0;
+        
+        let result = run_with_retries(&entry, FENCE, &mut attempts).await;
+        
+        assert_eq!(result, Err("failure #3".to_string()));
+        assert_eq!(attempts, 3);
+        assert_eq!(job.calls.load(Ordering::SeqCst), 3);
+    }
+    
+    #[tokio::test]
+    async fn single_attempt_does_not_retry() {
+        let (entry, job) = entry(1, 1, Duration::from_secs(60));
+        let mut attempts = 0;
+        
+        assert_eq!(run_with_retries(&entry, FENCE, &mut attempts).await, Err("failure #1".to_string()));
+        assert_eq!(job.calls.load(Ordering::SeqCst), 1);
+    }
+    
+    #[tokio::test]
+    async fn trigger_requires_known_job_and_leadership() {
+        let scheduler = scheduler();
+        assert!(matches!(scheduler.trigger("nope"), Err(SchedulerError::UnknownJob(_))));
+        assert!(matches!(scheduler.trigger("test_job"), Err(SchedulerError::NotLeader(_))));
+    }
+    
+    #[tokio::test]
+    async fn trigger_skips_when_running() {
+        let scheduler = scheduler();
+        let entry = scheduler.entry("test_job").unwrap();
+        *entry.fence.lock().unwrap() = Some(FENCE);
+        entry.running.store(true, Ordering::SeqCst);
+        
+        assert!(matches!(scheduler.trigger("test_job"), Err(SchedulerError::AlreadyRunning(_))));
+        // Job loop tidak dibangunkan
+        let woken = tokio::time::timeout(Duration::from_millis(20), entry.manual.notified()).await;
+        assert!(woken.is_err());
+        
+        entry.running.store(false, Ordering::SeqCst);
+        scheduler.trigger("test_job").unwrap();
+        tokio::time::timeout(Duration::from_millis(20), entry.manual.notified())
+            .await
+            .expect("job loop should be notified");
+    }
+}
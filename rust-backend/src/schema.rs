@@ -1,41 +1,44 @@
 // src/schema.rs
 use async_graphql::*;
+use async_graphql::connection::{Connection, Edge};
 use chrono::{DateTime, Utc};
-use bigdecimal::BigDecimal;
-use serde::{Deserialize, Serialize};
+use futures_util::stream::{self, Stream, StreamExt};
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex as StdMutex};
+use tokio::sync::{broadcast, OnceCell};
+use crate::auth::{Role, RoleGuard};
+use crate::scheduler::Scheduler;
+use crate::error::AppError;
+use crate::pagination::LeaderboardCursor;
 use crate::repository::LeaderboardRepository;
-use crate::models::LeaderboardEntry;
+use crate::models::{validate_bucket, validate_timeframe, Chain, LeaderboardEntry, LeaderboardSnapshot, Movers, PerformancePoint, WalletAddress};
+use crate::websocket::LeaderboardEvent;
 
-#[derive(SimpleObject, Serialize, Deserialize, Clone)]
-pub struct LeaderboardEntry {
-    #[graphql(name = "rank")]
-    pub rank: i32,
-    
-    #[graphql(name = "traderWallet")]
-    pub trader_wallet: String,
-    
-    #[graphql(name = "accountValue")]
-    pub account_value: BigDecimal,
-    
-    #[graphql(name = "pnl")]
-    pub pnl: BigDecimal,
-    
-    #[graphql(name = "roi")]
-    pub roi: BigDecimal,
-    
-    #[graphql(name = "volume")]
-    pub volume: BigDecimal,
-    
-    #[graphql(name = "lastUpdated")]
-    pub last_updated: DateTime<Utc>,
-}
-
-#[derive(InputObject)]
+#[derive(InputObject, Clone)]
 pub struct LeaderboardFilter {
     pub limit: Option<i32>,
     pub offset: Option<i32>,
     pub sort_by: Option<String>,
     pub sort_order: Option<String>,
+    pub timeframe: Option<String>,
+}
+
+pub type LeaderboardSchema = Schema<LeaderboardQuery, LeaderboardMutation, LeaderboardSubscription>;
+
+// Field tambahan di level connection
+#[derive(SimpleObject)]
+pub struct LeaderboardConnectionFields {
+    pub total_count: i64,
+    // Versi leaderboard yang melatarbelakangi halaman ini
+    pub version: i64,
+}
+
+// Leaderboard seperti yang tersimpan di satu snapshot
+#[derive(SimpleObject)]
+pub struct LeaderboardSnapshotView {
+    pub snapshot: LeaderboardSnapshot,
+    pub entries: Vec<LeaderboardEntry>,
 }
 
 #[derive(Default)]
@@ -48,41 +51,198 @@ impl LeaderboardQuery {
         ctx: &Context<'_>,
         filter: Option<LeaderboardFilter>,
     ) -> Result<Vec<LeaderboardEntry>> {
-        let repo = ctx.data::<LeaderboardRepository>()?;
+        let repo = ctx.data::<Arc<LeaderboardRepository>>()?;
         let filter = filter.unwrap_or_default();
         
         let limit = filter.limit.unwrap_or(100);
         let offset = filter.offset.unwrap_or(0);
         let sort_by = filter.sort_by.as_deref().unwrap_or("roi");
         let sort_order = filter.sort_order.as_deref().unwrap_or("desc");
+        let timeframe = validate_timeframe(filter.timeframe.as_deref()).map_err(gql_error)?;
         
-        let entries = repo.get_leaderboard(limit, offset, sort_by, sort_order)
+        let entries = repo.get_leaderboard(limit, offset, sort_by, sort_order, timeframe)
             .await
-            .map_err(|e| Error::new(e.to_string()))?;
+            .map_err(gql_error)?;
         
         Ok(entries)
     }
     
+    // Relay-style pagination; halaman berikutnya tetap di versi leaderboard cursor walaupun view di-refresh
+    async fn leaderboard_connection(
+        &self,
+        ctx: &Context<'_>,
+        first: Option<i32>,
+        after: Option<String>,
+        sort_by: Option<String>,
+        sort_order: Option<String>,
+        timeframe: Option<String>,
+    ) -> Result<Connection<String, LeaderboardEntry, LeaderboardConnectionFields>> {
+        let repo = ctx.data::<Arc<LeaderboardRepository>>()?;
+        let first = first.unwrap_or(50).clamp(1, 500);
+        let timeframe = validate_timeframe(timeframe.as_deref()).map_err(gql_error)?;
+        
+        let page = async {
+            let cursor = after.as_deref().map(LeaderboardCursor::decode).transpose()?;
+            repo.get_leaderboard_page(
+                first,
+                cursor.as_ref(),
+                sort_by.as_deref().unwrap_or("roi"),
+                sort_order.as_deref().unwrap_or("desc"),
+                timeframe,
+            )
+            .await
+        }
+        .await
+        .map_err(gql_error)?;
+        
+        let mut connection = Connection::with_additional_fields(
+            after.is_some(),
+            page.has_next_page,
+            LeaderboardConnectionFields {
+                total_count: page.total_count,
+                version: page.version,
+            },
+        );
+        connection.edges.extend(
+            page.entries
+                .iter()
+                .map(|entry| Edge::new(page.cursor_for(entry), entry.clone())),
+        );
+        
+        Ok(connection)
+    }
+    
+    // Leaderboard pada waktu tertentu (`asOf`) atau snapshot tertentu (`snapshotId`)
+    async fn leaderboard_snapshot(
+        &self,
+        ctx: &Context<'_>,
+        as_of: Option<DateTime<Utc>>,
+        snapshot_id: Option<i64>,
+        timeframe: Option<String>,
+        limit: Option<i32>,
+        offset: Option<i32>,
+    ) -> Result<Option<LeaderboardSnapshotView>> {
+        let repo = ctx.data::<Arc<LeaderboardRepository>>()?;
+        let timeframe = validate_timeframe(timeframe.as_deref()).map_err(gql_error)?;
+        
+        let snapshot = match (as_of, snapshot_id) {
+            (Some(as_of), None) => repo.get_snapshot_as_of(as_of).await,
+            (None, Some(snapshot_id)) => repo.get_snapshot(snapshot_id).await,
+            _ => return Err(AppError::invalid("exactly one of `asOf` or `snapshotId` is required").extend()),
+        }
+        .map_err(gql_error)?;
+        
+        let Some(snapshot) = snapshot else {
+            return Ok(None);
+        };
+        
+        let entries = repo.get_snapshot_entries(
+            snapshot.id,
+            timeframe,
+            limit.unwrap_or(100).clamp(1, 500),
+            offset.unwrap_or(0).max(0),
+        )
+        .await
+        .map_err(gql_error)?;
+        
+        Ok(Some(LeaderboardSnapshotView { snapshot, entries }))
+    }
+    
+    async fn leaderboard_snapshots(
+        &self,
+        ctx: &Context<'_>,
+        limit: Option<i32>,
+        before: Option<i64>,
+    ) -> Result<Vec<LeaderboardSnapshot>> {
+        let repo = ctx.data::<Arc<LeaderboardRepository>>()?;
+        let snapshots = repo.list_snapshots(limit.unwrap_or(50).clamp(1, 500), before)
+            .await
+            .map_err(gql_error)?;
+        
+        Ok(snapshots)
+    }
+    
+    // Kenaikan & penurunan rank terbesar dalam `windowHours` terakhir
+    async fn movers(
+        &self,
+        ctx: &Context<'_>,
+        timeframe: Option<String>,
+        window_hours: Option<i64>,
+        limit: Option<i32>,
+    ) -> Result<Movers> {
+        let repo = ctx.data::<Arc<LeaderboardRepository>>()?;
+        let window_hours = window_hours.unwrap_or(24).clamp(1, 24 * 90);
+        let since = Utc::now() - chrono::Duration::hours(window_hours);
+        let timeframe = validate_timeframe(timeframe.as_deref()).map_err(gql_error)?;
+        
+        let movers = repo.get_movers(
+            timeframe,
+            since,
+            limit.unwrap_or(10).clamp(1, 100),
+        )
+        .await
+        .map_err(gql_error)?;
+        
+        Ok(movers)
+    }
+    
     async fn trader_stats(
         &self,
         ctx: &Context<'_>,
         wallet_address: String,
+        timeframe: Option<String>,
     ) -> Result<LeaderboardEntry> {
-        let repo = ctx.data::<LeaderboardRepository>()?;
+        let repo = ctx.data::<Arc<LeaderboardRepository>>()?;
+        let timeframe = validate_timeframe(timeframe.as_deref()).map_err(gql_error)?;
+        let wallet = parse_wallet(&wallet_address, None)?;
         
-        // Query untuk trader spesifik
-        let entry = repo.get_trader_by_wallet(&wallet_address)
+        // Query untuk trader spesifik; wallet yang tidak ada di leaderboard -> NOT_FOUND
+        repo.get_trader_by_wallet(wallet.as_str(), timeframe)
             .await
-            .map_err(|e| Error::new(e.to_string()))?;
+            .map_err(gql_error)?
+            .ok_or_else(|| {
+                AppError::NotFound(format!("trader {} has no {} stats", wallet, timeframe)).extend()
+            })
+    }
+    
+    async fn trader_history(
+        &self,
+        ctx: &Context<'_>,
+        wallet_address: String,
+        timeframe: Option<String>,
+        from: Option<DateTime<Utc>>,
+        to: Option<DateTime<Utc>>,
+        bucket: Option<String>,
+    ) -> Result<Vec<PerformancePoint>> {
+        let repo = ctx.data::<Arc<LeaderboardRepository>>()?;
+        let wallet = parse_wallet(&wallet_address, None)?;
+        let to = to.unwrap_or_else(Utc::now);
+        let from = from.unwrap_or(to - chrono::Duration::days(7));
+        let timeframe = validate_timeframe(timeframe.as_deref()).map_err(gql_error)?;
+        let bucket = validate_bucket(bucket.as_deref()).map_err(gql_error)?;
+        
+        if from >= to {
+            return Err(AppError::invalid("`from` must be before `to`").extend());
+        }
         
-        Ok(entry)
+        let points = repo.get_trader_history(
+            wallet.as_str(),
+            timeframe,
+            from,
+            to,
+            bucket,
+        )
+        .await
+        .map_err(gql_error)?;
+        
+        Ok(points)
     }
     
     async fn leaderboard_count(&self, ctx: &Context<'_>) -> Result<i32> {
-        let repo = ctx.data::<LeaderboardRepository>()?;
+        let repo = ctx.data::<Arc<LeaderboardRepository>>()?;
         let count = repo.get_total_traders()
             .await
-            .map_err(|e| Error::new(e.to_string()))?;
+            .map_err(gql_error)?;
         
         Ok(count)
     }
@@ -93,39 +253,305 @@ pub struct LeaderboardMutation;
 
 #[Object]
 impl LeaderboardMutation {
+    #[graphql(guard = "RoleGuard::new(Role::Operator)")]
     async fn refresh_leaderboard(&self, ctx: &Context<'_>) -> Result<bool> {
-        let repo = ctx.data::<LeaderboardRepository>()?;
-        repo.refresh_materialized_view()
-            .await
-            .map_err(|e| Error::new(e.to_string()))?;
+        let scheduler = ctx.data::<Arc<Scheduler>>()?;
+        
+        // Sama dengan endpoint REST: trigger job refresh_leaderboard, yang clear cache dan broadcast ke subscriber
+        scheduler.trigger("refresh_leaderboard").map_err(gql_error)?;
         
         Ok(true)
     }
     
+    #[graphql(guard = "RoleGuard::new(Role::Admin)")]
     async fn add_trader(
         &self,
         ctx: &Context<'_>,
         wallet_address: String,
+        chain: Option<Chain>,
     ) -> Result<bool> {
         let pool = ctx.data::<sqlx::PgPool>()?;
+        let wallet = parse_wallet(&wallet_address, chain)?;
         
-        sqlx::query!(
+        sqlx::query(
             r#"
-            INSERT INTO traders (wallet_address)
-            VALUES ($1)
-            ON CONFLICT (wallet_address) DO UPDATE
+            INSERT INTO traders (chain, wallet_address)
+            VALUES ($1, $2)
+            ON CONFLICT (chain, wallet_address) DO UPDATE
             SET last_active = NOW()
             "#,
-            wallet_address
         )
+        .bind(wallet.chain().as_str())
+        .bind(wallet.as_str())
         .execute(pool)
         .await
-        .map_err(|e| Error::new(e.to_string()))?;
+        .map_err(gql_error)?;
         
         Ok(true)
     }
 }
 
+#[derive(Default)]
+pub struct LeaderboardSubscription {
+    feed: Arc<LeaderboardFeed>,
+}
+
+#[Subscription]
+impl LeaderboardSubscription {
+    // Kirim leaderboard terbaru (sesuai filter) setiap kali materialized view di-refresh
+    async fn leaderboard_updated(
+        &self,
+        ctx: &Context<'_>,
+        filter: Option<LeaderboardFilter>,
+    ) -> Result<impl Stream<Item = Vec<LeaderboardEntry>>> {
+        let repo = ctx.data::<Arc<LeaderboardRepository>>()?.clone();
+        let rx = ctx.data::<broadcast::Sender<String>>()?.subscribe();
+        let key = FeedKey::from_filter(filter.unwrap_or_default()).map_err(gql_error)?;
+        
+        Ok(self.feed.clone().stream(repo, rx, key).filter_map(|entries| async move { entries }))
+    }
+    
+    // Hanya kirim event kalau rank trader berubah dibanding refresh sebelumnya
+    async fn trader_rank_changed(
+        &self,
+        ctx: &Context<'_>,
+        wallet_address: String,
+        timeframe: Option<String>,
+    ) -> Result<impl Stream<Item = LeaderboardEntry>> {
+        let repo = ctx.data::<Arc<LeaderboardRepository>>()?.clone();
+        let rx = ctx.data::<broadcast::Sender<String>>()?.subscribe();
+        let timeframe = validate_timeframe(timeframe.as_deref()).map_err(gql_error)?;
+        let wallet_address = parse_wallet(&wallet_address, None)?.to_string();
+        let last_rank = repo.get_trader_by_wallet(&wallet_address, timeframe).await.ok().flatten().map(|e| e.rank);
+        
+        let entries = refresh_events(rx).then(move |_| {
+            let repo = repo.clone();
+            let wallet_address = wallet_address.clone();
+            async move { repo.get_trader_by_wallet(&wallet_address, timeframe).await.ok().flatten() }
+        });
+        Ok(rank_changes(entries, last_rank))
+    }
+    
+    // Kirim top N baru kalau urutan wallet di dalamnya berubah
+    async fn top_n_changed(
+        &self,
+        ctx: &Context<'_>,
+        n: i32,
+        timeframe: Option<String>,
+    ) -> Result<impl Stream<Item = Vec<LeaderboardEntry>>> {
+        if !(1..=100).contains(&n) {
+            return Err(AppError::invalid("n must be between 1 and 100").extend());
+        }
+        
+        let repo = ctx.data::<Arc<LeaderboardRepository>>()?.clone();
+        let rx = ctx.data::<broadcast::Sender<String>>()?.subscribe();
+        let key = FeedKey {
+            timeframe: validate_timeframe(timeframe.as_deref()).map_err(gql_error)?,
+            sort_by: "roi".to_string(),
+            sort_order: "desc".to_string(),
+            limit: n,
+            offset: 0,
+        };
+        
+        Ok(top_n_changes(self.feed.clone().stream(repo, rx, key)))
+    }
+}
+
+// Parameter query leaderboard yang sudah dinormalisasi; subscription dengan key sama berbagi hasil query
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct FeedKey {
+    timeframe: &'static str,
+    sort_by: String,
+    sort_order: String,
+    limit: i32,
+    offset: i32,
+}
+
+// Hasil query leaderboard per versi: tiap filter berbeda di-query sekali per refresh, berapa pun subscriber-nya.
+// Hanya versi terbaru yang disimpan.
+#[derive(Default)]
+struct LeaderboardFeed {
+    state: StdMutex<FeedState>,
+}
+
+#[derive(Default)]
+struct FeedState {
+    version: i64,
+    results: HashMap<FeedKey, Arc<OnceCell<Option<Vec<LeaderboardEntry>>>>>,
+}
+
+impl FeedKey {
+    // Limit dibatasi 1..=100 seperti topNChanged, karena hasilnya dikirim ulang ke klien setiap refresh
+    fn from_filter(filter: LeaderboardFilter) -> Result<Self, AppError> {
+        Ok(Self {
+            timeframe: validate_timeframe(filter.timeframe.as_deref())?,
+            sort_by: filter.sort_by.unwrap_or_else(|| "roi".to_string()),
+            sort_order: filter.sort_order.unwrap_or_else(|| "desc".to_string()).to_lowercase(),
+            limit: filter.limit.unwrap_or(100).clamp(1, 100),
+            offset: filter.offset.unwrap_or(0).max(0),
+        })
+    }
+}
+
+impl LeaderboardFeed {
+    // Hasil untuk setiap refresh; None kalau query gagal atau versinya sudah tersusul
+    fn stream(
+        self: Arc<Self>,
+        repo: Arc<LeaderboardRepository>,
+        rx: broadcast::Receiver<String>,
+        key: FeedKey,
+    ) -> impl Stream<Item = Option<Vec<LeaderboardEntry>>> {
+        refresh_events(rx).then(move |version| {
+            let feed = self.clone();
+            let repo = repo.clone();
+            let key = key.clone();
+            async move { feed.get(version, key, |key| fetch_leaderboard(repo, key)).await }
+        })
+    }
+    
+    async fn get<F, Fut>(&self, version: i64, key: FeedKey, fetch: F) -> Option<Vec<LeaderboardEntry>>
+    where
+        F: FnOnce(FeedKey) -> Fut,
+        Fut: Future<Output = Option<Vec<LeaderboardEntry>>>,
+    {
+        let cell = {
+            let mut state = self.state.lock().unwrap();
+            // Subscriber yang tertinggal: event versi yang lebih baru sudah ada di antreannya
+            if version < state.version {
+                return None;
+            }
+            if version > state.version {
+                state.version = version;
+                state.results.clear();
+            }
+            state.results.entry(key.clone()).or_default().clone()
+        };
+        
+        cell.get_or_init(|| fetch(key)).await.clone()
+    }
+}
+
+async fn fetch_leaderboard(repo: Arc<LeaderboardRepository>, key: FeedKey) -> Option<Vec<LeaderboardEntry>> {
+    match repo.get_leaderboard(key.limit, key.offset, &key.sort_by, &key.sort_order, key.timeframe).await {
+        Ok(entries) => Some(entries),
+        Err(e) => {
+            log::error!("Failed to fetch leaderboard for subscription: {:?}", e);
+            None
+        }
+    }
+}
+
+// Entry trader hanya diteruskan kalau rank-nya berbeda dari yang terakhir dikirim
+fn rank_changes(
+    entries: impl Stream<Item = Option<LeaderboardEntry>>,
+    mut last_rank: Option<i32>,
+) -> impl Stream<Item = LeaderboardEntry> {
+    entries.filter_map(move |entry| {
+        let changed = match entry {
+            Some(entry) if last_rank != Some(entry.rank) => {
+                last_rank = Some(entry.rank);
+                Some(entry)
+            }
+            _ => None,
+        };
+        async move { changed }
+    })
+}
+
+// Top N hanya diteruskan kalau urutan wallet-nya berubah
+fn top_n_changes(
+    results: impl Stream<Item = Option<Vec<LeaderboardEntry>>>,
+) -> impl Stream<Item = Vec<LeaderboardEntry>> {
+    let mut last_wallets: Vec<String> = Vec::new();
+    results.filter_map(move |entries| {
+        let changed = entries.and_then(|entries| {
+            let wallets: Vec<String> = entries.iter().map(|e| e.trader_wallet.clone()).collect();
+            if wallets == last_wallets {
+                return None;
+            }
+            last_wallets = wallets;
+            Some(entries)
+        });
+        async move { changed }
+    })
+}
+
+// Validasi & normalisasi wallet; input salah dikembalikan sebagai error GraphQL dengan `code`
+fn parse_wallet(input: &str, chain: Option<Chain>) -> Result<WalletAddress> {
+    WalletAddress::parse(input, chain).map_err(gql_error)
+}
+
+// Root field Query/Mutation yang boleh menjadi label metrik; perbarui kalau ada resolver root baru
+const ROOT_FIELDS: [&str; 10] = [
+    "leaderboard",
+    "leaderboardConnection",
+    "leaderboardSnapshot",
+    "leaderboardSnapshots",
+    "movers",
+    "traderStats",
+    "traderHistory",
+    "leaderboardCount",
+    "refreshLeaderboard",
+    "addTrader",
+];
+
+// Label metrik untuk satu request: root field dari operasi yang dijalankan. Nama operasi dan query
+// berasal dari klien, jadi hanya nama di ROOT_FIELDS yang dipakai; selain satu root field yang dikenal -> "other".
+pub fn operation_label(request: &Request) -> &'static str {
+    use async_graphql::parser::types::{DocumentOperations, Selection};
+    
+    let Ok(document) = parser::parse_query(&request.query) else {
+        return "other";
+    };
+    let operation = match (&document.operations, request.operation_name.as_deref()) {
+        (DocumentOperations::Single(operation), _) => operation,
+        (DocumentOperations::Multiple(operations), Some(name)) => match operations.get(name) {
+            Some(operation) => operation,
+            None => return "other",
+        },
+        // Operasi bernama tetap masuk Multiple walaupun hanya satu
+        (DocumentOperations::Multiple(operations), None) if operations.len() == 1 => {
+            operations.values().next().unwrap()
+        }
+        (DocumentOperations::Multiple(_), None) => return "other",
+    };
+    
+    let items = &operation.node.selection_set.node.items;
+    match items.as_slice() {
+        [item] => match &item.node {
+            Selection::Field(field) => ROOT_FIELDS
+                .iter()
+                .find(|root| **root == field.node.name.node.as_str())
+                .copied()
+                .unwrap_or("other"),
+            _ => "other",
+        },
+        _ => "other",
+    }
+}
+
+// Error repository/validasi -> error GraphQL dengan extensions.code; detail SQL tidak dikirim ke klien
+fn gql_error(e: impl Into<AppError>) -> Error {
+    e.into().extend()
+}
+
+// Ubah broadcast channel `ws_tx` jadi stream versi leaderboard yang baru di-refresh.
+// Kalau receiver tertinggal (lagged), event yang masih tersimpan tetap diterima setelahnya, jadi cukup lanjut.
+fn refresh_events(rx: broadcast::Receiver<String>) -> impl Stream<Item = i64> {
+    stream::unfold(rx, |mut rx| async move {
+        loop {
+            match rx.recv().await {
+                Ok(msg) => match serde_json::from_str::<LeaderboardEvent>(&msg) {
+                    Ok(LeaderboardEvent::LeaderboardUpdated { version }) => return Some((version, rx)),
+                    Err(e) => log::warn!("Ignoring unknown leaderboard event: {:?}", e),
+                },
+                Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                Err(broadcast::error::RecvError::Closed) => return None,
+            }
+        }
+    })
+}
+
 impl Default for LeaderboardFilter {
     fn default() -> Self {
         Self {
@@ -133,6 +559,249 @@ impl Default for LeaderboardFilter {
             offset: Some(0),
             sort_by: Some("roi".to_string()),
             sort_order: Some("desc".to_string()),
+            timeframe: Some("daily".to_string()),
+        }
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bigdecimal::BigDecimal;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+    use sqlx::postgres::PgPoolOptions;
+    
+    fn entry(rank: i32, wallet: &str) -> LeaderboardEntry {
+        LeaderboardEntry {
+            rank,
+            trader_wallet: wallet.to_string(),
+            display_name: None,
+            account_value: BigDecimal::from(1000),
+            pnl: BigDecimal::from(100),
+            roi: BigDecimal::from(10),
+            volume: BigDecimal::from(5000),
+            last_updated: Utc::now(),
+            previous_rank: None,
+            rank_change: None,
+            is_new: false,
         }
     }
-}
\ No newline at end of file
+    
+    fn key(limit: i32) -> FeedKey {
+        FeedKey {
+            timeframe: "daily",
+            sort_by: "roi".to_string(),
+            sort_order: "desc".to_string(),
+            limit,
+            offset: 0,
+        }
+    }
+    
+    fn wallets(entries: &[LeaderboardEntry]) -> Vec<&str> {
+        entries.iter().map(|e| e.trader_wallet.as_str()).collect()
+    }
+    
+    fn event(version: i64) -> String {
+        serde_json::to_string(&LeaderboardEvent::LeaderboardUpdated { version }).unwrap()
+    }
+    
+    // Skema dengan pool yang tidak pernah terhubung: cukup untuk validasi argumen sebelum query
+    fn schema(ws_tx: broadcast::Sender<String>) -> LeaderboardSchema {
+        let pool = PgPoolOptions::new()
+            .acquire_timeout(Duration::from_millis(50))
+            .connect_lazy("postgres://localhost:1/unused")
+            .unwrap();
+        Schema::build(LeaderboardQuery, LeaderboardMutation, LeaderboardSubscription::default())
+            .data(Arc::new(LeaderboardRepository::new(pool)))
+            .data(ws_tx)
+            .finish()
+    }
+    
+    async fn subscription_error_code(query: &str) -> serde_json::Value {
+        let (ws_tx, _) = broadcast::channel(4);
+        let response = schema(ws_tx).execute_stream(query).next().await.unwrap();
+        assert_eq!(response.errors.len(), 1, "{:?}", response.errors);
+        let extensions = response.errors[0].extensions.as_ref().unwrap();
+        serde_json::to_value(extensions).unwrap()["code"].clone()
+    }
+    
+    #[tokio::test]
+    async fn feed_queries_each_filter_once_per_version() {
+        let feed = LeaderboardFeed::default();
+        let queries = AtomicUsize::new(0);
+        let fetch = |key: FeedKey| {
+            queries.fetch_add(1, Ordering::SeqCst);
+            async move { Some(vec![entry(1, &format!("0xtop{}", key.limit))]) }
+        };
+        
+        let (a, b) = tokio::join!(feed.get(1, key(10), fetch), feed.get(1, key(10), fetch));
+        assert_eq!(queries.load(Ordering::SeqCst), 1);
+        assert_eq!(wallets(&a.unwrap()), ["0xtop10"]);
+        assert_eq!(wallets(&b.unwrap()), ["0xtop10"]);
+        
+        // Filter lain di versi yang sama: query sendiri
+        assert_eq!(wallets(&feed.get(1, key(20), fetch).await.unwrap()), ["0xtop20"]);
+        assert_eq!(queries.load(Ordering::SeqCst), 2);
+        
+        // Versi baru: query ulang, dan versi lama tidak dihitung lagi
+        assert!(feed.get(2, key(10), fetch).await.is_some());
+        assert_eq!(queries.load(Ordering::SeqCst), 3);
+        assert!(feed.get(1, key(10), fetch).await.is_none());
+        assert_eq!(queries.load(Ordering::SeqCst), 3);
+    }
+    
+    #[tokio::test]
+    async fn feed_shares_failures_too() {
+        let feed = LeaderboardFeed::default();
+        let queries = AtomicUsize::new(0);
+        let fetch = |_| {
+            queries.fetch_add(1, Ordering::SeqCst);
+            async { None }
+        };
+        
+        assert!(feed.get(1, key(10), fetch).await.is_none());
+        assert!(feed.get(1, key(10), fetch).await.is_none());
+        assert_eq!(queries.load(Ordering::SeqCst), 1);
+    }
+    
+    #[test]
+    fn leaderboard_updated_filter_is_clamped() {
+        let filter = LeaderboardFilter {
+            limit: Some(1_000_000),
+            offset: Some(-5),
+            sort_by: None,
+            sort_order: Some("ASC".to_string()),
+            timeframe: Some("weekly".to_string()),
+        };
+        let clamped = FeedKey::from_filter(filter).unwrap();
+        assert_eq!(clamped.limit, 100);
+        assert_eq!(clamped.offset, 0);
+        assert_eq!(clamped.sort_by, "roi");
+        assert_eq!(clamped.sort_order, "asc");
+        assert_eq!(clamped.timeframe, "weekly");
+        
+        let filter = LeaderboardFilter { limit: Some(0), ..LeaderboardFilter::default() };
+        assert_eq!(FeedKey::from_filter(filter).unwrap().limit, 1);
+        
+        // Filter yang sama (setelah normalisasi) memakai key yang sama
+        let filter = LeaderboardFilter { sort_order: Some("DESC".to_string()), ..LeaderboardFilter::default() };
+        assert_eq!(FeedKey::from_filter(filter).unwrap(), key(100));
+    }
+    
+    #[tokio::test]
+    async fn refresh_events_yield_versions() {
+        let (tx, rx) = broadcast::channel(8);
+        tx.send(event(3)).unwrap();
+        tx.send("not an event".to_string()).unwrap();
+        tx.send(event(4)).unwrap();
+        drop(tx);
+        
+        let versions: Vec<i64> = refresh_events(rx).collect().await;
+        assert_eq!(versions, [3, 4]);
+    }
+    
+    #[tokio::test]
+    async fn refresh_events_continue_after_lag() {
+        let (tx, rx) = broadcast::channel(2);
+        for version in 1..=5 {
+            tx.send(event(version)).unwrap();
+        }
+        drop(tx);
+        
+        let versions: Vec<i64> = refresh_events(rx).collect().await;
+        assert_eq!(versions, [4, 5]);
+    }
+    
+    #[tokio::test]
+    async fn trader_rank_changed_only_emits_new_ranks() {
+        let entries = stream::iter([
+            Some(entry(3, "0xa")),
+            Some(entry(3, "0xa")),
+            None,
+            Some(entry(1, "0xa")),
+            Some(entry(1, "0xa")),
+            Some(entry(2, "0xa")),
+        ]);
+        let ranks: Vec<i32> = rank_changes(entries, Some(3)).map(|e| e.rank).collect().await;
+        assert_eq!(ranks, [1, 2]);
+        
+        // Trader yang belum ada di leaderboard saat subscribe: entry pertama langsung dikirim
+        let entries = stream::iter([Some(entry(7, "0xa"))]);
+        let ranks: Vec<i32> = rank_changes(entries, None).map(|e| e.rank).collect().await;
+        assert_eq!(ranks, [7]);
+    }
+    
+    #[tokio::test]
+    async fn top_n_changed_only_emits_new_orders() {
+        let results = stream::iter([
+            Some(vec![entry(1, "0xa"), entry(2, "0xb")]),
+            Some(vec![entry(1, "0xa"), entry(2, "0xb")]),
+            None,
+            Some(vec![entry(1, "0xb"), entry(2, "0xa")]),
+            Some(vec![entry(1, "0xb"), entry(2, "0xc")]),
+        ]);
+        let orders: Vec<Vec<String>> = top_n_changes(results)
+            .map(|entries| entries.into_iter().map(|e| e.trader_wallet).collect())
+            .collect()
+            .await;
+        assert_eq!(orders, [["0xa", "0xb"], ["0xb", "0xa"], ["0xb", "0xc"]]);
+    }
+    
+    #[tokio::test]
+    async fn subscriptions_validate_arguments() {
+        assert_eq!(subscription_error_code("subscription { topNChanged(n: 0) { rank } }").await, "INVALID_INPUT");
+        assert_eq!(subscription_error_code("subscription { topNChanged(n: 101) { rank } }").await, "INVALID_INPUT");
+        assert_eq!(
+            subscription_error_code("subscription { topNChanged(n: 10, timeframe: \"yearly\") { rank } }").await,
+            "INVALID_INPUT"
+        );
+        assert_eq!(
+            subscription_error_code("subscription { leaderboardUpdated(filter: { timeframe: \"yearly\" }) { rank } }").await,
+            "INVALID_INPUT"
+        );
+        assert_eq!(
+            subscription_error_code("subscription { traderRankChanged(walletAddress: \"not-a-wallet\") { rank } }").await,
+            "INVALID_WALLET_ADDRESS"
+        );
+    }
+    
+    fn label(query: &str, operation_name: Option<&str>) -> &'static str {
+        let mut request = Request::new(query);
+        if let Some(name) = operation_name {
+            request = request.operation_name(name);
+        }
+        operation_label(&request)
+    }
+    
+    #[test]
+    fn operation_label_uses_known_root_field() {
+        assert_eq!(label("{ leaderboard { rank } }", None), "leaderboard");
+        assert_eq!(label("query Stats { traderStats(walletAddress: \"0xabc\") { rank } }", None), "traderStats");
+        assert_eq!(label("mutation { refreshLeaderboard }", None), "refreshLeaderboard");
+    }
+    
+    #[test]
+    fn operation_label_folds_unknown_root_fields() {
+        assert_eq!(label("{ __typename }", None), "other");
+        assert_eq!(label("{ attackerControlledName12345 }", None), "other");
+        // Nama operasi dari klien tidak pernah dipakai sebagai label
+        assert_eq!(label("query leaderboard { secret }", None), "other");
+        assert_eq!(label("not graphql at all {", None), "other");
+    }
+    
+    #[test]
+    fn operation_label_needs_exactly_one_root_field() {
+        assert_eq!(label("{ leaderboard { rank } leaderboardCount }", None), "other");
+        assert_eq!(label("{ ...Root } fragment Root on LeaderboardQuery { leaderboard { rank } }", None), "other");
+    }
+    
+    #[test]
+    fn operation_label_with_multiple_operations() {
+        let document = "query A { leaderboard { rank } } mutation B { refreshLeaderboard }";
+        assert_eq!(label(document, Some("A")), "leaderboard");
+        assert_eq!(label(document, Some("B")), "refreshLeaderboard");
+        // Tanpa operationName (atau nama yang tidak ada) operasinya ambigu
+        assert_eq!(label(document, None), "other");
+        assert_eq!(label(document, Some("C")), "other");
+    }
+}
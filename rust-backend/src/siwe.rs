@@ -0,0 +1,476 @@
+// src/siwe.rs
+// Sign-In with Ethereum (EIP-4361): parsing pesan dan recover signer dari signature personal_sign
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, Utc};
+use k256::ecdsa::{RecoveryId, Signature, VerifyingKey};
+use sha3::{Digest, Keccak256};
+use std::fmt;
+use crate::error::AppError;
+
+const HEADER_SUFFIX: &str = " wants you to sign in with your Ethereum account:";
+
+// Umur nonce dari /auth/siwe/nonce; pesan yang `Issued At`-nya lebih tua dari ini pasti sudah basi
+pub const NONCE_TTL_MINUTES: i64 = 10;
+
+// Toleransi selisih jam antara wallet dan server untuk `Issued At`
+const CLOCK_SKEW_SECS: i64 = 60;
+
+#[derive(Debug)]
+pub enum SiweError {
+    Malformed(String),
+    InvalidSignature(String),
+    AddressMismatch,
+    DomainMismatch,
+    UriMismatch,
+    ChainMismatch(u64),
+    IssuedInFuture,
+    Expired,
+    NotYetValid,
+    NonceRejected,
+}
+
+impl fmt::Display for SiweError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SiweError::Malformed(msg) => write!(f, "malformed SIWE message: {}", msg),
+            SiweError::InvalidSignature(msg) => write!(f, "invalid signature: {}", msg),
+            SiweError::AddressMismatch => write!(f, "signature was not produced by the message address"),
+            SiweError::DomainMismatch => write!(f, "message domain does not match this server"),
+            SiweError::UriMismatch => write!(f, "message uri does not match the message domain"),
+            SiweError::ChainMismatch(chain_id) => write!(f, "chain id {} is not accepted", chain_id),
+            SiweError::IssuedInFuture => write!(f, "message is issued in the future"),
+            SiweError::Expired => write!(f, "message has expired"),
+            SiweError::NotYetValid => write!(f, "message is not valid yet"),
+            SiweError::NonceRejected => write!(f, "invalid or expired nonce"),
+        }
+    }
+}
+
+impl std::error::Error for SiweError {}
+
+#[derive(Debug, Clone)]
+pub struct SiweMessage {
+    pub domain: String,
+    pub address: String,
+    // Hanya teks untuk ditampilkan wallet ke user; tidak ikut divalidasi
+    #[allow(dead_code)]
+    pub statement: Option<String>,
+    pub uri: String,
+    pub chain_id: u64,
+    pub nonce: String,
+    pub issued_at: DateTime<Utc>,
+    pub expiration_time: Option<DateTime<Utc>>,
+    pub not_before: Option<DateTime<Utc>>,
+}
+
+impl SiweMessage {
+    pub fn parse(message: &str) -> Result<Self, SiweError> {
+        let mut lines = message.lines();
+        
+        let domain = lines
+            .next()
+            .and_then(|line| line.strip_suffix(HEADER_SUFFIX))
+            .ok_or_else(|| SiweError::Malformed("missing header".to_string()))?
+            .to_string();
+        
+        let address = lines
+            .next()
+            .map(str::trim)
+            .filter(|address| is_hex_address(address))
+            .ok_or_else(|| SiweError::Malformed("missing or invalid address".to_string()))?
+            .to_string();
+        
+        let mut statement = None;
+        let mut uri = None;
+        let mut version = None;
+        let mut chain_id = None;
+        let mut nonce = None;
+        let mut issued_at = None;
+        let mut expiration_time = None;
+        let mut not_before = None;
+        
+        for line in lines {
+            if let Some(value) = line.strip_prefix("URI: ") {
+                uri = Some(value.to_string());
+            } else if let Some(value) = line.strip_prefix("Version: ") {
+                version = Some(value.to_string());
+            } else if let Some(value) = line.strip_prefix("Chain ID: ") {
+                chain_id = Some(value.parse().map_err(|_| SiweError::Malformed("invalid chain id".to_string()))?);
+            } else if let Some(value) = line.strip_prefix("Nonce: ") {
+                nonce = Some(value.to_string());
+            } else if let Some(value) = line.strip_prefix("Issued At: ") {
+                issued_at = Some(parse_timestamp(value)?);
+            } else if let Some(value) = line.strip_prefix("Expiration Time: ") {
+                expiration_time = Some(parse_timestamp(value)?);
+            } else if let Some(value) = line.strip_prefix("Not Before: ") {
+                not_before = Some(parse_timestamp(value)?);
+            } else if !line.is_empty() && uri.is_none() && statement.is_none() {
+                // Statement opsional, berada di antara address dan field URI
+                statement = Some(line.to_string());
+            }
+        }
+        
+        let version = version.ok_or_else(|| SiweError::Malformed("missing version".to_string()))?;
+        if version != "1" {
+            return Err(SiweError::Malformed(format!("unsupported version {}", version)));
+        }
+        
+        let nonce = nonce.ok_or_else(|| SiweError::Malformed("missing nonce".to_string()))?;
+        if nonce.len() < 8 || !nonce.chars().all(|c| c.is_ascii_alphanumeric()) {
+            return Err(SiweError::Malformed("nonce must be at least 8 alphanumeric characters".to_string()));
+        }
+        
+        Ok(Self {
+            domain,
+            address,
+            statement,
+            uri: uri.ok_or_else(|| SiweError::Malformed("missing uri".to_string()))?,
+            chain_id: chain_id.ok_or_else(|| SiweError::Malformed("missing chain id".to_string()))?,
+            nonce,
+            issued_at: issued_at.ok_or_else(|| SiweError::Malformed("missing issued at".to_string()))?,
+            expiration_time,
+            not_before,
+        })
+    }
+    
+    // Cek domain, uri, chain & waktu berlaku, lalu pastikan signature memang dari `address`.
+    // Nonce dicek terpisah (lihat `verify_and_consume`) karena butuh storage.
+    pub fn verify(
+        &self,
+        raw_message: &str,
+        signature: &str,
+        expected_domain: &str,
+        chain_ids: &[u64],
+        now: DateTime<Utc>,
+    ) -> Result<(), SiweError> {
+        if self.domain != expected_domain {
+            return Err(SiweError::DomainMismatch);
+        }
+        if !uri_authority(&self.uri).is_some_and(|authority| authority.eq_ignore_ascii_case(&self.domain)) {
+            return Err(SiweError::UriMismatch);
+        }
+        if !chain_ids.contains(&self.chain_id) {
+            return Err(SiweError::ChainMismatch(self.chain_id));
+        }
+        if self.issued_at > now + Duration::seconds(CLOCK_SKEW_SECS) {
+            return Err(SiweError::IssuedInFuture);
+        }
+        // Nonce hanya hidup NONCE_TTL_MINUTES, jadi pesan yang lebih tua tidak mungkin memakai nonce yang sah
+        if self.issued_at < now - Duration::minutes(NONCE_TTL_MINUTES) {
+            return Err(SiweError::Expired);
+        }
+        if self.expiration_time.is_some_and(|expires| now >= expires) {
+            return Err(SiweError::Expired);
+        }
+        if self.not_before.is_some_and(|not_before| now < not_before) {
+            return Err(SiweError::NotYetValid);
+        }
+        
+        let signer = recover_address(raw_message, signature)?;
+        if !signer.eq_ignore_ascii_case(&self.address) {
+            return Err(SiweError::AddressMismatch);
+        }
+        
+        Ok(())
+    }
+    
+    // `verify` lalu pakai nonce-nya. Nonce baru dikonsumsi setelah signature valid,
+    // supaya request palsu tidak bisa menghabiskan nonce milik orang lain.
+    pub async fn verify_and_consume(
+        &self,
+        raw_message: &str,
+        signature: &str,
+        expected_domain: &str,
+        chain_ids: &[u64],
+        nonces: &impl NonceStore,
+        now: DateTime<Utc>,
+    ) -> Result<(), AppError> {
+        self.verify(raw_message, signature, expected_domain, chain_ids, now)?;
+        
+        if !nonces.consume_nonce(&self.nonce).await? {
+            return Err(SiweError::NonceRejected.into());
+        }
+        
+        Ok(())
+    }
+}
+
+// Penyimpanan nonce sekali pakai (di produksi: tabel siwe_nonces lewat WalletRepository)
+#[async_trait]
+pub trait NonceStore: Send + Sync {
+    // false kalau nonce tidak dikenal, kedaluwarsa atau sudah dipakai
+    async fn consume_nonce(&self, nonce: &str) -> Result<bool, sqlx::Error>;
+}
+
+// Recover address (0x + 40 hex, lowercase) dari signature EIP-191 `personal_sign`
+pub fn recover_address(message: &str, signature: &str) -> Result<String, SiweError> {
+    let bytes = hex::decode(signature.trim().trim_start_matches("0x"))
+        .map_err(|e| SiweError::InvalidSignature(e.to_string()))?;
+    if bytes.len() != 65 {
+        return Err(SiweError::InvalidSignature("signature must be 65 bytes".to_string()));
+    }
+    
+    let signature = Signature::from_slice(&bytes[..64])
+        .map_err(|e| SiweError::InvalidSignature(e.to_string()))?;
+    // Wallet mengirim v = 27/28, sebagian library memakai 0/1
+    let v = match bytes[64] {
+        27 | 28 => bytes[64] - 27,
+        0 | 1 => bytes[64],
+        other => return Err(SiweError::InvalidSignature(format!("invalid recovery id {}", other))),
+    };
+    let recovery_id = RecoveryId::try_from(v).map_err(|e| SiweError::InvalidSignature(e.to_string()))?;
+    
+    let hash = eip191_hash(message);
+    let key = VerifyingKey::recover_from_prehash(&hash, &signature, recovery_id)
+        .map_err(|e| SiweError::InvalidSignature(e.to_string()))?;
+    
+    Ok(public_key_address(&key))
+}
+
+pub fn public_key_address(key: &VerifyingKey) -> String {
+    let point = key.to_encoded_point(false);
+    // Buang prefix 0x04, address = 20 byte terakhir dari keccak256(public key)
+    let hash = Keccak256::digest(&point.as_bytes()[1..]);
+    format!("0x{}", hex::encode(&hash[12..]))
+}
+
+// Hash token session untuk disimpan di database (token asli hanya dipegang client)
+pub fn session_token_hash(token: &str) -> String {
+    hex::encode(Keccak256::digest(token.as_bytes()))
+}
+
+fn eip191_hash(message: &str) -> [u8; 32] {
+    let mut hasher = Keccak256::new();
+    hasher.update(format!("\x19Ethereum Signed Message:\n{}", message.len()).as_bytes());
+    hasher.update(message.as_bytes());
+    hasher.finalize().into()
+}
+
+fn is_hex_address(value: &str) -> bool {
+    value.len() == 42
+        && value.starts_with("0x")
+        && value[2..].chars().all(|c| c.is_ascii_hexdigit())
+}
+
+// https://host:port/path -> host:port
+fn uri_authority(uri: &str) -> Option<&str> {
+    let rest = &uri[uri.find("://")? + 3..];
+    let authority = rest.split(['/', '?', '#']).next()?;
+    let authority = authority.rsplit('@').next()?;
+    (!authority.is_empty()).then_some(authority)
+}
+
+fn parse_timestamp(value: &str) -> Result<DateTime<Utc>, SiweError> {
+    DateTime::parse_from_rfc3339(value)
+        .map(|t| t.with_timezone(&Utc))
+        .map_err(|_| SiweError::Malformed(format!("invalid timestamp {}", value)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use k256::ecdsa::SigningKey;
+    use std::collections::HashSet;
+    use std::sync::Mutex;
+    
+    const DOMAIN: &str = "leaderboard.example";
+    
+    struct MemoryNonces(Mutex<HashSet<String>>);
+    
+    impl MemoryNonces {
+        fn with(nonce: &str) -> Self {
+            Self(Mutex::new(HashSet::from([nonce.to_string()])))
+        }
+    }
+    
+    #[async_trait]
+    impl NonceStore for MemoryNonces {
+        async fn consume_nonce(&self, nonce: &str) -> Result<bool, sqlx::Error> {
+            Ok(self.0.lock().unwrap().remove(nonce))
+        }
+    }
+    
+    fn now() -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 5, 1, 12, 0, 0).unwrap()
+    }
+    
+    fn signing_key(seed: u8) -> SigningKey {
+        SigningKey::from_slice(&[seed; 32]).unwrap()
+    }
+    
+    fn address_of(key: &SigningKey) -> String {
+        public_key_address(key.verifying_key())
+    }
+    
+    fn message(address: &str, extra: &[&str]) -> String {
+        let mut lines = vec![
+            format!("{}{}", DOMAIN, HEADER_SUFFIX),
+            address.to_string(),
+            String::new(),
+            "Sign in to the leaderboard".to_string(),
+            String::new(),
+            format!("URI: https://{}/login", DOMAIN),
+            "Version: 1".to_string(),
+            "Chain ID: 1".to_string(),
+            "Nonce: abcdef12345".to_string(),
+            "Issued At: 2024-05-01T11:59:00Z".to_string(),
+        ];
+        lines.extend(extra.iter().map(|line| line.to_string()));
+        lines.join("\n")
+    }
+    
+    // personal_sign: signature 65 byte r || s || v dengan v = 27/28
+    fn sign(key: &SigningKey, message: &str) -> String {
+        let (signature, recovery_id) = key.sign_prehash_recoverable(&eip191_hash(message)).unwrap();
+        let mut bytes = signature.to_bytes().to_vec();
+        bytes.push(recovery_id.to_byte() + 27);
+        format!("0x{}", hex::encode(bytes))
+    }
+    
+    fn verify(raw: &str, signature: &str) -> Result<(), SiweError> {
+        SiweMessage::parse(raw)?.verify(raw, signature, DOMAIN, &[1], now())
+    }
+    
+    #[test]
+    fn parses_message_fields() {
+        let raw = message("0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed", &["Expiration Time: 2024-05-01T12:10:00Z"]);
+        let parsed = SiweMessage::parse(&raw).unwrap();
+        
+        assert_eq!(parsed.domain, DOMAIN);
+        assert_eq!(parsed.address, "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed");
+        assert_eq!(parsed.statement.as_deref(), Some("Sign in to the leaderboard"));
+        assert_eq!(parsed.uri, "https://leaderboard.example/login");
+        assert_eq!(parsed.chain_id, 1);
+        assert_eq!(parsed.nonce, "abcdef12345");
+        assert_eq!(parsed.issued_at, Utc.with_ymd_and_hms(2024, 5, 1, 11, 59, 0).unwrap());
+        assert_eq!(parsed.expiration_time, Some(Utc.with_ymd_and_hms(2024, 5, 1, 12, 10, 0).unwrap()));
+        assert_eq!(parsed.not_before, None);
+    }
+    
+    #[test]
+    fn rejects_malformed_messages() {
+        let raw = message("0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed", &[]);
+        
+        assert!(matches!(SiweMessage::parse("hello"), Err(SiweError::Malformed(_))));
+        assert!(matches!(
+            SiweMessage::parse(&raw.replace("Version: 1", "Version: 2")),
+            Err(SiweError::Malformed(_))
+        ));
+        assert!(matches!(
+            SiweMessage::parse(&raw.replace("Nonce: abcdef12345", "Nonce: short")),
+            Err(SiweError::Malformed(_))
+        ));
+    }
+    
+    #[test]
+    fn accepts_valid_signature() {
+        let key = signing_key(7);
+        let raw = message(&address_of(&key), &[]);
+        let signature = sign(&key, &raw);
+        
+        assert_eq!(recover_address(&raw, &signature).unwrap(), address_of(&key));
+        verify(&raw, &signature).unwrap();
+    }
+    
+    #[test]
+    fn rejects_wrong_domain() {
+        let key = signing_key(7);
+        let raw = message(&address_of(&key), &[]);
+        let signature = sign(&key, &raw);
+        let parsed = SiweMessage::parse(&raw).unwrap();
+        
+        assert!(matches!(
+            parsed.verify(&raw, &signature, "evil.example", &[1], now()),
+            Err(SiweError::DomainMismatch)
+        ));
+    }
+    
+    #[test]
+    fn rejects_uri_on_other_host() {
+        let key = signing_key(7);
+        let raw = message(&address_of(&key), &[]).replace("URI: https://leaderboard.example/login", "URI: https://evil.example/login");
+        
+        assert!(matches!(verify(&raw, &sign(&key, &raw)), Err(SiweError::UriMismatch)));
+    }
+    
+    #[test]
+    fn rejects_unexpected_chain_id() {
+        let key = signing_key(7);
+        let raw = message(&address_of(&key), &[]).replace("Chain ID: 1", "Chain ID: 137");
+        
+        assert!(matches!(verify(&raw, &sign(&key, &raw)), Err(SiweError::ChainMismatch(137))));
+    }
+    
+    #[test]
+    fn rejects_expired_message() {
+        let key = signing_key(7);
+        let raw = message(&address_of(&key), &["Expiration Time: 2024-05-01T11:59:30Z"]);
+        
+        assert!(matches!(verify(&raw, &sign(&key, &raw)), Err(SiweError::Expired)));
+    }
+    
+    #[test]
+    fn rejects_not_before_in_future() {
+        let key = signing_key(7);
+        let raw = message(&address_of(&key), &["Not Before: 2024-05-01T12:05:00Z"]);
+        
+        assert!(matches!(verify(&raw, &sign(&key, &raw)), Err(SiweError::NotYetValid)));
+    }
+    
+    #[test]
+    fn rejects_issued_at_outside_nonce_lifetime() {
+        let key = signing_key(7);
+        let future = message(&address_of(&key), &[]).replace("2024-05-01T11:59:00Z", "2024-05-01T12:30:00Z");
+        let stale = message(&address_of(&key), &[]).replace("2024-05-01T11:59:00Z", "2024-05-01T11:00:00Z");
+        
+        assert!(matches!(verify(&future, &sign(&key, &future)), Err(SiweError::IssuedInFuture)));
+        assert!(matches!(verify(&stale, &sign(&key, &stale)), Err(SiweError::Expired)));
+    }
+    
+    #[test]
+    fn rejects_wrong_signer() {
+        let owner = signing_key(7);
+        let attacker = signing_key(9);
+        let raw = message(&address_of(&owner), &[]);
+        
+        assert!(matches!(verify(&raw, &sign(&attacker, &raw)), Err(SiweError::AddressMismatch)));
+    }
+    
+    #[test]
+    fn rejects_tampered_message() {
+        let key = signing_key(7);
+        let raw = message(&address_of(&key), &[]);
+        let signature = sign(&key, &raw);
+        let tampered = raw.replace("Sign in to the leaderboard", "Transfer everything");
+        
+        assert!(matches!(verify(&tampered, &signature), Err(SiweError::AddressMismatch)));
+    }
+    
+    #[tokio::test]
+    async fn rejects_reused_nonce() {
+        let key = signing_key(7);
+        let raw = message(&address_of(&key), &[]);
+        let signature = sign(&key, &raw);
+        let parsed = SiweMessage::parse(&raw).unwrap();
+        let nonces = MemoryNonces::with("abcdef12345");
+        
+        parsed.verify_and_consume(&raw, &signature, DOMAIN, &[1], &nonces, now()).await.unwrap();
+        
+        let reused = parsed.verify_and_consume(&raw, &signature, DOMAIN, &[1], &nonces, now()).await;
+        assert!(matches!(reused, Err(AppError::Unauthorized(_))));
+    }
+    
+    #[tokio::test]
+    async fn invalid_signature_does_not_consume_nonce() {
+        let key = signing_key(7);
+        let raw = message(&address_of(&key), &[]);
+        let parsed = SiweMessage::parse(&raw).unwrap();
+        let nonces = MemoryNonces::with("abcdef12345");
+        
+        let forged = parsed.verify_and_consume(&raw, &sign(&signing_key(9), &raw), DOMAIN, &[1], &nonces, now()).await;
+        assert!(matches!(forged, Err(AppError::Unauthorized(_))));
+        
+        parsed.verify_and_consume(&raw, &sign(&key, &raw), DOMAIN, &[1], &nonces, now()).await.unwrap();
+    }
+}
@@ -0,0 +1,158 @@
+// src/telemetry.rs
+// Logging & tracing: semua log (termasuk `log::info!` lama) lewat `tracing`, output teks atau JSON,
+// dan span diekspor ke collector OTLP kalau endpoint diisi. Trace id dari header `traceparent`
+// dilanjutkan oleh middleware TracingLogger, jadi satu request bisa diikuti dari HTTP sampai SQL/Redis.
+use opentelemetry::KeyValue;
+use opentelemetry::global;
+use opentelemetry_otlp::WithExportConfig;
+use opentelemetry_sdk::propagation::TraceContextPropagator;
+use opentelemetry_sdk::trace::{self as sdktrace, Sampler};
+use opentelemetry_sdk::Resource;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{EnvFilter, Layer};
+use crate::config::TelemetryConfig;
+
+pub type TelemetryError = Box<dyn std::error::Error + Send + Sync>;
+
+// Flush span yang masih di buffer exporter saat aplikasi berhenti
+pub struct TelemetryGuard {
+    otlp_enabled: bool,
+}
+
+impl Drop for TelemetryGuard {
+    fn drop(&mut self) {
+        if self.otlp_enabled {
+            global::shutdown_tracer_provider();
+        }
+    }
+}
+
+pub fn init(config: &TelemetryConfig) -> Result<TelemetryGuard, TelemetryError> {
+    // traceparent/tracestate W3C untuk request masuk
+    global::set_text_map_propagator(TraceContextPropagator::new());
+    
+    let filter = EnvFilter::try_new(&config.log_level)?;
+    
+    let fmt_layer = match config.log_format.as_str() {
+        "json" => tracing_subscriber::fmt::layer()
+            .json()
+            .with_current_span(true)
+            .with_span_list(true)
+            .boxed(),
+        _ => tracing_subscriber::fmt::layer().boxed(),
+    };
+    
+    let otel_layer = match &config.otlp_endpoint {
+        Some(endpoint) => {
+            let tracer = opentelemetry_otlp::new_pipeline()
+                .tracing()
+                .with_exporter(opentelemetry_otlp::new_exporter().tonic().with_endpoint(endpoint))
+                .with_trace_config(
+                    sdktrace::config()
+                        // Ikuti keputusan sampling pemanggil; trace baru di-sample sesuai rasio
+                        .with_sampler(Sampler::ParentBased(Box::new(Sampler::TraceIdRatioBased(config.sample_ratio))))
+                        .with_resource(Resource::new(vec![KeyValue::new("service.name", config.service_name.clone())])),
+                )
+                .install_batch(opentelemetry_sdk::runtime::Tokio)?;
+            Some(tracing_opentelemetry::layer().with_tracer(tracer))
+        }
+        None => None,
+    };
+    
+    // `init` juga memasang bridge `log` -> `tracing`
+    tracing_subscriber::registry()
+        .with(filter)
+        .with(fmt_layer)
+        .with(otel_layer)
+        .try_init()?;
+    
+    match &config.otlp_endpoint {
+        Some(endpoint) => log::info!("Exporting traces to {} as {}", endpoint, config.service_name),
+        None => log::info!("OTLP endpoint not set, traces are only logged"),
+    }
+    
+    Ok(TelemetryGuard {
+        otlp_enabled: config.otlp_endpoint.is_some(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::{test, web, App, HttpResponse};
+    use opentelemetry::trace::{SpanId, TraceId, TracerProvider as _};
+    use opentelemetry_sdk::testing::trace::InMemorySpanExporter;
+    use sqlx::postgres::PgPoolOptions;
+    use std::sync::Arc;
+    use std::time::Duration;
+    use tracing_actix_web::TracingLogger;
+    use crate::cache::LeaderboardCache;
+    use crate::repository::LeaderboardRepository;
+    
+    const TRACEPARENT: &str = "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01";
+    
+    // Handler yang menyentuh cache & repository seperti endpoint leaderboard; error diabaikan
+    // karena yang diuji hanya span-nya (Redis dimatikan, Postgres tidak bisa dihubungi)
+    async fn leaderboard(
+        cache: web::Data<Arc<LeaderboardCache>>,
+        repo: web::Data<Arc<LeaderboardRepository>>,
+    ) -> HttpResponse {
+        let _ = cache.get_cached_leaderboard("leaderboard:daily").await;
+        let _ = repo.get_leaderboard(10, 0, "roi", "desc", "daily").await;
+        HttpResponse::Ok().finish()
+    }
+    
+    #[actix_web::test]
+    async fn traceparent_is_continued_into_request_and_child_spans() {
+        let exporter = InMemorySpanExporter::default();
+        let provider = sdktrace::TracerProvider::builder()
+            .with_simple_exporter(exporter.clone())
+            .build();
+        let subscriber = tracing_subscriber::registry()
+            .with(tracing_opentelemetry::layer().with_tracer(provider.tracer("test")));
+        let _guard = tracing::subscriber::set_default(subscriber);
+        global::set_text_map_propagator(TraceContextPropagator::new());
+        
+        let pool = PgPoolOptions::new()
+            .acquire_timeout(Duration::from_millis(200))
+            .connect_lazy("postgres://test@127.0.0.1:1/test")
+            .unwrap();
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(Arc::new(LeaderboardCache::new(None))))
+                .app_data(web::Data::new(Arc::new(LeaderboardRepository::new(pool))))
+                .wrap(TracingLogger::default())
+                .route("/api/leaderboard", web::get().to(leaderboard)),
+        )
+        .await;
+        
+        let req = test::TestRequest::get()
+            .uri("/api/leaderboard")
+            .insert_header(("traceparent", TRACEPARENT))
+            .to_request();
+        assert!(test::call_service(&app, req).await.status().is_success());
+        
+        provider.force_flush();
+        let spans = exporter.get_finished_spans().unwrap();
+        let trace_id = TraceId::from_hex("4bf92f3577b34da6a3ce929d0e0e4736").unwrap();
+        let find = |name: &str| {
+            spans
+                .iter()
+                .find(|span| span.name == name)
+                .unwrap_or_else(|| panic!("span {} was not exported", name))
+        };
+        
+        let request = spans
+            .iter()
+            .find(|span| span.parent_span_id == SpanId::from_hex("00f067aa0ba902b7").unwrap())
+            .expect("request span should continue the incoming traceparent");
+        assert_eq!(request.span_context.trace_id(), trace_id);
+        
+        for name in ["get_cached_leaderboard", "get_leaderboard"] {
+            let child = find(name);
+            assert_eq!(child.span_context.trace_id(), trace_id, "{}", name);
+            assert_eq!(child.parent_span_id, request.span_context.span_id(), "{}", name);
+        }
+    }
+}
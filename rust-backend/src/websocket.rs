@@ -1,34 +1,476 @@
 // src/websocket.rs
-use actix_ws::Session;
-use futures_util::StreamExt;
-use tokio::sync::broadcast;
-use serde_json::json;
+use std::collections::{HashMap, HashSet};
+use serde::{Deserialize, Serialize};
+use crate::models::{parse_timeframe, validate_timeframe, LeaderboardEntry, RankChange, WalletAddress};
+use crate::repository::LeaderboardRepository;
 
-pub struct LeaderboardWebSocket {
-    tx: broadcast::Sender<String>,
+// Protokol WebSocket `/api/ws` berbasis delta.
+// `seq` adalah versi leaderboard (= id snapshot), jadi klien bisa resume dari snapshot mana pun
+// yang masih tersimpan dengan `?since=<seq>`.
+
+// Ukuran window maksimum default yang bisa diikuti satu koneksi
+pub const WINDOW_SIZE: i32 = 100;
+
+// Jumlah wallet maksimum default di watchlist satu koneksi
+pub const MAX_WATCHED_WALLETS: usize = 100;
+
+// Batas per koneksi, diatur lewat bagian [websocket] di konfigurasi
+#[derive(Debug, Clone, Copy)]
+pub struct SessionLimits {
+    pub max_window: i32,
+    pub max_watched_wallets: usize,
+}
+
+// Potongan leaderboard yang diikuti satu koneksi
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct LeaderboardView {
+    pub timeframe: String,
+    pub sort_by: String,
+    pub sort_order: String,
+    pub offset: i32,
+    pub limit: i32,
 }
 
-impl LeaderboardWebSocket {
-    pub async fn handle_connection(&self, mut session: Session) {
-        let mut rx = self.tx.subscribe();
+impl LeaderboardView {
+    // Timeframe sudah divalidasi pemanggil (validate_timeframe); sort yang tidak dikenal jatuh ke default
+    pub fn new(
+        timeframe: Option<&str>,
+        sort_by: Option<&str>,
+        sort_order: Option<&str>,
+        offset: Option<i32>,
+        limit: Option<i32>,
+        max_window: i32,
+    ) -> Self {
+        let sort_by = match sort_by.unwrap_or("roi") {
+            "pnl" => "pnl",
+            "volume" => "volume",
+            _ => "roi",
+        };
+        let sort_order = if sort_order.unwrap_or("desc").eq_ignore_ascii_case("asc") { "asc" } else { "desc" };
         
-        // Kirim update setiap 10 detik
-        let update_interval = tokio::time::interval(std::time::Duration::from_secs(10));
+        Self {
+            timeframe: parse_timeframe(timeframe.unwrap_or("daily")).to_string(),
+            sort_by: sort_by.to_string(),
+            sort_order: sort_order.to_string(),
+            offset: offset.unwrap_or(0).max(0),
+            limit: limit.unwrap_or(max_window).clamp(1, max_window),
+        }
+    }
+    
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ServerMessage {
+    // Isi lengkap view pada `seq`
+    Snapshot {
+        seq: i64,
+        view: LeaderboardView,
+        data: Vec<LeaderboardEntry>,
+    },
+    // Perubahan dari `base_seq` ke `seq`; hanya valid untuk klien yang sedang di `base_seq`
+    Patch {
+        seq: i64,
+        base_seq: i64,
+        view: LeaderboardView,
+        ops: Vec<PatchOp>,
+    },
+    // Posisi terkini wallet yang di-watch
+    Watchlist {
+        seq: i64,
+        timeframe: String,
+        data: Vec<LeaderboardEntry>,
+    },
+    // Rank wallet yang di-watch berubah setelah refresh
+    RankChanged {
+        seq: i64,
+        data: RankChange,
+    },
+    Error {
+        code: String,
+        message: String,
+    },
+}
+
+impl ServerMessage {
+    pub fn error(code: &str, message: impl Into<String>) -> Self {
+        ServerMessage::Error {
+            code: code.to_string(),
+            message: message.into(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum PatchOp {
+    // Trader masuk window
+    Insert { entry: LeaderboardEntry },
+    // Trader keluar dari window
+    Remove { trader_wallet: String },
+    // Rank berubah (nilai lain bisa ikut berubah)
+    Move { from_rank: i32, entry: LeaderboardEntry },
+    // Rank sama, tapi pnl/roi/volume/nama berubah
+    Update { entry: LeaderboardEntry },
+}
+
+// Perintah dari klien, contoh:
+// {"type":"subscribe","timeframe":"weekly","sort_by":"pnl","offset":0,"limit":50,"watch":["0x..."]}
+// {"type":"unsubscribe","wallets":["0x..."]}
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ClientCommand {
+    // Ganti view yang diikuti dan/atau tambah wallet ke watchlist.
+    // Kalau hanya `watch` yang diisi, view yang sedang berjalan tidak diubah.
+    Subscribe {
+        timeframe: Option<String>,
+        sort_by: Option<String>,
+        sort_order: Option<String>,
+        offset: Option<i32>,
+        limit: Option<i32>,
+        since: Option<i64>,
+        #[serde(default)]
+        watch: Vec<String>,
+    },
+    // Tanpa `wallets`: berhenti menerima leaderboard dan kosongkan watchlist
+    Unsubscribe {
+        wallets: Option<Vec<String>>,
+    },
+}
+
+// Event yang dibroadcast lewat `ws_tx` (sebagai JSON) setiap kali leaderboard di-refresh.
+// Patch per view dan perubahan rank watchlist dihitung oleh publisher (lihat fanout.rs), bukan di sini.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum LeaderboardEvent {
+    LeaderboardUpdated {
+        version: i64,
+    },
+}
+
+// Pesan untuk membawa klien dari snapshot `base` ke `target` untuk satu view.
+// Patch kalau snapshot `base` masih ada, selain itu snapshot penuh.
+pub async fn build_update(
+    repo: &LeaderboardRepository,
+    view: &LeaderboardView,
+    base: Option<i64>,
+    target: i64,
+) -> Result<ServerMessage, sqlx::Error> {
+    let current = repo
+        .get_snapshot_window(target, &view.timeframe, &view.sort_by, &view.sort_order, view.limit, view.offset)
+        .await?;
+    
+    if let Some(base) = base.filter(|base| *base <= target) {
+        if repo.get_snapshot(base).await?.is_some() {
+            let previous = repo
+                .get_snapshot_window(base, &view.timeframe, &view.sort_by, &view.sort_order, view.limit, view.offset)
+                .await?;
+            return Ok(ServerMessage::Patch {
+                seq: target,
+                base_seq: base,
+                view: view.clone(),
+                ops: diff(&previous, &current),
+            });
+        }
+    }
+    
+    Ok(ServerMessage::Snapshot {
+        seq: target,
+        view: view.clone(),
+        data: current,
+    })
+}
+
+// Bandingkan dua window (urut rank) per wallet
+pub fn diff(previous: &[LeaderboardEntry], current: &[LeaderboardEntry]) -> Vec<PatchOp> {
+    let previous_by_wallet: HashMap<&str, &LeaderboardEntry> = previous
+        .iter()
+        .map(|entry| (entry.trader_wallet.as_str(), entry))
+        .collect();
+    let current_wallets: HashSet<&str> = current.iter().map(|entry| entry.trader_wallet.as_str()).collect();
+    
+    let mut ops: Vec<PatchOp> = previous
+        .iter()
+        .filter(|entry| !current_wallets.contains(entry.trader_wallet.as_str()))
+        .map(|entry| PatchOp::Remove { trader_wallet: entry.trader_wallet.clone() })
+        .collect();
+    
+    for entry in current {
+        match previous_by_wallet.get(entry.trader_wallet.as_str()) {
+            None => ops.push(PatchOp::Insert { entry: entry.clone() }),
+            Some(old) if old.rank != entry.rank => ops.push(PatchOp::Move {
+                from_rank: old.rank,
+                entry: entry.clone(),
+            }),
+            Some(old) if !same_values(old, entry) => ops.push(PatchOp::Update { entry: entry.clone() }),
+            Some(_) => {}
+        }
+    }
+    
+    ops
+}
+
+// previous_rank/rank_change sengaja tidak dibandingkan karena selalu relatif ke snapshot masing-masing
+fn same_values(a: &LeaderboardEntry, b: &LeaderboardEntry) -> bool {
+    a.display_name == b.display_name
+        && a.account_value == b.account_value
+        && a.pnl == b.pnl
+        && a.roi == b.roi
+        && a.volume == b.volume
+        && a.last_updated == b.last_updated
+}
+
+// State satu koneksi WebSocket: view yang diikuti, posisi seq, dan watchlist
+pub struct ClientSession {
+    view: Option<LeaderboardView>,
+    timeframe: String,
+    seq: i64,
+    watched: HashSet<String>,
+    limits: SessionLimits,
+}
+
+impl ClientSession {
+    pub fn new(view: LeaderboardView, limits: SessionLimits) -> Self {
+        Self {
+            timeframe: view.timeframe.clone(),
+            view: Some(view),
+            seq: 0,
+            watched: HashSet::new(),
+            limits,
+        }
+    }
+    
+    // Pesan pertama setelah connect: snapshot view, atau patch kalau klien resume dengan `since`
+    pub async fn start(&mut self, repo: &LeaderboardRepository, since: Option<i64>) -> Vec<ServerMessage> {
+        let Some(view) = self.view.clone() else {
+            return Vec::new();
+        };
         
-        tokio::pin!(update_interval);
+        match self.view_message(repo, &view, since).await {
+            Ok(message) => vec![message],
+            Err(e) => {
+                log::error!("Failed to build WebSocket snapshot: {:?}", e);
+                vec![ServerMessage::error("INTERNAL_ERROR", "Failed to load leaderboard")]
+            }
+        }
+    }
+    
+    pub async fn handle_text(&mut self, repo: &LeaderboardRepository, text: &str) -> Vec<ServerMessage> {
+        let command = match serde_json::from_str::<ClientCommand>(text) {
+            Ok(command) => command,
+            Err(e) => return vec![ServerMessage::error("INVALID_COMMAND", e.to_string())],
+        };
         
-        loop {
-            tokio::select! {
-                _ = update_interval.tick() => {
-                    let leaderboard_data = self.get_updated_leaderboard().await;
-                    let _ = session.text(json!(leaderboard_data).to_string()).await;
-                }
-                msg = rx.recv() => {
-                    if let Ok(msg) = msg {
-                        let _ = session.text(msg).await;
-                    }
+        let result = match command {
+            ClientCommand::Subscribe { timeframe, sort_by, sort_order, offset, limit, since, watch } => {
+                let view_requested = timeframe.is_some()
+                    || sort_by.is_some()
+                    || sort_order.is_some()
+                    || offset.is_some()
+                    || limit.is_some()
+                    || watch.is_empty();
+                let timeframe = match validate_timeframe(timeframe.as_deref()) {
+                    Ok(timeframe) => timeframe,
+                    Err(e) => return vec![ServerMessage::error(e.code(), e.public_message())],
+                };
+                let view = view_requested.then(|| {
+                    LeaderboardView::new(
+                        Some(timeframe),
+                        sort_by.as_deref(),
+                        sort_order.as_deref(),
+                        offset,
+                        limit,
+                        self.limits.max_window,
+                    )
+                });
+                self.subscribe(repo, view, since, &watch).await
+            }
+            ClientCommand::Unsubscribe { wallets: Some(wallets) } => {
+                for wallet in &wallets {
+                    let wallet = WalletAddress::parse(wallet, None)
+                        .map(|wallet| wallet.to_string())
+                        .unwrap_or_else(|_| wallet.trim().to_string());
+                    self.watched.remove(&wallet);
                 }
+                self.watchlist_message(repo).await.map(|message| vec![message])
+            }
+            ClientCommand::Unsubscribe { wallets: None } => {
+                self.view = None;
+                self.watched.clear();
+                Ok(Vec::new())
+            }
+        };
+        
+        result.unwrap_or_else(|e| {
+            log::error!("Failed to handle WebSocket command: {:?}", e);
+            vec![ServerMessage::error("INTERNAL_ERROR", "Failed to load leaderboard")]
+        })
+    }
+    
+    // View yang diikuti dan seq terakhir yang sudah dikirim; dipakai publisher untuk mengelompokkan klien
+    pub fn position(&self) -> (Option<&LeaderboardView>, i64) {
+        (self.view.as_ref(), self.seq)
+    }
+    
+    pub fn watches(&self, change: &RankChange) -> bool {
+        change.timeframe == self.timeframe && self.watched.contains(&change.trader_wallet)
+    }
+    
+    // Timeframe dan wallet yang di-watch; publisher hanya memuat perubahan rank untuk wallet ini
+    pub fn watchlist(&self) -> (&str, &HashSet<String>) {
+        (&self.timeframe, &self.watched)
+    }
+    
+    pub fn advance(&mut self, version: i64) {
+        self.seq = self.seq.max(version);
+    }
+    
+    async fn subscribe(
+        &mut self,
+        repo: &LeaderboardRepository,
+        view: Option<LeaderboardView>,
+        since: Option<i64>,
+        watch: &[String],
+    ) -> Result<Vec<ServerMessage>, sqlx::Error> {
+        let mut wallets = Vec::with_capacity(watch.len());
+        for input in watch {
+            match WalletAddress::parse(input, None) {
+                Ok(wallet) => wallets.push(wallet.to_string()),
+                Err(e) => return Ok(vec![ServerMessage::error(e.code(), e.to_string())]),
+            }
+        }
+        
+        let new_wallets = wallets.iter().filter(|wallet| !self.watched.contains(*wallet)).count();
+        if self.watched.len() + new_wallets > self.limits.max_watched_wallets {
+            return Ok(vec![ServerMessage::error(
+                "WATCHLIST_TOO_LARGE",
+                format!("at most {} wallets can be watched", self.limits.max_watched_wallets),
+            )]);
+        }
+        
+        let mut messages = Vec::new();
+        let timeframe_changed = view.as_ref().is_some_and(|view| view.timeframe != self.timeframe);
+        
+        if let Some(view) = view {
+            self.timeframe = view.timeframe.clone();
+            messages.push(self.view_message(repo, &view, since).await?);
+            self.view = Some(view);
+        }
+        
+        self.watched.extend(wallets);
+        if !watch.is_empty() || (timeframe_changed && !self.watched.is_empty()) {
+            messages.push(self.watchlist_message(repo).await?);
+        }
+        
+        Ok(messages)
+    }
+    
+    async fn view_message(
+        &mut self,
+        repo: &LeaderboardRepository,
+        view: &LeaderboardView,
+        since: Option<i64>,
+    ) -> Result<ServerMessage, sqlx::Error> {
+        match repo.get_latest_snapshot().await? {
+            Some(latest) => {
+                let message = build_update(repo, view, since, latest.id).await?;
+                self.seq = latest.id;
+                Ok(message)
+            }
+            // Belum ada snapshot sama sekali: kirim isi view apa adanya dengan seq 0
+            None => {
+                let data = repo
+                    .get_leaderboard(view.limit, view.offset, &view.sort_by, &view.sort_order, &view.timeframe)
+                    .await?;
+                Ok(ServerMessage::Snapshot { seq: 0, view: view.clone(), data })
+            }
+        }
+    }
+    
+    async fn watchlist_message(&self, repo: &LeaderboardRepository) -> Result<ServerMessage, sqlx::Error> {
+        let wallets: Vec<String> = self.watched.iter().cloned().collect();
+        let data = match repo.get_latest_snapshot().await? {
+            Some(latest) if !wallets.is_empty() => {
+                repo.get_snapshot_entries_for_wallets(latest.id, &self.timeframe, &wallets).await?
             }
+            _ => Vec::new(),
+        };
+        
+        Ok(ServerMessage::Watchlist {
+            seq: self.seq,
+            timeframe: self.timeframe.clone(),
+            data,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bigdecimal::BigDecimal;
+    use chrono::{TimeZone, Utc};
+    
+    fn entry(rank: i32, wallet: &str, roi: i64) -> LeaderboardEntry {
+        LeaderboardEntry {
+            rank,
+            trader_wallet: wallet.to_string(),
+            display_name: None,
+            account_value: BigDecimal::from(1000),
+            pnl: BigDecimal::from(roi * 10),
+            roi: BigDecimal::from(roi),
+            volume: BigDecimal::from(5000),
+            last_updated: Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap(),
+            previous_rank: None,
+            rank_change: None,
+            is_new: false,
         }
     }
-}
\ No newline at end of file
+    
+    #[test]
+    fn unchanged_window_produces_no_ops() {
+        let window = vec![entry(1, "0xa", 30), entry(2, "0xb", 20)];
+        assert!(diff(&window, &window).is_empty());
+    }
+    
+    #[test]
+    fn rank_change_columns_alone_are_not_an_update() {
+        let previous = vec![entry(1, "0xa", 30)];
+        let mut current = previous.clone();
+        current[0].previous_rank = Some(3);
+        current[0].rank_change = Some(2);
+        assert!(diff(&previous, &current).is_empty());
+    }
+    
+    #[test]
+    fn new_wallet_is_inserted_and_missing_wallet_removed() {
+        let previous = vec![entry(1, "0xa", 30), entry(2, "0xb", 20)];
+        let current = vec![entry(1, "0xa", 30), entry(2, "0xc", 25)];
+        let ops = diff(&previous, &current);
+        
+        assert_eq!(ops.len(), 2);
+        assert!(matches!(&ops[0], PatchOp::Remove { trader_wallet } if trader_wallet == "0xb"));
+        assert!(matches!(&ops[1], PatchOp::Insert { entry } if entry.trader_wallet == "0xc" && entry.rank == 2));
+    }
+    
+    #[test]
+    fn rank_change_is_a_move() {
+        let previous = vec![entry(1, "0xa", 30), entry(2, "0xb", 20)];
+        let current = vec![entry(1, "0xb", 40), entry(2, "0xa", 30)];
+        let ops = diff(&previous, &current);
+        
+        assert_eq!(ops.len(), 2);
+        assert!(matches!(&ops[0], PatchOp::Move { from_rank: 2, entry } if entry.trader_wallet == "0xb" && entry.rank == 1));
+        assert!(matches!(&ops[1], PatchOp::Move { from_rank: 1, entry } if entry.trader_wallet == "0xa" && entry.rank == 2));
+    }
+    
+    #[test]
+    fn value_change_at_same_rank_is_an_update() {
+        let previous = vec![entry(1, "0xa", 30)];
+        let mut current = vec![entry(1, "0xa", 35)];
+        current[0].display_name = Some("alice".to_string());
+        let ops = diff(&previous, &current);
+        
+        assert_eq!(ops.len(), 1);
+        assert!(matches!(&ops[0], PatchOp::Update { entry } if entry.roi == BigDecimal::from(35)));
+    }
+}